@@ -0,0 +1,386 @@
+//! AFK periods, and labeling them after the fact as offline activity
+//! (a meeting, a whiteboard session, a phone call).
+
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Utc};
+use rusqlite::Connection;
+
+use crate::config::HEARTBEAT_MERGE_GAP_SECS;
+use crate::daemon::power::{LOCKED_APP, SLEEP_APP};
+use crate::db::{categories as db_categories, events};
+use crate::error::{Result, TimelyError};
+use crate::query;
+use crate::types::{format_duration, AfkLabelResult, AfkPeriod, Event, EventSource};
+
+/// App name of labeled time spent away from the computer.
+pub const OFFLINE_APP: &str = "Offline";
+
+/// What `timely afk label` applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelTarget {
+    /// The whole AFK period containing this event
+    Period(i64),
+    /// Whatever AFK time falls in this range
+    Range(DateTime<Utc>, DateTime<Utc>),
+}
+
+/// AFK periods of `device_id` overlapping `[from, to]` that last at least `min_secs`, oldest first.
+pub fn list_periods(
+    conn: &Connection,
+    device_id: &str,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+    min_secs: f64,
+) -> Result<Vec<AfkPeriod>> {
+    Ok(group(events::query_afk_events(conn, device_id, from, to)?)
+        .iter()
+        .map(|events| period(events))
+        .filter(|p| p.seconds >= min_secs)
+        .collect())
+}
+
+/// Consecutive AFK events (no more than the merge gap apart) belong to one period.
+fn group(afk_events: Vec<Event>) -> Vec<Vec<Event>> {
+    let mut groups: Vec<Vec<Event>> = Vec::new();
+    for event in afk_events {
+        let continues = groups
+            .last()
+            .and_then(|g| g.last())
+            .is_some_and(|last| seconds_between(&end_of(last), &event.timestamp) <= HEARTBEAT_MERGE_GAP_SECS);
+        match groups.last_mut() {
+            Some(group) if continues => group.push(event),
+            _ => groups.push(vec![event]),
+        }
+    }
+    groups
+}
+
+fn period(events: &[Event]) -> AfkPeriod {
+    let from = events[0].timestamp;
+    let to = events.iter().map(end_of).max().unwrap_or(from);
+    let seconds = seconds_between(&from, &to);
+    let reason = match events[0].app.as_str() {
+        SLEEP_APP => "sleep",
+        LOCKED_APP => "locked",
+        _ => "idle",
+    };
+    AfkPeriod {
+        id: events[0].id,
+        event_ids: events.iter().map(|e| e.id).collect(),
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        seconds,
+        time: format_duration(seconds),
+        reason: reason.to_string(),
+    }
+}
+
+/// Turn the AFK time `target` covers into non-AFK `category` activity, titled
+/// with `note`. AFK events reaching past a range are split at its edges.
+pub fn label(
+    conn: &Connection,
+    device_id: &str,
+    target: &LabelTarget,
+    category: &str,
+    note: Option<&str>,
+) -> Result<AfkLabelResult> {
+    let tx = conn.unchecked_transaction()?;
+    let category_id = db_categories::get_or_create_category(&tx, category)?.id;
+
+    let pieces: Vec<(Event, DateTime<Utc>, DateTime<Utc>)> = match target {
+        LabelTarget::Period(id) => {
+            let event = events::get_event(&tx, *id)?
+                .filter(|e| e.is_afk && e.device_id == device_id)
+                .ok_or_else(|| TimelyError::Generic(format!("No AFK event with id {}", id)))?;
+            let around = chrono::Duration::days(1);
+            let nearby = events::query_afk_events(&tx, device_id, &(event.timestamp - around), &(end_of(&event) + around))?;
+            group(nearby)
+                .into_iter()
+                .find(|g| g.iter().any(|e| e.id == *id))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| {
+                    let (start, end) = (e.timestamp, end_of(&e));
+                    (e, start, end)
+                })
+                .collect()
+        }
+        LabelTarget::Range(from, to) => events::query_afk_events(&tx, device_id, from, to)?
+            .into_iter()
+            .map(|e| {
+                let (start, end) = (e.timestamp.max(*from), end_of(&e).min(*to));
+                (e, start, end)
+            })
+            .filter(|(_, start, end)| end > start)
+            .collect(),
+    };
+    if pieces.is_empty() {
+        return Err(TimelyError::Generic("No AFK time to label there".into()));
+    }
+
+    let title = note.unwrap_or("");
+    let mut seconds = 0.0;
+    for (event, start, end) in &pieces {
+        if *start > event.timestamp {
+            // Keep the AFK part before the range, label the rest as a new event
+            events::extend_event(&tx, event.id, seconds_between(&event.timestamp, start))?;
+            events::insert_full_event(&tx, &offline_piece(event, start, end, title, category_id))?;
+        } else {
+            events::set_offline_activity(&tx, event.id, OFFLINE_APP, title, category_id)?;
+            events::extend_event(&tx, event.id, seconds_between(start, end))?;
+        }
+        if *end < end_of(event) {
+            // The AFK part after the range stays AFK
+            events::insert_full_event(
+                &tx,
+                &Event {
+                    timestamp: *end,
+                    duration: seconds_between(end, &end_of(event)),
+                    ..event.clone()
+                },
+            )?;
+        }
+        seconds += seconds_between(start, end);
+    }
+    tx.commit()?;
+
+    Ok(AfkLabelResult {
+        category: category.to_string(),
+        note: note.map(String::from),
+        events: pieces.len(),
+        seconds,
+        time: format_duration(seconds),
+    })
+}
+
+/// Parse a label target: an AFK event id (`4217`), a range of two times
+/// separated by `..` (`14:00..14:40`, `2h..1h`, RFC 3339), or `HH:MM-HH:MM`.
+/// Times of day are today's.
+pub fn parse_target(spec: &str, now: &DateTime<Local>) -> Result<LabelTarget> {
+    let spec = spec.trim();
+    if let Ok(id) = spec.parse::<i64>() {
+        return Ok(LabelTarget::Period(id));
+    }
+
+    let (from, to) = match spec.split_once("..") {
+        Some((from, to)) => (parse_point(from, now)?, parse_point(to, now)?),
+        None => {
+            let invalid = || {
+                TimelyError::InvalidTimeRange(format!(
+                    "Cannot parse '{}'. Use an AFK id from `timely afk list`, 14:00..14:40, or 14:00-14:40",
+                    spec
+                ))
+            };
+            let (from, to) = spec.split_once('-').ok_or_else(invalid)?;
+            let from = time_today(from, now).ok_or_else(invalid)?;
+            let to = time_today(to, now).ok_or_else(invalid)?;
+            (from, to)
+        }
+    };
+    if to <= from {
+        return Err(TimelyError::InvalidTimeRange(format!("'{}' ends before it starts", spec)));
+    }
+    Ok(LabelTarget::Range(from, to))
+}
+
+fn parse_point(input: &str, now: &DateTime<Local>) -> Result<DateTime<Utc>> {
+    match time_today(input, now) {
+        Some(at) => Ok(at),
+        None => query::parse_time(input),
+    }
+}
+
+fn time_today(input: &str, now: &DateTime<Local>) -> Option<DateTime<Utc>> {
+    let time = NaiveTime::parse_from_str(input.trim(), "%H:%M").ok()?;
+    let local = Local.from_local_datetime(&now.date_naive().and_time(time)).earliest()?;
+    Some(local.with_timezone(&Utc))
+}
+
+/// The labeled part of an AFK event, as a new event of its own.
+fn offline_piece(event: &Event, start: &DateTime<Utc>, end: &DateTime<Utc>, title: &str, category_id: i64) -> Event {
+    Event {
+        id: 0,
+        device_id: event.device_id.clone(),
+        timestamp: *start,
+        duration: seconds_between(start, end),
+        app: OFFLINE_APP.to_string(),
+        title: title.to_string(),
+        raw_title: None,
+        app_id: None,
+        app_path: None,
+        url: None,
+        url_domain: None,
+        category_id: Some(category_id),
+        category_name: None,
+        is_afk: false,
+        is_idle: false,
+        project: None,
+        file: None,
+        language: None,
+        branch: None,
+        command: None,
+        cwd: None,
+        exit_status: None,
+        source: EventSource::Label,
+    }
+}
+
+fn end_of(event: &Event) -> DateTime<Utc> {
+    event.timestamp + chrono::Duration::milliseconds((event.duration * 1000.0) as i64)
+}
+
+fn seconds_between(start: &DateTime<Utc>, end: &DateTime<Utc>) -> f64 {
+    (*end - *start).num_milliseconds() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::types::WatcherSnapshot;
+
+    fn setup_db() -> (tempfile::NamedTempFile, Connection) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = db::open_db(tmp.path()).unwrap();
+        db_categories::seed_builtin_categories(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO devices (id, name, platform, last_sync) VALUES ('dev', 'test', 'linux', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        (tmp, conn)
+    }
+
+    fn record(conn: &Connection, app: &str, is_afk: bool, at: DateTime<Utc>, secs: f64) -> i64 {
+        let snapshot = WatcherSnapshot {
+            app: app.to_string(),
+            title: "window".to_string(),
+            is_afk,
+            ..Default::default()
+        };
+        crate::daemon::heartbeat::process_heartbeat_at(conn, "dev", &snapshot, &at, secs, 0.0)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn stored(conn: &Connection) -> Vec<(String, bool, f64)> {
+        let from = "2026-03-10T00:00:00Z".parse().unwrap();
+        let to = "2026-03-11T00:00:00Z".parse().unwrap();
+        let mut stored = events::query_events(conn, &from, &to, None).unwrap();
+        stored.sort_by_key(|e| e.timestamp);
+        stored.into_iter().map(|e| (e.app, e.is_afk, e.duration)).collect()
+    }
+
+    #[test]
+    fn test_list_groups_consecutive_afk_events() {
+        let (_tmp, conn) = setup_db();
+        let at = |m: i64| "2026-03-10T09:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::Duration::minutes(m);
+        record(&conn, "Code", false, at(0), 600.0);
+        let idle = record(&conn, "Code", true, at(10), 300.0);
+        record(&conn, LOCKED_APP, true, at(15), 900.0);
+        record(&conn, "Code", false, at(30), 600.0);
+        record(&conn, "Code", true, at(40), 60.0);
+
+        let periods = list_periods(&conn, "dev", &at(0), &at(60), 0.0).unwrap();
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].id, idle);
+        assert_eq!(periods[0].event_ids.len(), 2);
+        assert_eq!(periods[0].seconds, 1200.0);
+        assert_eq!(periods[0].reason, "idle");
+        assert_eq!(list_periods(&conn, "dev", &at(0), &at(60), 120.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_label_period() {
+        let (_tmp, conn) = setup_db();
+        let at = |m: i64| "2026-03-10T09:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::Duration::minutes(m);
+        let idle = record(&conn, "Code", true, at(0), 300.0);
+        record(&conn, SLEEP_APP, true, at(5), 600.0);
+
+        let result = label(&conn, "dev", &LabelTarget::Period(idle), "meetings", Some("standup")).unwrap();
+        assert_eq!(result.events, 2);
+        assert_eq!(result.seconds, 900.0);
+        assert!(list_periods(&conn, "dev", &at(0), &at(60), 0.0).unwrap().is_empty());
+
+        let event = events::get_event(&conn, idle).unwrap().unwrap();
+        assert_eq!(event.app, OFFLINE_APP);
+        assert_eq!(event.title, "standup");
+        assert_eq!(event.category_name.as_deref(), Some("meetings"));
+
+        // Only AFK events can be labeled
+        assert!(label(&conn, "dev", &LabelTarget::Period(idle), "meetings", None).is_err());
+    }
+
+    #[test]
+    fn test_labels_survive_reclassification() {
+        let (_tmp, conn) = setup_db();
+        let at = |m: i64| "2026-03-10T09:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::Duration::minutes(m);
+        let idle = record(&conn, "Code", true, at(0), 1800.0);
+        label(&conn, "dev", &LabelTarget::Range(at(10), at(20)), "meetings", None).unwrap();
+        label(&conn, "dev", &LabelTarget::Period(idle), "meetings", None).unwrap();
+
+        // What `timely categorize apply` does
+        let rules = crate::categories::rule_set(&conn).unwrap();
+        crate::categories::reclassify_events(&conn, &rules, None).unwrap();
+        let from = at(0);
+        let to = at(60);
+        let stored = events::query_events(&conn, &from, &to, None).unwrap();
+        let labeled: Vec<_> = stored.iter().filter(|e| !e.is_afk).collect();
+        assert_eq!(labeled.len(), 2);
+        assert!(labeled.iter().all(|e| e.category_name.as_deref() == Some("meetings")));
+    }
+
+    #[test]
+    fn test_label_range_splits_at_edges() {
+        let (_tmp, conn) = setup_db();
+        let at = |m: i64| "2026-03-10T09:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::Duration::minutes(m);
+        record(&conn, "Code", false, at(0), 600.0);
+        record(&conn, "Code", true, at(10), 3600.0);
+
+        let result = label(&conn, "dev", &LabelTarget::Range(at(20), at(50)), "meetings", None).unwrap();
+        assert_eq!(result.seconds, 1800.0);
+        assert_eq!(
+            stored(&conn),
+            vec![
+                ("Code".to_string(), false, 600.0),
+                ("Code".to_string(), true, 600.0),
+                (OFFLINE_APP.to_string(), false, 1800.0),
+                ("Code".to_string(), true, 1200.0),
+            ]
+        );
+
+        // Nothing left there to label
+        assert!(label(&conn, "dev", &LabelTarget::Range(at(20), at(50)), "meetings", None).is_err());
+    }
+
+    #[test]
+    fn test_split_pieces_are_not_the_last_event() {
+        let (_tmp, conn) = setup_db();
+        let at = |m: i64| "2026-03-10T09:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::Duration::minutes(m);
+        record(&conn, "Code", true, at(0), 3600.0);
+        record(&conn, "Firefox", false, at(60), 60.0);
+
+        // Labeling inserts the labeled and trailing pieces as newer rows
+        label(&conn, "dev", &LabelTarget::Range(at(10), at(20)), "meetings", None).unwrap();
+        let last = events::get_last_event(&conn, "dev").unwrap().unwrap();
+        assert_eq!(last.app, "Firefox");
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let now = Local.with_ymd_and_hms(2026, 3, 10, 15, 0, 0).unwrap();
+        let at = |h, m| Local.with_ymd_and_hms(2026, 3, 10, h, m, 0).unwrap().with_timezone(&Utc);
+
+        assert_eq!(parse_target("4217", &now).unwrap(), LabelTarget::Period(4217));
+        assert_eq!(parse_target("14:00..14:40", &now).unwrap(), LabelTarget::Range(at(14, 0), at(14, 40)));
+        assert_eq!(parse_target("14:00-14:40", &now).unwrap(), LabelTarget::Range(at(14, 0), at(14, 40)));
+        assert_eq!(
+            parse_target("2026-03-10T09:00:00Z..2026-03-10T10:00:00Z", &now).unwrap(),
+            LabelTarget::Range(
+                "2026-03-10T09:00:00Z".parse().unwrap(),
+                "2026-03-10T10:00:00Z".parse().unwrap()
+            )
+        );
+        assert!(parse_target("14:40..14:00", &now).is_err());
+        assert!(parse_target("lunch", &now).is_err());
+    }
+}
@@ -28,10 +28,15 @@ pub const BUILTIN_CATEGORIES: &[(&str, Option<&str>, f64)] = &[
     ("reference/search", Some("reference"), 0.5),
 ];
 
+/// (alias, canonical app name): names one program is reported under
+pub const BUILTIN_APP_ALIASES: &[(&str, &str)] = &[
+    ("Code", "Visual Studio Code"),
+    ("code-oss", "Visual Studio Code"),
+];
+
 /// (category_name, field, pattern, priority)
 pub const BUILTIN_RULES: &[(&str, &str, &str, i32)] = &[
     // IDEs & Editors
-    ("work/coding", "app", "Code", 100),
     ("work/coding", "app", "Visual Studio Code", 100),
     ("work/coding", "app", "Cursor", 100),
     ("work/coding", "app", "IntelliJ IDEA", 100),
@@ -63,6 +68,17 @@ pub const BUILTIN_RULES: &[(&str, &str, &str, i32)] = &[
     ("work/terminal", "app", "Warp", 90),
     ("work/terminal", "app", "WezTerm", 90),
     ("work/terminal", "app", "Ghostty", 90),
+    ("work/terminal", "app", "XTerm", 90),
+    ("work/terminal", "app", "Gnome-terminal", 90),
+    ("work/terminal", "app", "org.gnome.Terminal", 90),
+    ("work/terminal", "app", "konsole", 90),
+    ("work/terminal", "app", "foot", 90),
+    ("work/terminal", "app", "Tilix", 90),
+    ("work/terminal", "app", "Terminator", 90),
+    ("work/terminal", "app", "Xfce4-terminal", 90),
+    ("work/terminal", "app", "org.gnome.Ptyxis", 90),
+    ("work/terminal", "app", "org.wezfurlong.wezterm", 90),
+    ("work/terminal", "app", "com.mitchellh.ghostty", 90),
     // Writing
     ("work/writing", "app", "Notion", 80),
     ("work/writing", "app", "Obsidian", 80),
@@ -1,47 +1,321 @@
 pub mod builtin;
 
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex::Regex;
+use rusqlite::Connection;
+
+use crate::db::{apps as db_apps, categories as db_categories};
+use crate::error::Result;
 use crate::types::{CategoryRule, WatcherSnapshot};
 
-pub fn classify(snapshot: &WatcherSnapshot, rules: &[CategoryRule]) -> Option<i64> {
-    // Rules are expected to be sorted by priority DESC already
-    for rule in rules {
-        let value = match rule.field.as_str() {
-            "app" => &snapshot.app,
-            "title" => &snapshot.title,
-            "url_domain" => {
-                if let Some(ref d) = snapshot.url_domain {
-                    d
-                } else {
-                    continue;
+/// Patterns starting with this are regular expressions (case-insensitive),
+/// e.g. `re:^JIRA-\d+`. Other patterns containing `*` or `?` are globs, and
+/// the rest match exactly (case-insensitive).
+pub const REGEX_PREFIX: &str = "re:";
+
+/// Rule fields, in the order `RuleSet` keeps them.
+const FIELDS: [&str; 3] = ["app", "title", "url_domain"];
+const APP: usize = 0;
+
+enum Matcher {
+    Glob(glob::Pattern),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Matcher::Glob(pattern) => pattern.matches(value),
+            Matcher::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// One field's rules, each tagged with its rank (position in priority order).
+#[derive(Default)]
+struct FieldRules {
+    /// Lowercased exact patterns
+    exact: HashMap<String, usize>,
+    /// Globs and regexes, best rank first
+    patterns: Vec<(usize, Matcher)>,
+}
+
+/// Category rules compiled for matching: exact patterns are hash lookups, and
+/// globs and regexes are built once. The highest-priority matching rule wins,
+/// as if the rules were tried one by one in priority order. App rules match
+/// every name of an app: a rule for "Code" also matches its alias "VS Code".
+pub struct RuleSet {
+    /// The database's rules version this set was built from
+    version: i64,
+    /// Category of each rule, by rank
+    categories: Vec<i64>,
+    fields: [FieldRules; 3],
+    /// Lowercased app alias → lowercased canonical name
+    aliases: HashMap<String, String>,
+    uncategorized: Option<i64>,
+}
+
+impl RuleSet {
+    /// Compile `rules`, which must be sorted by priority, highest first.
+    pub fn new(rules: &[CategoryRule]) -> Self {
+        Self::with_aliases(rules, HashMap::new())
+    }
+
+    /// `new`, matching app rules through `aliases` (lowercased alias → lowercased canonical name).
+    pub fn with_aliases(rules: &[CategoryRule], aliases: HashMap<String, String>) -> Self {
+        let mut set = Self {
+            version: 0,
+            categories: Vec::with_capacity(rules.len()),
+            fields: Default::default(),
+            aliases,
+            uncategorized: None,
+        };
+        for rule in rules {
+            let Some(field) = FIELDS.iter().position(|f| *f == rule.field) else {
+                continue;
+            };
+            let Some(matcher) = compile(&rule.pattern) else {
+                continue;
+            };
+            let rank = set.categories.len();
+            set.categories.push(rule.category_id);
+            let canonical = (field == APP).then(|| set.aliases.get(&rule.pattern.to_lowercase()).cloned()).flatten();
+            let field = &mut set.fields[field];
+            match matcher {
+                Some(matcher) => field.patterns.push((rank, matcher)),
+                None => {
+                    field.exact.entry(rule.pattern.to_lowercase()).or_insert(rank);
+                    if let Some(canonical) = canonical {
+                        field.exact.entry(canonical).or_insert(rank);
+                    }
                 }
             }
-            _ => continue,
-        };
+        }
+        set
+    }
+
+    /// Compile the rules stored in the database, with its app aliases.
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let version = db_categories::rules_version(conn)?;
+        let mut set = Self::with_aliases(&db_categories::list_rules(conn)?, db_apps::alias_map(conn)?);
+        set.version = version;
+        set.uncategorized = db_categories::get_category_by_name(conn, "uncategorized")?.map(|c| c.id);
+        Ok(set)
+    }
+
+    /// A set holding just one pattern, to test which events a rule covers.
+    pub fn for_pattern(field: &str, pattern: &str) -> Self {
+        Self::new(&[CategoryRule {
+            id: 0,
+            category_id: 0,
+            category_name: None,
+            field: field.to_string(),
+            pattern: pattern.to_string(),
+            is_builtin: false,
+            priority: 0,
+        }])
+    }
+
+    pub fn version(&self) -> i64 {
+        self.version
+    }
 
-        if matches_pattern(value, &rule.pattern) {
-            return Some(rule.category_id);
+    pub fn classify(&self, snapshot: &WatcherSnapshot) -> Option<i64> {
+        let values = [Some(&snapshot.app), Some(&snapshot.title), snapshot.url_domain.as_ref()];
+        let mut best: Option<usize> = None;
+        for (i, (field, value)) in self.fields.iter().zip(values).enumerate() {
+            let Some(value) = value else { continue };
+            if field.exact.is_empty() && field.patterns.is_empty() {
+                continue;
+            }
+            let value = value.to_lowercase();
+            // An app is matched by its own name and its canonical name
+            let canonical = if i == APP { self.aliases.get(&value) } else { None };
+            let names = || std::iter::once(&value).chain(canonical);
+            for name in names() {
+                if let Some(&rank) = field.exact.get(name) {
+                    best = Some(best.map_or(rank, |b| b.min(rank)));
+                }
+            }
+            for (rank, matcher) in &field.patterns {
+                if best.is_some_and(|b| *rank >= b) {
+                    break;
+                }
+                if names().any(|name| matcher.matches(name)) {
+                    best = Some(*rank);
+                    break;
+                }
+            }
         }
+        best.map(|rank| self.categories[rank])
     }
-    None
-}
 
-fn matches_pattern(value: &str, pattern: &str) -> bool {
-    // Case-insensitive exact match or glob match
-    let value_lower = value.to_lowercase();
-    let pattern_lower = pattern.to_lowercase();
+    /// `classify`, falling back to the "uncategorized" category.
+    pub fn classify_or_uncategorized(&self, snapshot: &WatcherSnapshot) -> Option<i64> {
+        self.classify(snapshot).or(self.uncategorized)
+    }
+}
 
+/// None for an invalid pattern; Some(None) for an exact match.
+fn compile(pattern: &str) -> Option<Option<Matcher>> {
+    if let Some(regex) = pattern.strip_prefix(REGEX_PREFIX) {
+        return Regex::new(&format!("(?i){}", regex)).ok().map(|r| Some(Matcher::Regex(r)));
+    }
     if pattern.contains('*') || pattern.contains('?') {
-        glob_match(&value_lower, &pattern_lower)
-    } else {
-        // Exact match (case-insensitive)
-        value_lower == pattern_lower
+        return glob::Pattern::new(&pattern.to_lowercase()).ok().map(|g| Some(Matcher::Glob(g)));
+    }
+    Some(None)
+}
+
+/// The compiled rules of `conn`'s database, shared across connections to it
+/// (the daemon, web handlers, CLI) and rebuilt only when its rules version changes.
+pub fn rule_set(conn: &Connection) -> Result<Arc<RuleSet>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<RuleSet>>>> = OnceLock::new();
+
+    let Some(path) = conn.path().filter(|p| !p.is_empty()) else {
+        // In-memory database: nothing to share with
+        return Ok(Arc::new(RuleSet::load(conn)?));
+    };
+    let version = db_categories::rules_version(conn)?;
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(rules) = lock(cache).get(path).filter(|r| r.version == version) {
+        return Ok(rules.clone());
+    }
+
+    let rules = Arc::new(RuleSet::load(conn)?);
+    lock(cache).insert(path.to_string(), rules.clone());
+    Ok(rules)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn classify(snapshot: &WatcherSnapshot, rules: &[CategoryRule]) -> Option<i64> {
+    RuleSet::new(rules).classify(snapshot)
+}
+
+/// Reapply `rules` to stored non-AFK events, limited to those `only` matches
+/// when given (e.g. the events a changed rule covers). AFK time labeled by
+/// hand keeps its category. Returns how many events changed category.
+pub fn reclassify_events(conn: &Connection, rules: &RuleSet, only: Option<&RuleSet>) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut updates = Vec::new();
+    {
+        let mut stmt = tx.prepare(
+            "SELECT id, app, title, url_domain, category_id FROM events WHERE is_afk = 0 AND source != 'label'",
+        )?;
+        let mut rows = stmt.query([])?;
+        // Many events share an app/title/domain; classify each combination once
+        let mut seen: HashMap<(String, String, Option<String>), Option<Option<i64>>> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let key = (row.get(1)?, row.get(2)?, row.get(3)?);
+            let current: Option<i64> = row.get(4)?;
+            let category = seen
+                .entry(key)
+                .or_insert_with_key(|(app, title, url_domain)| {
+                    let snapshot = WatcherSnapshot {
+                        app: app.clone(),
+                        title: title.clone(),
+                        url_domain: url_domain.clone(),
+                        ..Default::default()
+                    };
+                    let covered = only.is_none_or(|only| only.classify(&snapshot).is_some());
+                    covered.then(|| rules.classify_or_uncategorized(&snapshot))
+                });
+            if let Some(category) = *category {
+                if category != current {
+                    updates.push((id, category));
+                }
+            }
+        }
+    }
+
+    let mut stmt = tx.prepare("UPDATE events SET category_id = ?1 WHERE id = ?2")?;
+    for (id, category) in &updates {
+        stmt.execute(rusqlite::params![category, id])?;
     }
+    drop(stmt);
+    tx.commit()?;
+    Ok(updates.len())
 }
 
-fn glob_match(value: &str, pattern: &str) -> bool {
-    let glob_pattern = glob::Pattern::new(pattern);
-    match glob_pattern {
-        Ok(p) => p.matches(value),
-        Err(_) => false,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(category_id: i64, field: &str, pattern: &str) -> CategoryRule {
+        CategoryRule {
+            id: category_id,
+            category_id,
+            category_name: None,
+            field: field.to_string(),
+            pattern: pattern.to_string(),
+            is_builtin: false,
+            priority: 0,
+        }
+    }
+
+    fn window(app: &str, title: &str, url_domain: Option<&str>) -> WatcherSnapshot {
+        WatcherSnapshot {
+            app: app.to_string(),
+            title: title.to_string(),
+            url_domain: url_domain.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_priority_order_across_kinds() {
+        let rules = RuleSet::new(&[
+            rule(1, "title", "*secret*"),
+            rule(2, "url_domain", "github.com"),
+            rule(3, "app", "firefox"),
+            rule(4, "title", r"re:^PR #\d+"),
+            rule(5, "app", "Firefox"),
+        ]);
+        // An exact match loses to a higher-priority glob on another field
+        assert_eq!(rules.classify(&window("Firefox", "My Secret Plan", Some("github.com"))), Some(1));
+        assert_eq!(rules.classify(&window("Firefox", "Issues", Some("github.com"))), Some(2));
+        // Duplicate exact patterns: the first (highest priority) wins
+        assert_eq!(rules.classify(&window("FIREFOX", "Inbox", None)), Some(3));
+        assert_eq!(rules.classify(&window("Slack", "pr #42 review", None)), Some(4));
+        assert_eq!(rules.classify(&window("Slack", "general", None)), None);
+    }
+
+    #[test]
+    fn test_app_rules_match_aliases() {
+        let aliases = HashMap::from([
+            ("code".to_string(), "vs code".to_string()),
+            ("visual studio code".to_string(), "vs code".to_string()),
+        ]);
+        let rules = RuleSet::with_aliases(&[rule(1, "app", "Code"), rule(2, "app", "*studio*")], aliases);
+        assert_eq!(rules.classify(&window("Visual Studio Code", "main.rs", None)), Some(1));
+        assert_eq!(rules.classify(&window("VS Code", "main.rs", None)), Some(1));
+        assert_eq!(rules.classify(&window("Android Studio", "main.kt", None)), Some(2));
+    }
+
+    #[test]
+    fn test_invalid_patterns_are_skipped() {
+        let rules = RuleSet::new(&[rule(1, "title", "re:(unclosed"), rule(2, "title", "[*")]);
+        assert_eq!(rules.classify(&window("Code", "(unclosed", None)), None);
+    }
+
+    #[test]
+    fn test_rule_set_invalidated_by_version() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+        db_categories::seed_builtin_categories(&conn).unwrap();
+        let before = rule_set(&conn).unwrap();
+        assert!(Arc::ptr_eq(&before, &rule_set(&conn).unwrap()));
+
+        let cat = db_categories::get_category_by_name(&conn, "work/coding").unwrap().unwrap();
+        db_categories::insert_rule(&conn, cat.id, "app", "Zed", false, 200).unwrap();
+        let after = rule_set(&conn).unwrap();
+        assert!(after.version() > before.version());
+        assert_eq!(after.classify(&window("Zed", "main.rs", None)), Some(cat.id));
     }
 }
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Local};
+
+use crate::afk;
+use crate::db;
+use crate::db::devices;
+use crate::error::Result;
+use crate::output;
+use crate::query;
+
+pub fn cmd_list(from: &str, to: &str, min_secs: f64, json: bool) -> Result<()> {
+    let from_dt = query::parse_time(from)?;
+    let to_dt = query::parse_time(to)?;
+
+    let conn = db::open_default_db()?;
+    let device = devices::get_or_create_device(&conn)?;
+    let periods = afk::list_periods(&conn, &device.id, &from_dt, &to_dt, min_secs)?;
+
+    if json {
+        output::print_json(&periods);
+    } else if periods.is_empty() {
+        println!("No AFK periods ({} to {})", from, to);
+    } else {
+        println!("{:<10} {:<8} {:<8} {:<10} Reason", "ID", "From", "To", "Time");
+        println!("{:-<48}", "");
+        for p in &periods {
+            println!(
+                "{:<10} {:<8} {:<8} {:<10} {}",
+                p.id,
+                local_time(&p.from),
+                local_time(&p.to),
+                p.time,
+                p.reason
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn cmd_label(target: &str, category: &str, note: Option<&str>, json: bool) -> Result<()> {
+    let target = afk::parse_target(target, &Local::now())?;
+
+    let conn = db::open_default_db()?;
+    let device = devices::get_or_create_device(&conn)?;
+    let result = afk::label(&conn, &device.id, &target, category, note)?;
+
+    if json {
+        output::print_json(&result);
+    } else {
+        println!("Labeled {} away as '{}'", result.time, result.category);
+    }
+    Ok(())
+}
+
+fn local_time(rfc3339: &str) -> String {
+    DateTime::parse_from_rfc3339(rfc3339)
+        .map(|t| t.with_timezone(&Local).format("%H:%M").to_string())
+        .unwrap_or_else(|_| rfc3339.to_string())
+}
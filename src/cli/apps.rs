@@ -0,0 +1,107 @@
+use crate::categories::{self, RuleSet};
+use crate::db;
+use crate::db::{apps, categories as db_categories};
+use crate::error::Result;
+use crate::output;
+
+pub fn cmd_alias(alias: &str, canonical: &str, retroactive: bool, json: bool) -> Result<()> {
+    let conn = db::open_default_db()?;
+    let alias = apps::set_alias(&conn, alias, canonical)?;
+    let retroactive_count = if retroactive { reclassify_app(&conn, &alias.alias)? } else { 0 };
+
+    if json {
+        output::print_json(&serde_json::json!({
+            "alias": alias.alias,
+            "canonical": alias.canonical,
+            "retroactive_updates": retroactive_count,
+        }));
+    } else {
+        println!("Alias added: '{}' -> '{}'", alias.alias, alias.canonical);
+        if retroactive {
+            println!("Retroactively updated {} events", retroactive_count);
+        }
+    }
+    Ok(())
+}
+
+pub fn cmd_unalias(alias: &str, retroactive: bool, json: bool) -> Result<()> {
+    let conn = db::open_default_db()?;
+    let removed = apps::remove_alias(&conn, alias)?;
+    let retroactive_count = if removed && retroactive { reclassify_app(&conn, alias)? } else { 0 };
+
+    if json {
+        output::print_json(&serde_json::json!({
+            "alias": alias,
+            "removed": removed,
+            "retroactive_updates": retroactive_count,
+        }));
+    } else if removed {
+        println!("Alias removed: '{}'", alias);
+        if retroactive {
+            println!("Retroactively updated {} events", retroactive_count);
+        }
+    } else {
+        println!("No alias '{}'", alias);
+    }
+    Ok(())
+}
+
+/// Reapply the rules to the events recorded under the app name `app`.
+fn reclassify_app(conn: &rusqlite::Connection, app: &str) -> Result<usize> {
+    db_categories::seed_builtin_categories(conn)?;
+    let rules = categories::rule_set(conn)?;
+    let covered = RuleSet::for_pattern("app", app);
+    categories::reclassify_events(conn, &rules, Some(&covered))
+}
+
+pub fn cmd_list(json: bool) -> Result<()> {
+    let conn = db::open_default_db()?;
+    let apps = apps::list_apps(&conn)?;
+    let aliases = apps::list_aliases(&conn)?;
+
+    if json {
+        output::print_json(&serde_json::json!({
+            "apps": apps,
+            "aliases": aliases,
+        }));
+        return Ok(());
+    }
+
+    if apps.is_empty() {
+        println!("No apps recorded yet.");
+    } else {
+        println!("{:<24} {:<24} {:<32} Path", "App", "Grouped as", "Id");
+        println!("{:-<100}", "");
+        for app in &apps {
+            println!(
+                "{:<24} {:<24} {:<32} {}",
+                truncate(&app.app, 24),
+                truncate(&app.canonical, 24),
+                truncate(app.app_id.as_deref().unwrap_or("-"), 32),
+                app.app_path.as_deref().unwrap_or("-"),
+            );
+        }
+    }
+
+    // Aliases set up ahead of any events under that name
+    let unseen: Vec<_> = aliases
+        .iter()
+        .filter(|a| !apps.iter().any(|app| app.app.eq_ignore_ascii_case(&a.alias)))
+        .collect();
+    if !unseen.is_empty() {
+        println!();
+        println!("Aliases not seen yet:");
+        for alias in unseen {
+            println!("  {} -> {}", alias.alias, alias.canonical);
+        }
+    }
+    Ok(())
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() > max {
+        format!("{}…", s.chars().take(max - 1).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
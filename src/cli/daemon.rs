@@ -1,7 +1,12 @@
 use crate::config;
+use crate::daemon::control::{self, Request};
 use crate::error::{Result, TimelyError};
 use crate::output;
 use crate::types::DaemonStatus;
+#[cfg(target_os = "linux")]
+use crate::daemon::systemd::Systemctl;
+#[cfg(target_os = "linux")]
+use crate::types::ServiceStatus;
 
 pub fn cmd_start(json: bool) -> Result<()> {
     // Check if already running
@@ -68,42 +73,54 @@ pub fn cmd_start(json: bool) -> Result<()> {
             return Err(TimelyError::Generic("Failed to load launchd plist".into()));
         }
 
-        // Non-blocking update check (never fail the command)
-        let update_version = crate::cli::update::check_for_update()
-            .ok()
-            .filter(|c| c.update_available)
-            .map(|c| c.latest_version);
+        report_started("launchd", json);
+        Ok(())
+    }
 
-        if json {
-            let mut data = serde_json::json!({
-                "started": true,
-                "method": "launchd",
-            });
-            if let Some(ref ver) = update_version {
-                data["update_available"] = serde_json::json!(ver);
-            }
-            output::print_json(&data);
-        } else {
-            println!("Daemon started via launchd");
-            if let Some(ver) = update_version {
-                eprintln!(
-                    "Note: timely {} is available (you have {}). Run `timely update` to upgrade.",
-                    ver,
-                    env!("CARGO_PKG_VERSION")
-                );
-            }
-        }
+    #[cfg(target_os = "linux")]
+    {
+        let exe = std::env::current_exe()?.canonicalize()?;
+        Systemctl::default().start(&config::systemd_unit_path()?, &exe)?;
+        report_started("systemd", json);
+        Ok(())
     }
 
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
         let _ = json;
-        return Err(TimelyError::PlatformNotSupported(
-            "daemon start only supported on macOS (launchd)".into(),
-        ));
+        Err(TimelyError::PlatformNotSupported(
+            "daemon start only supported on macOS (launchd) and Linux (systemd)".into(),
+        ))
     }
+}
 
-    Ok(())
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn report_started(method: &str, json: bool) {
+    // Non-blocking update check (never fail the command)
+    let update_version = crate::cli::update::check_for_update()
+        .ok()
+        .filter(|c| c.update_available)
+        .map(|c| c.latest_version);
+
+    if json {
+        let mut data = serde_json::json!({
+            "started": true,
+            "method": method,
+        });
+        if let Some(ref ver) = update_version {
+            data["update_available"] = serde_json::json!(ver);
+        }
+        output::print_json(&data);
+    } else {
+        println!("Daemon started via {}", method);
+        if let Some(ver) = update_version {
+            eprintln!(
+                "Note: timely {} is available (you have {}). Run `timely update` to upgrade.",
+                ver,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+    }
 }
 
 pub fn cmd_stop(json: bool) -> Result<()> {
@@ -148,6 +165,17 @@ pub fn cmd_stop(json: bool) -> Result<()> {
 
     #[cfg(not(target_os = "macos"))]
     {
+        #[cfg(target_os = "linux")]
+        if config::systemd_unit_path()?.exists() && Systemctl::default().stop().is_ok() {
+            let _ = std::fs::remove_file(config::pid_path()?);
+            if json {
+                output::print_json(&serde_json::json!({ "stopped": true }));
+            } else {
+                println!("Daemon stopped");
+            }
+            return Ok(());
+        }
+
         if let Some(pid) = read_pid()? {
             if is_process_alive(pid) {
                 unsafe {
@@ -167,23 +195,73 @@ pub fn cmd_stop(json: bool) -> Result<()> {
 }
 
 pub fn cmd_status(json: bool) -> Result<()> {
-    let (running, pid) = if let Some(pid) = read_pid()? {
-        (is_process_alive(pid), Some(pid))
-    } else {
-        (false, None)
+    // A daemon answering on its control socket reports its own health
+    let status = match control::status(&config::control_socket_path()?) {
+        Ok(status) => status,
+        Err(_) => {
+            let (running, pid) = if let Some(pid) = read_pid()? {
+                (is_process_alive(pid), Some(pid))
+            } else {
+                (false, None)
+            };
+            DaemonStatus {
+                running,
+                pid: if running { pid } else { None },
+                uptime_seconds: None,
+                uptime_time: None,
+                responding: false,
+                device: None,
+                paused: None,
+                paused_until: None,
+                last_heartbeat_at: None,
+                last_error: None,
+                watchers: Vec::new(),
+                service: None,
+            }
+        }
     };
-
+    #[cfg(target_os = "linux")]
     let status = DaemonStatus {
-        running,
-        pid: if running { pid } else { None },
-        uptime_seconds: None, // Would need start time tracking
-        uptime_time: None,
+        service: service_status()?,
+        ..status
     };
 
     if json {
         output::print_json(&status);
-    } else if running {
-        println!("Daemon is running (pid {})", pid.unwrap());
+    } else if status.responding {
+        println!(
+            "Daemon is running (pid {}, up {})",
+            status.pid.unwrap_or_default(),
+            status.uptime_time.as_deref().unwrap_or("?")
+        );
+        if status.paused == Some(true) {
+            match status.paused_until {
+                Some(ref until) => println!("Tracking is paused until {}", until),
+                None => println!("Tracking is paused"),
+            }
+        }
+        if let Some(ref at) = status.last_heartbeat_at {
+            println!("Last heartbeat: {}", at);
+        }
+        if let Some(ref error) = status.last_error {
+            println!("Last error: {}", error);
+        }
+        if let Some(restarts) = status.service.as_ref().and_then(|s| s.restarts).filter(|n| *n > 0) {
+            println!("Restarted by systemd {} times", restarts);
+        }
+        for watcher in &status.watchers {
+            match watcher.error {
+                Some(ref error) => println!("  {:<12} error: {}", watcher.name, error),
+                None => println!("  {:<12} ok", watcher.name),
+            }
+        }
+    } else if let Some(service) = status.service.as_ref().filter(|s| s.active_state != "inactive") {
+        println!(
+            "{} is {} ({}) but the daemon is not answering on its control socket",
+            service.unit, service.active_state, service.sub_state
+        );
+    } else if status.running {
+        println!("Daemon is running (pid {}) but not answering on its control socket", status.pid.unwrap());
     } else {
         println!("Daemon is not running");
     }
@@ -191,10 +269,30 @@ pub fn cmd_status(json: bool) -> Result<()> {
     Ok(())
 }
 
+/// Ask the running daemon to rebuild its watchers from config.
+pub fn cmd_reload(json: bool) -> Result<()> {
+    control::request(&config::control_socket_path()?, Request::Reload).map_err(|_| TimelyError::DaemonNotRunning)?;
+    if json {
+        output::print_json(&serde_json::json!({ "reloading": true }));
+    } else {
+        println!("Daemon is reloading its watchers");
+    }
+    Ok(())
+}
+
 pub fn cmd_run() -> Result<()> {
     crate::daemon::run_daemon()
 }
 
+/// systemd's view of the service, if its unit has been installed.
+#[cfg(target_os = "linux")]
+fn service_status() -> Result<Option<ServiceStatus>> {
+    if !config::systemd_unit_path()?.exists() {
+        return Ok(None);
+    }
+    Ok(Systemctl::default().status().ok())
+}
+
 fn read_pid() -> Result<Option<u32>> {
     let path = config::pid_path()?;
     if path.exists() {
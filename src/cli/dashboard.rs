@@ -1,3 +1,5 @@
+use std::net::SocketAddr;
+
 use crate::db;
 use crate::db::categories as db_categories;
 use crate::error::Result;
@@ -24,7 +26,8 @@ pub fn cmd_dashboard(port: u16) -> Result<()> {
             let _ = std::process::Command::new("open").arg(&url).spawn();
         }
 
-        axum::serve(listener, app).await?;
+        // Peer addresses let local-only routes tell who is calling
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
         Ok(())
     })
 }
@@ -13,7 +13,7 @@ pub fn cmd_list(json: bool) -> Result<()> {
         if devices.is_empty() {
             println!("No devices registered");
         } else {
-            println!("{:<40} {:<20} {:<10} {}", "ID", "Name", "Platform", "Last Sync");
+            println!("{:<40} {:<20} {:<10} Last Sync", "ID", "Name", "Platform");
             println!("{:-<90}", "");
             for d in &devices {
                 println!("{:<40} {:<20} {:<10} {}", d.id, d.name, d.platform, d.last_sync.to_rfc3339());
@@ -2,14 +2,14 @@ use crate::db;
 use crate::db::events;
 use crate::error::{Result, TimelyError};
 use crate::output;
-use crate::query;
+use crate::query::{self, SourceFilter};
 
-pub fn cmd_export(format: &str, from: &str, to: &str) -> Result<()> {
+pub fn cmd_export(format: &str, from: &str, to: &str, sources: &SourceFilter) -> Result<()> {
     let from_dt = query::parse_time(from)?;
     let to_dt = query::parse_time(to)?;
 
     let conn = db::open_default_db()?;
-    let event_list = events::query_events(&conn, &from_dt, &to_dt, None)?;
+    let event_list = events::query_events_filtered(&conn, &from_dt, &to_dt, None, sources)?;
 
     if event_list.is_empty() {
         return Err(TimelyError::NoData);
@@ -17,10 +17,10 @@ pub fn cmd_export(format: &str, from: &str, to: &str) -> Result<()> {
 
     match format {
         "csv" => {
-            println!("timestamp,duration,app,title,url,url_domain,category,is_afk");
+            println!("timestamp,duration,app,title,url,url_domain,category,is_afk,source");
             for e in &event_list {
                 println!(
-                    "{},{},{},{},{},{},{},{}",
+                    "{},{},{},{},{},{},{},{},{}",
                     e.timestamp.to_rfc3339(),
                     e.duration,
                     csv_escape(&e.app),
@@ -29,6 +29,7 @@ pub fn cmd_export(format: &str, from: &str, to: &str) -> Result<()> {
                     csv_escape(e.url_domain.as_deref().unwrap_or("")),
                     csv_escape(e.category_name.as_deref().unwrap_or("")),
                     e.is_afk,
+                    e.source,
                 );
             }
         }
@@ -2,7 +2,7 @@ use crate::db;
 use crate::db::{devices, events};
 use crate::error::Result;
 use crate::output;
-use crate::types::Event;
+use crate::types::{Event, EventSource};
 
 pub fn cmd_import(file: &str, json: bool) -> Result<()> {
     let content = std::fs::read_to_string(file)?;
@@ -23,17 +23,13 @@ pub fn cmd_import(file: &str, json: bool) -> Result<()> {
 
     let mut count = 0;
     for event in &imported {
-        events::insert_event(
+        events::insert_full_event(
             &conn,
-            &device.id,
-            &event.timestamp,
-            event.duration,
-            &event.app,
-            &event.title,
-            event.url.as_deref(),
-            event.url_domain.as_deref(),
-            event.category_id,
-            event.is_afk,
+            &Event {
+                device_id: device.id.clone(),
+                source: EventSource::Import,
+                ..event.clone()
+            },
         )?;
         count += 1;
     }
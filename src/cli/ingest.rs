@@ -0,0 +1,34 @@
+use std::io::Read;
+
+use crate::db;
+use crate::db::categories as db_categories;
+use crate::error::Result;
+use crate::ingest;
+use crate::output;
+
+pub fn cmd_ingest(source: Option<&str>, device: Option<&str>, json: bool) -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    let mut events = ingest::parse_ndjson(&input)?;
+    // Flags fill in whatever the lines leave out
+    for event in &mut events {
+        if event.source.is_none() {
+            event.source = source.map(String::from);
+        }
+        if event.device.is_none() {
+            event.device = device.map(String::from);
+        }
+    }
+
+    let conn = db::open_default_db()?;
+    db_categories::seed_builtin_categories(&conn)?;
+    let result = ingest::ingest_events(&conn, &events)?;
+
+    if json {
+        output::print_json(&result);
+    } else {
+        println!("Ingested {} events", result.accepted);
+    }
+    Ok(())
+}
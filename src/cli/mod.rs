@@ -7,14 +7,22 @@ pub mod config_cmd;
 pub mod devices;
 pub mod export;
 pub mod import_cmd;
+pub mod ingest;
+pub mod shell_hook;
 pub mod dashboard;
 pub mod sync_cmd;
 pub mod focus;
+pub mod pause;
+pub mod redact;
+pub mod afk;
+pub mod apps;
 pub mod trends;
 pub mod update;
 
 use clap::{Parser, Subcommand};
 
+use crate::types::EventSource;
+
 #[derive(Parser)]
 #[command(
     name = "timely",
@@ -68,7 +76,7 @@ pub enum Commands {
         /// End time (default: now)
         #[arg(long, default_value = "now")]
         to: String,
-        /// Group by: category, app, or url
+        /// Group by: category, app, url, project, or language
         #[arg(long, default_value = "category")]
         by: String,
         /// Exclude AFK/passive time from the summary
@@ -83,6 +91,12 @@ pub enum Commands {
         /// Query a specific device by name
         #[arg(long)]
         device: Option<String>,
+        /// Only events from these sources (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["all_devices", "device"])]
+        source: Vec<EventSource>,
+        /// Leave out events from these sources (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["all_devices", "device"])]
+        exclude_source: Vec<EventSource>,
     },
     /// Show activity timeline
     Timeline {
@@ -104,6 +118,12 @@ pub enum Commands {
         /// Query a specific device by name
         #[arg(long)]
         device: Option<String>,
+        /// Only events from these sources (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["all_devices", "device"])]
+        source: Vec<EventSource>,
+        /// Leave out events from these sources (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',', conflicts_with_all = ["all_devices", "device"])]
+        exclude_source: Vec<EventSource>,
     },
     /// Manage category rules
     Categorize {
@@ -131,6 +151,12 @@ pub enum Commands {
         /// End time
         #[arg(long, default_value = "now")]
         to: String,
+        /// Only events from these sources (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        source: Vec<EventSource>,
+        /// Leave out events from these sources (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        exclude_source: Vec<EventSource>,
     },
     /// Import activity data from file
     Import {
@@ -140,6 +166,29 @@ pub enum Commands {
         #[arg(long)]
         json: bool,
     },
+    /// Ingest events as newline-delimited JSON from stdin
+    Ingest {
+        /// Source for events that don't name one (e.g. "ci-bot")
+        #[arg(long)]
+        source: Option<String>,
+        /// Device name for events that don't name one (default: the source)
+        #[arg(long)]
+        device: Option<String>,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print shell integration hooks (add `eval "$(timely shell-hook zsh)"` to your rc file)
+    ShellHook {
+        /// Shell: zsh, bash, or fish
+        shell: String,
+    },
+    /// Report a shell command to the daemon (called by the shell hooks)
+    #[command(hide = true)]
+    ShellEvent {
+        #[command(subcommand)]
+        action: ShellEventAction,
+    },
     /// Launch the web dashboard
     Dashboard {
         /// Port to serve on
@@ -151,6 +200,45 @@ pub enum Commands {
         #[command(subcommand)]
         action: SyncAction,
     },
+    /// Pause tracking: indefinitely, for a duration (30m, 2h) or until a time (until 17:00)
+    Pause {
+        /// Empty, a duration, or `until HH:MM`
+        spec: Vec<String>,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// Resume tracking after `timely pause`
+    Resume {
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apply the current redaction rules (`redact.*` config) to stored events
+    Redact {
+        /// Start time (default: today)
+        #[arg(long, default_value = "today")]
+        from: String,
+        /// End time (default: now)
+        #[arg(long, default_value = "now")]
+        to: String,
+        /// Count the events that would change without modifying them
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// List AFK periods, or label them as offline activity (meetings, calls)
+    Afk {
+        #[command(subcommand)]
+        action: AfkAction,
+    },
+    /// List recorded apps and manage app aliases
+    Apps {
+        #[command(subcommand)]
+        action: AppsAction,
+    },
     /// Analyze focus and context switching
     Focus {
         /// Start time (default: today)
@@ -212,10 +300,107 @@ pub enum DaemonAction {
         #[arg(long)]
         json: bool,
     },
+    /// Reload watcher configuration in the running daemon
+    Reload {
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
     /// Run daemon in foreground
     Run,
 }
 
+#[derive(Subcommand)]
+pub enum AfkAction {
+    /// List periods away from the computer
+    List {
+        /// Start time (default: today)
+        #[arg(long, default_value = "today")]
+        from: String,
+        /// End time (default: now)
+        #[arg(long, default_value = "now")]
+        to: String,
+        /// Only periods at least this many seconds long
+        #[arg(long, default_value_t = 0.0)]
+        min_secs: f64,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// Turn AFK time into categorized offline activity
+    Label {
+        /// An id from `timely afk list`, or a range (14:00..14:40, 14:00-14:40)
+        target: String,
+        /// Category name (e.g. "meetings")
+        category: String,
+        /// What you were doing; becomes the activity's title
+        #[arg(long)]
+        note: Option<String>,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AppsAction {
+    /// Group an app name under another (e.g. "Code" as "VS Code")
+    Alias {
+        /// The app name as recorded
+        alias: String,
+        /// The name to group it under
+        canonical: String,
+        /// Reclassify existing events recorded under the alias
+        #[arg(long)]
+        retroactive: bool,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// Stop grouping an app name under another
+    Unalias {
+        alias: String,
+        /// Reclassify existing events recorded under the alias
+        #[arg(long)]
+        retroactive: bool,
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+    /// List recorded app names with their canonical name, id and path
+    List {
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ShellEventAction {
+    /// A command is about to run
+    Start {
+        #[arg(long)]
+        pid: u32,
+        #[arg(long)]
+        seq: u64,
+        #[arg(long)]
+        cwd: String,
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// The command finished
+    End {
+        #[arg(long)]
+        pid: u32,
+        #[arg(long)]
+        seq: u64,
+        #[arg(long)]
+        status: i32,
+        #[arg(long)]
+        cwd: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum CategorizeAction {
     /// Set a category rule
@@ -240,6 +425,12 @@ pub enum CategorizeAction {
         #[arg(long)]
         json: bool,
     },
+    /// Reapply all rules to recorded events (e.g. after editing rules)
+    Apply {
+        /// Output as JSON envelope: {"ok": true, "data": ...}
+        #[arg(long)]
+        json: bool,
+    },
     /// Delete a category rule by ID
     Delete {
         /// Rule ID to delete
@@ -1,8 +1,9 @@
 use chrono::Utc;
-use crate::config::HEARTBEAT_MERGE_GAP_SECS;
+use crate::config::{self, HEARTBEAT_MERGE_GAP_SECS};
+use crate::daemon::control;
 use crate::db;
 use crate::db::config_store;
-use crate::db::devices;
+use crate::db::{devices, events};
 use crate::error::{Result, TimelyError};
 use crate::output;
 use crate::sync::client;
@@ -32,44 +33,17 @@ pub fn cmd_now(json: bool, all_devices: bool, device: Option<&str>) -> Result<()
         return Ok(());
     }
 
+    // The running daemon knows the current activity without re-deriving it
+    if let Some(response) = live_now() {
+        print_now(&response, json);
+        return Ok(());
+    }
+
     // Local query mode
     let conn = db::open_default_db()?;
     let device = devices::get_or_create_device(&conn)?;
 
-    // Find the most recent event with a non-empty app name
-    let last = {
-        let mut stmt = conn.prepare(
-            "SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
-                    e.category_id, c.name, e.is_afk
-             FROM events e
-             LEFT JOIN categories c ON c.id = e.category_id
-             WHERE e.device_id = ?1 AND e.app != ''
-             ORDER BY e.id DESC LIMIT 1",
-        )?;
-        let mut rows = stmt.query(rusqlite::params![device.id])?;
-        match rows.next()? {
-            Some(row) => {
-                let ts_str: String = row.get(2)?;
-                let timestamp = chrono::DateTime::parse_from_rfc3339(&ts_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-                crate::types::Event {
-                    id: row.get(0)?,
-                    device_id: row.get(1)?,
-                    timestamp,
-                    duration: row.get(3)?,
-                    app: row.get(4)?,
-                    title: row.get(5)?,
-                    url: row.get(6)?,
-                    url_domain: row.get(7)?,
-                    category_id: row.get(8)?,
-                    category_name: row.get(9)?,
-                    is_afk: row.get::<_, i32>(10)? != 0,
-                }
-            }
-            None => return Err(TimelyError::NoData),
-        }
-    };
+    let last = events::get_last_app_event(&conn, &device.id)?.ok_or(TimelyError::NoData)?;
 
     let elapsed = (Utc::now() - last.timestamp).num_seconds() as f64;
 
@@ -94,10 +68,35 @@ pub fn cmd_now(json: bool, all_devices: bool, device: Option<&str>) -> Result<()
         duration_time: format_duration(active_duration),
         is_afk: last.is_afk,
         stale,
+        paused: false,
     };
 
+    print_now(&response, json);
+    Ok(())
+}
+
+/// Current activity from the daemon's control socket, if it is running.
+fn live_now() -> Option<NowResponse> {
+    let live = control::snapshot(&config::control_socket_path().ok()?).ok()?;
+    let current = live.current?;
+    Some(NowResponse {
+        app: current.app,
+        title: current.title,
+        url: current.url,
+        category: current.category_name,
+        productivity_score: None,
+        since: current.timestamp.to_rfc3339(),
+        duration_seconds: current.duration,
+        duration_time: format_duration(current.duration),
+        is_afk: current.is_afk,
+        stale: false,
+        paused: live.paused,
+    })
+}
+
+fn print_now(response: &NowResponse, json: bool) {
     if json {
-        output::print_json(&response);
+        output::print_json(response);
     } else {
         println!("{} — {}", response.app, response.title);
         if let Some(ref url) = response.url {
@@ -107,13 +106,13 @@ pub fn cmd_now(json: bool, all_devices: bool, device: Option<&str>) -> Result<()
             println!("Category: {}", cat);
         }
         println!("Since: {} ({})", response.since, response.duration_time);
-        if response.is_afk {
+        if response.paused {
+            println!("Status: tracking paused");
+        } else if response.is_afk {
             println!("Status: AFK");
         }
         if response.stale {
             println!("Note: daemon is not running — showing last recorded activity");
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+
+use crate::config;
+use crate::daemon::control::{self, Request};
+use crate::daemon::pause::{self, Pause};
+use crate::db;
+use crate::error::{Result, TimelyError};
+use crate::output;
+
+pub fn cmd_pause(spec: &[String], json: bool) -> Result<()> {
+    let until = parse_pause_spec(&spec.join(" "), &Local::now())?;
+
+    // Saved first, so the pause holds even if the daemon (re)starts later
+    let conn = db::open_default_db()?;
+    pause::save(&conn, &Pause { until })?;
+    let notified = control::request(&config::control_socket_path()?, Request::Pause { until }).is_ok();
+
+    if json {
+        output::print_json(&serde_json::json!({
+            "paused": true,
+            "until": until.map(|u| u.to_rfc3339()),
+            "daemon_notified": notified,
+        }));
+    } else {
+        match until {
+            Some(until) => println!("Tracking paused until {}", until.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+            None => println!("Tracking paused until `timely resume`"),
+        }
+        if !notified {
+            println!("Note: the daemon is not running; the pause applies when it starts");
+        }
+    }
+    Ok(())
+}
+
+pub fn cmd_resume(json: bool) -> Result<()> {
+    let conn = db::open_default_db()?;
+    pause::clear(&conn)?;
+    let notified = control::request(&config::control_socket_path()?, Request::Resume).is_ok();
+
+    if json {
+        output::print_json(&serde_json::json!({ "paused": false, "daemon_notified": notified }));
+    } else {
+        println!("Tracking resumed");
+        if !notified {
+            println!("Note: the daemon is not running");
+        }
+    }
+    Ok(())
+}
+
+/// When a pause given as `""` (indefinite), a duration (`30m`, `2h`, `1h30m`)
+/// or `until HH:MM` / `until <RFC 3339>` ends. A time of day that has already
+/// passed today means tomorrow.
+pub fn parse_pause_spec(spec: &str, now: &DateTime<Local>) -> Result<Option<DateTime<Utc>>> {
+    let spec = spec.trim().to_lowercase();
+    if spec.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || {
+        TimelyError::InvalidTimeRange(format!(
+            "Cannot parse pause '{}'. Use: 30m, 2h, 1h30m, until 17:00, or until <RFC 3339 time>",
+            spec
+        ))
+    };
+
+    if let Some(at) = spec.strip_prefix("until") {
+        let at = at.trim();
+        if let Ok(dt) = DateTime::parse_from_rfc3339(at) {
+            return Ok(Some(dt.with_timezone(&Utc)));
+        }
+        let time = NaiveTime::parse_from_str(at, "%H:%M").map_err(|_| invalid())?;
+        let mut date = now.date_naive();
+        if date.and_time(time) <= now.naive_local() {
+            date = date.succ_opt().ok_or_else(invalid)?;
+        }
+        let local = Local.from_local_datetime(&date.and_time(time)).earliest().ok_or_else(invalid)?;
+        return Ok(Some(local.with_timezone(&Utc)));
+    }
+
+    let mut total = Duration::zero();
+    let mut digits = String::new();
+    for c in spec.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let n: i64 = digits.parse().map_err(|_| invalid())?;
+        digits.clear();
+        let part = match c {
+            'd' => Duration::try_days(n),
+            'h' => Duration::try_hours(n),
+            'm' => Duration::try_minutes(n),
+            's' => Duration::try_seconds(n),
+            _ => return Err(invalid()),
+        };
+        total = part.and_then(|part| total.checked_add(&part)).ok_or_else(invalid)?;
+    }
+    if !digits.is_empty() || total <= Duration::zero() {
+        return Err(invalid());
+    }
+    let until = now.with_timezone(&Utc).checked_add_signed(total).ok_or_else(invalid)?;
+    Ok(Some(until))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_durations() {
+        let now = at(9, 0);
+        assert_eq!(parse_pause_spec("", &now).unwrap(), None);
+        assert_eq!(
+            parse_pause_spec("30m", &now).unwrap(),
+            Some(now.with_timezone(&Utc) + Duration::minutes(30))
+        );
+        assert_eq!(
+            parse_pause_spec("1h30m", &now).unwrap(),
+            Some(now.with_timezone(&Utc) + Duration::minutes(90))
+        );
+        assert!(parse_pause_spec("30", &now).is_err());
+        assert!(parse_pause_spec("soon", &now).is_err());
+    }
+
+    #[test]
+    fn test_parse_overflow() {
+        let now = at(9, 0);
+        assert!(parse_pause_spec("9999999999999d", &now).is_err());
+        assert!(parse_pause_spec("99999999999999999999m", &now).is_err());
+        assert!(parse_pause_spec("100000000d100000000d", &now).is_err());
+    }
+
+    #[test]
+    fn test_parse_until() {
+        let later_today = parse_pause_spec("until 17:00", &at(9, 0)).unwrap().unwrap();
+        assert_eq!(later_today, at(17, 0).with_timezone(&Utc));
+
+        let tomorrow = parse_pause_spec("until 08:30", &at(9, 0)).unwrap().unwrap();
+        assert_eq!(tomorrow, (at(8, 30) + Duration::days(1)).with_timezone(&Utc));
+
+        let exact = parse_pause_spec("until 2026-03-10T12:00:00Z", &at(9, 0)).unwrap().unwrap();
+        assert_eq!(exact.to_rfc3339(), "2026-03-10T12:00:00+00:00");
+        assert!(parse_pause_spec("until lunch", &at(9, 0)).is_err());
+    }
+}
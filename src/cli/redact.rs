@@ -0,0 +1,28 @@
+use crate::db;
+use crate::error::Result;
+use crate::output;
+use crate::query;
+use crate::redact::{self, Redactor};
+
+pub fn cmd_redact(from: &str, to: &str, dry_run: bool, json: bool) -> Result<()> {
+    let from_dt = query::parse_time(from)?;
+    let to_dt = query::parse_time(to)?;
+
+    let conn = db::open_default_db()?;
+    let redactor = Redactor::from_config(&conn)?;
+    let changed = redact::redact_events(&conn, &redactor, &from_dt, &to_dt, dry_run)?;
+
+    if json {
+        output::print_json(&serde_json::json!({
+            "from": from_dt.to_rfc3339(),
+            "to": to_dt.to_rfc3339(),
+            "dry_run": dry_run,
+            "redacted": changed,
+        }));
+    } else if dry_run {
+        println!("{} events would be redacted ({} to {})", changed, from, to);
+    } else {
+        println!("Redacted {} events ({} to {})", changed, from, to);
+    }
+    Ok(())
+}
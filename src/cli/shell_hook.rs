@@ -0,0 +1,33 @@
+use crate::config;
+use crate::error::Result;
+use crate::watchers::shell::{self, ShellMessage};
+
+pub fn cmd_shell_hook(shell_name: &str) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    print!("{}", shell::hook_script(shell_name, &exe.to_string_lossy())?);
+    Ok(())
+}
+
+pub fn cmd_start(pid: u32, seq: u64, cwd: &str, command: &str) -> Result<()> {
+    shell::send(
+        &config::shell_socket_path()?,
+        &ShellMessage::Start {
+            pid,
+            seq,
+            command: command.to_string(),
+            cwd: cwd.to_string(),
+        },
+    )
+}
+
+pub fn cmd_end(pid: u32, seq: u64, exit_status: i32, cwd: &str) -> Result<()> {
+    shell::send(
+        &config::shell_socket_path()?,
+        &ShellMessage::End {
+            pid,
+            seq,
+            exit_status,
+            cwd: cwd.to_string(),
+        },
+    )
+}
@@ -2,11 +2,21 @@ use crate::db;
 use crate::db::config_store;
 use crate::error::Result;
 use crate::output;
-use crate::query;
+use crate::query::{self, SourceFilter};
 use crate::query::summary::{self, GroupBy};
 use crate::sync::client;
 
-pub fn cmd_summary(from: &str, to: &str, by: &str, exclude_afk: bool, json: bool, all_devices: bool, device: Option<&str>) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn cmd_summary(
+    from: &str,
+    to: &str,
+    by: &str,
+    exclude_afk: bool,
+    json: bool,
+    all_devices: bool,
+    device: Option<&str>,
+    sources: &SourceFilter,
+) -> Result<()> {
     // Remote query mode: when --all-devices or --device is set
     if all_devices || device.is_some() {
         let conn = db::open_default_db()?;
@@ -43,17 +53,25 @@ pub fn cmd_summary(from: &str, to: &str, by: &str, exclude_afk: bool, json: bool
     let group_by = match by {
         "app" => GroupBy::App,
         "url" => GroupBy::Url,
+        "project" => GroupBy::Project,
+        "language" => GroupBy::Language,
         _ => GroupBy::Category,
     };
 
     let conn = db::open_default_db()?;
-    let result = summary::build_summary(&conn, &from_dt, &to_dt, group_by, exclude_afk)?;
+    let result = summary::build_summary_filtered(&conn, &from_dt, &to_dt, group_by, exclude_afk, sources)?;
 
     if json {
         output::print_json(&result);
     } else {
         println!("Activity Summary ({} to {})", from, to);
         println!("Total: {} | Productivity: {:.2}", result.total_time, result.productivity_score);
+        if result.untracked_seconds > 0.0 {
+            println!("Untracked: {} (timely was not running)", result.untracked_time);
+        }
+        if result.paused_seconds > 0.0 {
+            println!("Paused: {}", result.paused_time);
+        }
         println!("{:-<60}", "");
         for group in &result.groups {
             let score_str = group
@@ -65,6 +83,18 @@ pub fn cmd_summary(from: &str, to: &str, by: &str, exclude_afk: bool, json: bool
                 group.label, group.time, group.percentage, score_str
             );
         }
+        if !result.media.is_empty() {
+            println!("\nBackground media");
+            println!("{:-<60}", "");
+            for media in &result.media {
+                let alongside = media
+                    .alongside
+                    .first()
+                    .map(|top| format!("  (while {} {})", top.category, top.time))
+                    .unwrap_or_default();
+                println!("{:<30} {:>8}{}", media.player, media.time, alongside);
+            }
+        }
     }
 
     Ok(())
@@ -2,11 +2,19 @@ use crate::db;
 use crate::db::config_store;
 use crate::error::Result;
 use crate::output;
-use crate::query;
+use crate::query::{self, SourceFilter};
 use crate::query::timeline;
 use crate::sync::client;
 
-pub fn cmd_timeline(from: &str, to: &str, limit: Option<i64>, json: bool, all_devices: bool, device: Option<&str>) -> Result<()> {
+pub fn cmd_timeline(
+    from: &str,
+    to: &str,
+    limit: Option<i64>,
+    json: bool,
+    all_devices: bool,
+    device: Option<&str>,
+    sources: &SourceFilter,
+) -> Result<()> {
     // Remote query mode: when --all-devices or --device is set
     if all_devices || device.is_some() {
         let conn = db::open_default_db()?;
@@ -50,7 +58,7 @@ pub fn cmd_timeline(from: &str, to: &str, limit: Option<i64>, json: bool, all_de
     let to_dt = query::parse_time(to)?;
 
     let conn = db::open_default_db()?;
-    let result = timeline::build_timeline(&conn, &from_dt, &to_dt, limit)?;
+    let result = timeline::build_timeline_filtered(&conn, &from_dt, &to_dt, limit, sources)?;
 
     if json {
         output::print_json(&result);
@@ -60,12 +68,16 @@ pub fn cmd_timeline(from: &str, to: &str, limit: Option<i64>, json: bool, all_de
         for entry in &result.entries {
             let cat = entry.category.as_deref().unwrap_or("-");
             let afk = if entry.is_afk { " [AFK]" } else { "" };
+            let title = match &entry.command {
+                Some(command) => describe_command(command, entry.cwd.as_deref(), entry.exit_status),
+                None => entry.title.clone(),
+            };
             println!(
                 "{} {:>8}  {:<20} {:<30} {}{}",
                 &entry.timestamp[11..19],
                 entry.duration_time,
                 entry.app,
-                truncate(&entry.title, 30),
+                truncate(&title, 30),
                 cat,
                 afk,
             );
@@ -76,6 +88,23 @@ pub fn cmd_timeline(from: &str, to: &str, limit: Option<i64>, json: bool, all_de
     Ok(())
 }
 
+/// "cargo test in ~/src/foo", with a failing exit status appended.
+fn describe_command(command: &str, cwd: Option<&str>, exit_status: Option<i32>) -> String {
+    let mut text = command.to_string();
+    if let Some(cwd) = cwd {
+        let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string());
+        let cwd = match home.as_deref().and_then(|h| cwd.strip_prefix(h)) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("~{}", rest),
+            _ => cwd.to_string(),
+        };
+        text.push_str(&format!(" in {}", cwd));
+    }
+    if let Some(status) = exit_status.filter(|s| *s != 0) {
+        text.push_str(&format!(" (exit {})", status));
+    }
+    text
+}
+
 fn truncate(s: &str, max: usize) -> String {
     if s.len() <= max {
         s.to_string()
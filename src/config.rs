@@ -4,11 +4,23 @@ use std::path::PathBuf;
 
 pub const POLL_INTERVAL_SECS: u64 = 5;
 pub const HEARTBEAT_MERGE_GAP_SECS: f64 = 65.0;
+/// How often the daemon writes extensions of the open event (and its coverage) to the database
+pub const FLUSH_INTERVAL_SECS: u64 = 30;
 pub const DB_FILENAME: &str = "timely.db";
 pub const PID_FILENAME: &str = "timely.pid";
+pub const SHELL_SOCKET_FILENAME: &str = "shell.sock";
+pub const CONTROL_SOCKET_FILENAME: &str = "control.sock";
 pub const LAUNCHD_LABEL: &str = "com.timely.daemon";
 pub const BUNDLE_IDENTIFIER: &str = "com.timely.app";
+pub const SYSTEMD_UNIT_NAME: &str = "timely.service";
+/// systemd restarts the daemon if its loop stops pinging for this long
+pub const SYSTEMD_WATCHDOG_SECS: u64 = 60;
 pub const SYNC_DEFAULT_INTERVAL_SECS: u64 = 300;
+pub const AFK_DEFAULT_THRESHOLD_SECS: u64 = 180;
+/// Gaps in daemon coverage shorter than this are not reported as untracked
+pub const COVERAGE_MIN_GAP_SECS: f64 = 60.0;
+/// WakaTime plugins heartbeat every ~2 minutes; gaps up to its default 15 minute timeout count as coding.
+pub const WAKATIME_MERGE_GAP_SECS: f64 = 900.0;
 
 pub fn data_dir() -> Result<PathBuf> {
     let dir = dirs::home_dir()
@@ -28,6 +40,28 @@ pub fn pid_path() -> Result<PathBuf> {
     Ok(data_dir()?.join(PID_FILENAME))
 }
 
+/// Datagram socket the daemon's shell watcher listens on for shell hook messages.
+pub fn shell_socket_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join(SHELL_SOCKET_FILENAME))
+}
+
+/// Stream socket the daemon answers control requests on (status, pause, reload, ...).
+pub fn control_socket_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join(CONTROL_SOCKET_FILENAME))
+}
+
+/// `~/.config/systemd/user/timely.service` (honours `XDG_CONFIG_HOME`).
+pub fn systemd_unit_path() -> Result<PathBuf> {
+    let dir = dirs::config_dir()
+        .ok_or_else(|| TimelyError::Config("Cannot determine config directory".into()))?
+        .join("systemd")
+        .join("user");
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir.join(SYSTEMD_UNIT_NAME))
+}
+
 pub fn launchd_plist_path() -> Result<PathBuf> {
     let dir = dirs::home_dir()
         .ok_or_else(|| TimelyError::Config("Cannot determine home directory".into()))?
@@ -0,0 +1,141 @@
+//! The `afk.on_return` hook, run when the user comes back from an AFK period
+//! (idle, screen lock, sleep) long enough to be worth labeling.
+
+use std::process::Command;
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use crate::afk;
+use crate::config::HEARTBEAT_MERGE_GAP_SECS;
+use crate::db::config_store;
+use crate::error::{Result, TimelyError};
+use crate::types::AfkPeriod;
+
+/// Periods shorter than this don't fire the hook, unless `afk.on_return_min_secs` says otherwise.
+pub const ON_RETURN_DEFAULT_MIN_SECS: f64 = 300.0;
+
+/// Config:
+/// - `afk.on_return`: shell command to run; the period is in `TIMELY_AFK_ID`,
+///   `TIMELY_AFK_FROM`, `TIMELY_AFK_TO` and `TIMELY_AFK_SECONDS`
+/// - `afk.on_return_min_secs`: shortest period that fires it (default 300)
+pub struct ReturnHook {
+    command: Option<String>,
+    min_secs: f64,
+    away: bool,
+}
+
+impl ReturnHook {
+    pub fn from_config(conn: &Connection) -> Result<Self> {
+        let min_secs = match config_store::get(conn, "afk.on_return_min_secs")? {
+            Some(v) => v
+                .parse()
+                .map_err(|_| TimelyError::Config(format!("Invalid afk.on_return_min_secs '{}'", v)))?,
+            None => ON_RETURN_DEFAULT_MIN_SECS,
+        };
+        Ok(Self {
+            command: config_store::get(conn, "afk.on_return")?.filter(|c| !c.trim().is_empty()),
+            min_secs,
+            away: false,
+        })
+    }
+
+    /// Pick up changed config, remembering whether the user is away.
+    pub fn reload(&mut self, conn: &Connection) -> Result<()> {
+        let away = self.away;
+        *self = Self::from_config(conn)?;
+        self.away = away;
+        Ok(())
+    }
+
+    /// The user is away: an AFK heartbeat, or the screen is locked or asleep.
+    pub fn away(&mut self) {
+        self.away = true;
+    }
+
+    /// A non-AFK heartbeat at `now`, after the AFK event before it was written.
+    /// Returns the period the hook was started for.
+    pub fn back(&mut self, conn: &Connection, device_id: &str, now: &DateTime<Utc>) -> Result<Option<AfkPeriod>> {
+        if !std::mem::take(&mut self.away) {
+            return Ok(None);
+        }
+        let Some(command) = &self.command else {
+            return Ok(None);
+        };
+
+        let since = *now - chrono::Duration::days(1);
+        let Some(period) = afk::list_periods(conn, device_id, &since, now, 0.0)?.pop() else {
+            return Ok(None);
+        };
+        // Only the period that just ended, and only if it was long enough
+        let ended = chrono::DateTime::parse_from_rfc3339(&period.to)
+            .map(|t| (*now - t.with_timezone(&Utc)).num_seconds() as f64 <= HEARTBEAT_MERGE_GAP_SECS)
+            .unwrap_or(false);
+        if !ended || period.seconds < self.min_secs {
+            return Ok(None);
+        }
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("TIMELY_AFK_ID", period.id.to_string())
+            .env("TIMELY_AFK_FROM", &period.from)
+            .env("TIMELY_AFK_TO", &period.to)
+            .env("TIMELY_AFK_SECONDS", format!("{:.0}", period.seconds))
+            .spawn()?;
+        // Reap it without holding up the loop
+        std::thread::spawn(move || child.wait());
+        Ok(Some(period))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::heartbeat;
+    use crate::db;
+    use crate::types::WatcherSnapshot;
+
+    #[test]
+    fn test_fires_once_after_long_enough_period() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = db::open_db(tmp.path()).unwrap();
+        conn.execute(
+            "INSERT INTO devices (id, name, platform, last_sync) VALUES ('dev', 'test', 'linux', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        let out = tempfile::NamedTempFile::new().unwrap();
+        config_store::set(
+            &conn,
+            "afk.on_return",
+            &format!("echo $TIMELY_AFK_SECONDS > {}", out.path().display()),
+        )
+        .unwrap();
+        config_store::set(&conn, "afk.on_return_min_secs", "600").unwrap();
+        let mut hook = ReturnHook::from_config(&conn).unwrap();
+
+        let now = Utc::now();
+        let idle = WatcherSnapshot {
+            app: "Code".to_string(),
+            is_afk: true,
+            ..Default::default()
+        };
+        let start = now - chrono::Duration::minutes(20);
+        heartbeat::process_heartbeat_at(&conn, "dev", &idle, &start, 1200.0, 0.0).unwrap();
+
+        // Not away as far as the hook knows
+        assert!(hook.back(&conn, "dev", &now).unwrap().is_none());
+
+        hook.away();
+        let period = hook.back(&conn, "dev", &now).unwrap().unwrap();
+        assert_eq!(period.seconds, 1200.0);
+        assert!(hook.back(&conn, "dev", &now).unwrap().is_none());
+
+        // Too short
+        config_store::set(&conn, "afk.on_return_min_secs", "3600").unwrap();
+        hook.reload(&conn).unwrap();
+        hook.away();
+        assert!(hook.back(&conn, "dev", &now).unwrap().is_none());
+    }
+}
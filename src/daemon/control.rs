@@ -0,0 +1,279 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TimelyError};
+use crate::output;
+use crate::types::{format_duration, DaemonStatus, Event, LiveSnapshot, WatcherHealth, WatcherSnapshot};
+
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A request on the control socket: one JSON object per line, e.g.
+/// `{"command": "status"}`. The reply is a single line with the usual
+/// `{"ok": true, "data": ...}` / `{"ok": false, "error": ...}` envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum Request {
+    /// Uptime, pause state and per-watcher health
+    Status,
+    /// The last collected snapshot and the event it was recorded on
+    Snapshot,
+    /// Stop recording until resumed, or until `until` if given
+    Pause {
+        #[serde(default)]
+        until: Option<DateTime<Utc>>,
+    },
+    Resume,
+    /// Rebuild the watchers from config
+    Reload,
+}
+
+/// Daemon state shared between the loop and the control socket.
+pub struct DaemonState {
+    pub pid: u32,
+    pub device: String,
+    pub started_at: DateTime<Utc>,
+    pub paused: bool,
+    pub paused_until: Option<DateTime<Utc>>,
+    pub reload_requested: bool,
+    pub watchers: Vec<WatcherHealth>,
+    pub snapshot: Option<WatcherSnapshot>,
+    pub snapshot_at: Option<DateTime<Utc>>,
+    /// The last snapshot matched an exclusion; `snapshot` is then withheld
+    pub excluded: bool,
+    pub current: Option<Event>,
+    pub last_heartbeat_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl DaemonState {
+    pub fn new(device: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            device: device.to_string(),
+            started_at: Utc::now(),
+            paused: false,
+            paused_until: None,
+            reload_requested: false,
+            watchers: Vec::new(),
+            snapshot: None,
+            snapshot_at: None,
+            excluded: false,
+            current: None,
+            last_heartbeat_at: None,
+            last_error: None,
+        }
+    }
+
+    pub fn status(&self) -> DaemonStatus {
+        let uptime = (Utc::now() - self.started_at).num_milliseconds() as f64 / 1000.0;
+        DaemonStatus {
+            running: true,
+            pid: Some(self.pid),
+            uptime_seconds: Some(uptime),
+            uptime_time: Some(format_duration(uptime)),
+            responding: true,
+            device: Some(self.device.clone()),
+            paused: Some(self.paused),
+            paused_until: self.paused_until.map(|t| t.to_rfc3339()),
+            last_heartbeat_at: self.last_heartbeat_at.map(|t| t.to_rfc3339()),
+            last_error: self.last_error.clone(),
+            watchers: self.watchers.clone(),
+            service: None,
+        }
+    }
+
+    pub fn live_snapshot(&self) -> LiveSnapshot {
+        LiveSnapshot {
+            paused: self.paused,
+            excluded: self.excluded,
+            at: self.snapshot_at.map(|t| t.to_rfc3339()),
+            snapshot: self.snapshot.clone(),
+            current: self.current.clone(),
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> serde_json::Value {
+        match request {
+            Request::Status => output::success(&self.status()),
+            Request::Snapshot => output::success(&self.live_snapshot()),
+            Request::Pause { until } => {
+                self.paused = true;
+                self.paused_until = until;
+                output::success(&serde_json::json!({ "paused": true, "until": until }))
+            }
+            Request::Resume => {
+                self.paused = false;
+                self.paused_until = None;
+                output::success(&serde_json::json!({ "paused": false }))
+            }
+            Request::Reload => {
+                self.reload_requested = true;
+                output::success(&serde_json::json!({ "reloading": true }))
+            }
+        }
+    }
+}
+
+/// Answers control requests on a Unix socket from a background thread.
+/// The socket file is removed when the server is dropped.
+pub struct ControlServer {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ControlServer {
+    pub fn bind(path: &Path, state: Arc<Mutex<DaemonState>>) -> Result<Self> {
+        match UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(TimelyError::Generic(format!(
+                    "daemon already running ({} is in use)",
+                    path.display()
+                )))
+            }
+            // A socket file left behind by a daemon that didn't shut down cleanly
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                let _ = std::fs::remove_file(path);
+            }
+            Err(_) => {}
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = serve(stream, &state) {
+                            eprintln!("control socket error: {}", e);
+                        }
+                    }
+                    Err(_) => thread::sleep(Duration::from_millis(50)),
+                }
+            }
+        });
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn serve(stream: UnixStream, state: &Mutex<DaemonState>) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let reply = match serde_json::from_str::<Request>(line.trim()) {
+        Ok(request) => lock(state).handle(request),
+        Err(e) => output::error(&TimelyError::Generic(format!("invalid request: {}", e))),
+    };
+
+    let mut stream = stream;
+    writeln!(stream, "{}", reply)?;
+    Ok(())
+}
+
+/// Lock the shared state, recovering it if a holder panicked.
+pub fn lock(state: &Mutex<DaemonState>) -> MutexGuard<'_, DaemonState> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Send `request` to the daemon listening on `path` and return the reply's `data`.
+pub fn request(path: &Path, request: Request) -> Result<serde_json::Value> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let mut reply: serde_json::Value = serde_json::from_str(line.trim())?;
+    if reply.get("ok").and_then(|ok| ok.as_bool()) == Some(true) {
+        Ok(reply.get_mut("data").map(|d| d.take()).unwrap_or_default())
+    } else {
+        let message = reply.get("error").and_then(|e| e.as_str()).unwrap_or("no reply");
+        Err(TimelyError::Generic(format!("daemon: {}", message)))
+    }
+}
+
+pub fn status(path: &Path) -> Result<DaemonStatus> {
+    Ok(serde_json::from_value(request(path, Request::Status)?)?)
+}
+
+pub fn snapshot(path: &Path) -> Result<LiveSnapshot> {
+    Ok(serde_json::from_value(request(path, Request::Snapshot)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        assert_eq!(serde_json::to_string(&Request::Status).unwrap(), r#"{"command":"status"}"#);
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"command": "pause"}"#).unwrap(),
+            Request::Pause { until: None }
+        );
+    }
+
+    #[test]
+    fn test_round_trip_over_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let state = Arc::new(Mutex::new(DaemonState::new("laptop")));
+        let server = ControlServer::bind(&path, state.clone()).unwrap();
+
+        let reply = status(&path).unwrap();
+        assert!(reply.running && reply.responding);
+        assert_eq!(reply.device.as_deref(), Some("laptop"));
+        assert_eq!(reply.paused, Some(false));
+
+        request(&path, Request::Pause { until: None }).unwrap();
+        assert!(lock(&state).paused);
+        assert!(snapshot(&path).unwrap().paused);
+
+        drop(server);
+        assert!(!path.exists());
+        assert!(status(&path).is_err());
+    }
+
+    #[test]
+    fn test_bind_leaves_a_live_socket_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let state = Arc::new(Mutex::new(DaemonState::new("laptop")));
+
+        // Stale: the listener is gone but its file is not
+        drop(UnixListener::bind(&path).unwrap());
+        let _server = ControlServer::bind(&path, state.clone()).unwrap();
+
+        assert!(ControlServer::bind(&path, state).is_err());
+        assert!(status(&path).unwrap().responding);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+
+use crate::categories;
+use crate::db::categories as db_categories;
+use crate::db::config_store;
+use crate::error::Result;
+use crate::types::WatcherSnapshot;
+
+/// Categories where being idle still counts as engaged (listening in on a video call).
+pub const DEFAULT_ENGAGED_CATEGORIES: &str = "communication/video-call";
+
+/// Activity during which idle input doesn't make you AFK, from config:
+/// - `afk.engaged_categories`: comma-separated categories, including their
+///   subcategories (default `communication/video-call`; empty for none)
+/// - `afk.engaged_media`: media playing in the background counts as engaged (default false)
+///
+/// Such time is recorded as not AFK but idle, so both views stay queryable.
+pub struct Engagement {
+    category_ids: HashSet<i64>,
+    media: bool,
+}
+
+impl Engagement {
+    pub fn from_config(conn: &Connection) -> Result<Self> {
+        let names: Vec<String> = config_store::get(conn, "afk.engaged_categories")?
+            .unwrap_or_else(|| DEFAULT_ENGAGED_CATEGORIES.to_string())
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let category_ids = if names.is_empty() {
+            HashSet::new()
+        } else {
+            db_categories::list_categories(conn)?
+                .into_iter()
+                .filter(|c| {
+                    let name = c.name.to_lowercase();
+                    names
+                        .iter()
+                        .any(|n| name == *n || name.strip_prefix(n.as_str()).is_some_and(|rest| rest.starts_with('/')))
+                })
+                .map(|c| c.id)
+                .collect()
+        };
+        let media = config_store::get(conn, "afk.engaged_media")?.is_some_and(|v| v == "true");
+        Ok(Self { category_ids, media })
+    }
+
+    /// Keep an idle `snapshot` out of AFK when its activity is engaged. Idle
+    /// snapshots are marked `is_idle` either way.
+    pub fn apply(&self, conn: &Connection, snapshot: &mut WatcherSnapshot) -> Result<()> {
+        if !snapshot.is_afk {
+            return Ok(());
+        }
+        snapshot.is_idle = true;
+        let engaged = (self.media && snapshot.media.is_some())
+            || (!self.category_ids.is_empty()
+                && categories::rule_set(conn)?
+                    .classify(snapshot)
+                    .is_some_and(|id| self.category_ids.contains(&id)));
+        if engaged {
+            snapshot.is_afk = false;
+        }
+        Ok(())
+    }
+}
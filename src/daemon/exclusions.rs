@@ -0,0 +1,144 @@
+use rusqlite::Connection;
+
+use crate::db::config_store;
+use crate::error::{Result, TimelyError};
+use crate::types::WatcherSnapshot;
+
+/// Window titles browsers give private/incognito windows.
+const PRIVATE_WINDOW_TITLES: &[&str] = &[
+    "*private browsing*",
+    "*(incognito)*",
+    "*- incognito",
+    "*inprivate*",
+];
+
+/// Activity that must never be written to the database, from config:
+/// - `exclude.apps`: comma-separated app names (case-insensitive)
+/// - `exclude.domains`: comma-separated domains, including their subdomains
+/// - `exclude.titles`: comma-separated title globs (case-insensitive)
+/// - `exclude.private_windows`: skip private/incognito browser windows (default true)
+pub struct Exclusions {
+    apps: Vec<String>,
+    domains: Vec<String>,
+    titles: Vec<glob::Pattern>,
+    private_windows: bool,
+}
+
+impl Exclusions {
+    pub fn from_config(conn: &Connection) -> Result<Self> {
+        let list = |key: &str| -> Result<Vec<String>> {
+            Ok(config_store::get(conn, key)?
+                .map(|v| {
+                    v.split(',')
+                        .map(|item| item.trim().to_lowercase())
+                        .filter(|item| !item.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default())
+        };
+
+        let mut titles = list("exclude.titles")?;
+        let private_windows = config_store::get(conn, "exclude.private_windows")?
+            .map(|v| v != "false")
+            .unwrap_or(true);
+        if private_windows {
+            titles.extend(PRIVATE_WINDOW_TITLES.iter().map(|t| t.to_string()));
+        }
+
+        let titles = titles
+            .iter()
+            .map(|t| {
+                glob::Pattern::new(t)
+                    .map_err(|e| TimelyError::Config(format!("Invalid exclude.titles pattern '{}': {}", t, e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            apps: list("exclude.apps")?,
+            domains: list("exclude.domains")?
+                .into_iter()
+                .map(|d| d.trim_start_matches("*.").to_string())
+                .collect(),
+            titles,
+            private_windows,
+        })
+    }
+
+    pub fn excludes(&self, snapshot: &WatcherSnapshot) -> bool {
+        if self.private_windows && snapshot.is_private {
+            return true;
+        }
+
+        let app = snapshot.app.to_lowercase();
+        if self.apps.contains(&app) {
+            return true;
+        }
+
+        if let Some(domain) = snapshot.url_domain.as_deref().map(str::to_lowercase) {
+            let excluded = self
+                .domains
+                .iter()
+                .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)));
+            if excluded {
+                return true;
+            }
+        }
+
+        let title = snapshot.title.to_lowercase();
+        self.titles.iter().any(|p| p.matches(&title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(app: &str, title: &str, domain: Option<&str>) -> WatcherSnapshot {
+        WatcherSnapshot {
+            app: app.to_string(),
+            title: title.to_string(),
+            url_domain: domain.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_exclusions_from_config() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+        config_store::set(&conn, "exclude.apps", "1Password, Signal").unwrap();
+        config_store::set(&conn, "exclude.domains", "bank.example, *.health.example").unwrap();
+        config_store::set(&conn, "exclude.titles", "*payroll*").unwrap();
+        let exclusions = Exclusions::from_config(&conn).unwrap();
+
+        assert!(exclusions.excludes(&window("1password", "Vault", None)));
+        assert!(exclusions.excludes(&window("Firefox", "Login", Some("bank.example"))));
+        assert!(exclusions.excludes(&window("Firefox", "Results", Some("lab.health.example"))));
+        assert!(exclusions.excludes(&window("Excel", "Q3 Payroll.xlsx", None)));
+        assert!(!exclusions.excludes(&window("Firefox", "Docs", Some("notbank.example"))));
+        assert!(!exclusions.excludes(&window("Code", "main.rs", None)));
+    }
+
+    #[test]
+    fn test_private_windows() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+        let private_firefox = window("Firefox", "Search — Mozilla Firefox Private Browsing", None);
+        let incognito_chrome = window("Google Chrome", "New Tab - Google Chrome (Incognito)", None);
+
+        let flagged = WatcherSnapshot {
+            is_private: true,
+            ..window("Firefox", "Search", None)
+        };
+
+        let exclusions = Exclusions::from_config(&conn).unwrap();
+        assert!(exclusions.excludes(&private_firefox));
+        assert!(exclusions.excludes(&incognito_chrome));
+        assert!(exclusions.excludes(&flagged));
+
+        config_store::set(&conn, "exclude.private_windows", "false").unwrap();
+        let exclusions = Exclusions::from_config(&conn).unwrap();
+        assert!(!exclusions.excludes(&private_firefox));
+        assert!(!exclusions.excludes(&flagged));
+    }
+}
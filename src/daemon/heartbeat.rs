@@ -1,73 +1,207 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use rusqlite::Connection;
 use crate::config::HEARTBEAT_MERGE_GAP_SECS;
 use crate::error::Result;
-use crate::types::WatcherSnapshot;
-use crate::db::{events, categories as db_categories};
+use crate::types::{Event, MediaEvent, MediaPlayback, WatcherSnapshot};
+use crate::db::{events, media};
 use crate::categories;
+use crate::normalize::TitleNormalizer;
+use crate::redact::Redactor;
+use super::engagement::Engagement;
+use super::exclusions::Exclusions;
+use super::pause::{self, Pause};
 
 pub fn process_heartbeat(
     conn: &Connection,
     device_id: &str,
     snapshot: &WatcherSnapshot,
 ) -> Result<()> {
-    // Skip empty snapshots (e.g. no focused window)
-    if snapshot.app.is_empty() {
-        return Ok(());
+    process_heartbeat_at(conn, device_id, snapshot, &Utc::now(), 0.0, HEARTBEAT_MERGE_GAP_SECS)?;
+    Ok(())
+}
+
+/// Record a heartbeat observed at `timestamp` lasting `duration` seconds.
+/// If it matches the device's last event and starts within `merge_gap` seconds
+/// of that event's end, the event is extended to cover it; otherwise a new
+/// event is inserted. Excluded activity (`exclude.*`) and anything reported
+/// while tracking is paused is dropped, whichever path reports it. Returns the
+/// id of the affected event, or None for an empty or dropped snapshot.
+pub fn process_heartbeat_at(
+    conn: &Connection,
+    device_id: &str,
+    snapshot: &WatcherSnapshot,
+    timestamp: &DateTime<Utc>,
+    duration: f64,
+    merge_gap: f64,
+) -> Result<Option<i64>> {
+    Pipeline::from_config(conn)?.process(conn, device_id, snapshot, timestamp, duration, merge_gap)
+}
+
+/// The pause, exclusions, redaction, normalization and engagement that
+/// `process_heartbeat_at` applies, loaded once for a batch of heartbeats.
+pub struct Pipeline {
+    pause: Option<Pause>,
+    exclusions: Exclusions,
+    redactor: Redactor,
+    normalizer: TitleNormalizer,
+    engagement: Engagement,
+}
+
+impl Pipeline {
+    pub fn from_config(conn: &Connection) -> Result<Self> {
+        Ok(Self {
+            pause: pause::get(conn)?,
+            exclusions: Exclusions::from_config(conn)?,
+            redactor: Redactor::from_config(conn)?,
+            normalizer: TitleNormalizer::from_config(conn)?,
+            engagement: Engagement::from_config(conn)?,
+        })
     }
 
-    let now = Utc::now();
+    /// `process_heartbeat_at` with this pipeline.
+    pub fn process(
+        &self,
+        conn: &Connection,
+        device_id: &str,
+        snapshot: &WatcherSnapshot,
+        timestamp: &DateTime<Utc>,
+        duration: f64,
+        merge_gap: f64,
+    ) -> Result<Option<i64>> {
+        // Skip empty snapshots (e.g. no focused window)
+        if snapshot.app.is_empty() {
+            return Ok(None);
+        }
 
-    // Load rules for classification
-    let rules = db_categories::list_rules(conn)?;
-    let category_id = categories::classify(snapshot, &rules);
+        let paused = self.pause.as_ref().is_some_and(|p| !p.is_over(timestamp));
+        if paused || self.exclusions.excludes(snapshot) {
+            return Ok(None);
+        }
 
-    // If no category matched, look up "uncategorized"
-    let category_id = category_id.or_else(|| {
-        db_categories::get_category_by_name(conn, "uncategorized")
-            .ok()
-            .flatten()
-            .map(|c| c.id)
-    });
+        // Redact before anything is classified or stored
+        let mut snapshot = snapshot.clone();
+        self.redactor.redact(&mut snapshot);
+        self.normalizer.normalize(&mut snapshot);
+        self.engagement.apply(conn, &mut snapshot)?;
+        record_heartbeat_at(conn, device_id, &snapshot, timestamp, duration, merge_gap)
+    }
+}
+
+/// `process_heartbeat_at` for a snapshot that has already been redacted,
+/// normalized and had `Engagement` applied.
+pub fn record_heartbeat_at(
+    conn: &Connection,
+    device_id: &str,
+    snapshot: &WatcherSnapshot,
+    timestamp: &DateTime<Utc>,
+    duration: f64,
+    merge_gap: f64,
+) -> Result<Option<i64>> {
+    if snapshot.app.is_empty() {
+        return Ok(None);
+    }
+
+    let category_id = categories::rule_set(conn)?.classify_or_uncategorized(snapshot);
 
     // Try to extend last event
     if let Some(last) = events::get_last_event(conn, device_id)? {
-        let same_activity = last.app == snapshot.app
-            && last.title == snapshot.title
-            && last.url_domain == snapshot.url_domain
-            && last.is_afk == snapshot.is_afk;
-
-        if same_activity {
-            let elapsed = (now - last.timestamp).num_milliseconds() as f64 / 1000.0;
-            if elapsed < last.duration + HEARTBEAT_MERGE_GAP_SECS {
+        if same_activity(&last, snapshot) {
+            let elapsed = (*timestamp - last.timestamp).num_milliseconds() as f64 / 1000.0;
+            if elapsed >= 0.0 && elapsed < last.duration + merge_gap {
                 // Extend existing event
-                events::extend_event(conn, last.id, elapsed)?;
-                return Ok(());
+                events::extend_event(conn, last.id, last.duration.max(elapsed + duration))?;
+                if let Some(status) = snapshot.exit_status.filter(|s| last.exit_status != Some(*s)) {
+                    events::set_exit_status(conn, last.id, status)?;
+                }
+                return Ok(Some(last.id));
             }
         }
     }
 
     // Insert new event
-    events::insert_event(
+    let id = events::insert_full_event(
         conn,
-        device_id,
-        &now,
-        0.0,
-        &snapshot.app,
-        &snapshot.title,
-        snapshot.url.as_deref(),
-        snapshot.url_domain.as_deref(),
-        category_id,
-        snapshot.is_afk,
+        &Event {
+            id: 0,
+            device_id: device_id.to_string(),
+            timestamp: *timestamp,
+            duration,
+            app: snapshot.app.clone(),
+            title: snapshot.title.clone(),
+            raw_title: snapshot.raw_title.clone(),
+            app_id: snapshot.app_id.clone(),
+            app_path: snapshot.app_path.clone(),
+            url: snapshot.url.clone(),
+            url_domain: snapshot.url_domain.clone(),
+            category_id,
+            category_name: None,
+            is_afk: snapshot.is_afk,
+            is_idle: snapshot.is_idle || snapshot.is_afk,
+            project: snapshot.project.clone(),
+            file: snapshot.file.clone(),
+            language: snapshot.language.clone(),
+            branch: snapshot.branch.clone(),
+            command: snapshot.command.clone(),
+            cwd: snapshot.cwd.clone(),
+            exit_status: snapshot.exit_status,
+            source: snapshot.source,
+        },
     )?;
 
+    Ok(Some(id))
+}
+
+/// Whether `snapshot` continues the activity recorded in `last`.
+pub fn same_activity(last: &Event, snapshot: &WatcherSnapshot) -> bool {
+    last.app == snapshot.app
+        && last.title == snapshot.title
+        && last.url_domain == snapshot.url_domain
+        && last.is_afk == snapshot.is_afk
+        && last.is_idle == (snapshot.is_idle || snapshot.is_afk)
+        && last.source == snapshot.source
+        && last.project == snapshot.project
+        && last.file == snapshot.file
+        && last.branch == snapshot.branch
+        && last.command == snapshot.command
+}
+
+/// Whether `playback` continues the media event `last`.
+pub fn same_media(last: &MediaEvent, playback: &MediaPlayback) -> bool {
+    last.player == playback.player && last.title == playback.title && last.artist == playback.artist
+}
+
+pub fn process_media_heartbeat(conn: &Connection, device_id: &str, playback: &MediaPlayback) -> Result<()> {
+    process_media_heartbeat_at(conn, device_id, playback, &Utc::now(), HEARTBEAT_MERGE_GAP_SECS)?;
     Ok(())
 }
 
+/// Record that `playback` was playing at `timestamp`, extending the device's
+/// last media event when it is the same track within `merge_gap` seconds.
+/// Media has its own table, so it never splits or extends focus events.
+pub fn process_media_heartbeat_at(
+    conn: &Connection,
+    device_id: &str,
+    playback: &MediaPlayback,
+    timestamp: &DateTime<Utc>,
+    merge_gap: f64,
+) -> Result<i64> {
+    if let Some(last) = media::get_last_media_event(conn, device_id)? {
+        if same_media(&last, playback) {
+            let elapsed = (*timestamp - last.timestamp).num_milliseconds() as f64 / 1000.0;
+            if elapsed >= 0.0 && elapsed < last.duration + merge_gap {
+                media::extend_media_event(conn, last.id, last.duration.max(elapsed))?;
+                return Ok(last.id);
+            }
+        }
+    }
+    media::insert_media_event(conn, device_id, timestamp, 0.0, playback)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db;
+    use crate::db::categories as db_categories;
     use tempfile::NamedTempFile;
 
     fn setup_db() -> (Connection, String) {
@@ -94,6 +228,7 @@ mod tests {
             url: None,
             url_domain: None,
             is_afk: false,
+            ..Default::default()
         };
 
         process_heartbeat(&conn, &device_id, &snapshot).unwrap();
@@ -112,6 +247,7 @@ mod tests {
             url: None,
             url_domain: None,
             is_afk: false,
+            ..Default::default()
         };
 
         process_heartbeat(&conn, &device_id, &snapshot).unwrap();
@@ -142,6 +278,7 @@ mod tests {
             url: None,
             url_domain: None,
             is_afk: false,
+            ..Default::default()
         };
         process_heartbeat(&conn, &device_id, &s1).unwrap();
 
@@ -151,6 +288,7 @@ mod tests {
             url: Some("https://google.com".to_string()),
             url_domain: Some("google.com".to_string()),
             is_afk: false,
+            ..Default::default()
         };
         process_heartbeat(&conn, &device_id, &s2).unwrap();
 
@@ -163,4 +301,50 @@ mod tests {
             .unwrap();
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn test_nothing_recorded_while_paused() {
+        let (conn, device_id) = setup_db();
+        let snapshot = WatcherSnapshot {
+            app: "Code".to_string(),
+            title: "main.rs".to_string(),
+            ..Default::default()
+        };
+        pause::save(&conn, &pause::Pause { until: None }).unwrap();
+        assert_eq!(process_heartbeat_at(&conn, &device_id, &snapshot, &Utc::now(), 0.0, 65.0).unwrap(), None);
+
+        pause::clear(&conn).unwrap();
+        assert!(process_heartbeat_at(&conn, &device_id, &snapshot, &Utc::now(), 0.0, 65.0).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_heartbeat_shell_command() {
+        let (conn, device_id) = setup_db();
+        let prompt = WatcherSnapshot {
+            app: "Alacritty".to_string(),
+            title: "~/src/foo".to_string(),
+            cwd: Some("/home/me/src/foo".to_string()),
+            ..Default::default()
+        };
+        let running = WatcherSnapshot {
+            command: Some("cargo test".to_string()),
+            ..prompt.clone()
+        };
+        let finished = WatcherSnapshot {
+            exit_status: Some(101),
+            ..running.clone()
+        };
+
+        let first = process_heartbeat_at(&conn, &device_id, &prompt, &Utc::now(), 0.0, 65.0).unwrap();
+        let second = process_heartbeat_at(&conn, &device_id, &running, &Utc::now(), 0.0, 65.0).unwrap();
+        assert_ne!(first, second);
+
+        // The exit status lands on the command's event
+        let third = process_heartbeat_at(&conn, &device_id, &finished, &Utc::now(), 0.0, 65.0).unwrap();
+        assert_eq!(second, third);
+        let last = events::get_last_event(&conn, &device_id).unwrap().unwrap();
+        assert_eq!(last.command.as_deref(), Some("cargo test"));
+        assert_eq!(last.cwd.as_deref(), Some("/home/me/src/foo"));
+        assert_eq!(last.exit_status, Some(101));
+    }
 }
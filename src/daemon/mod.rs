@@ -1,18 +1,44 @@
+pub mod away;
+pub mod control;
+pub mod engagement;
+pub mod exclusions;
 pub mod heartbeat;
+pub mod pause;
+pub mod power;
+pub mod recorder;
+#[cfg(target_os = "linux")]
+pub mod systemd;
 
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::config::{POLL_INTERVAL_SECS, SYNC_DEFAULT_INTERVAL_SECS};
+use rusqlite::Connection;
+
+use chrono::Utc;
+
+use crate::config::{
+    AFK_DEFAULT_THRESHOLD_SECS, FLUSH_INTERVAL_SECS, HEARTBEAT_MERGE_GAP_SECS, POLL_INTERVAL_SECS,
+    SYNC_DEFAULT_INTERVAL_SECS,
+};
 use crate::db;
 use crate::db::categories as db_categories;
 use crate::db::config_store;
+use crate::db::coverage;
 use crate::db::devices;
 use crate::error::Result;
 use crate::sync;
-use crate::watchers;
+use crate::types::Device;
+use crate::watchers::WatcherRegistry;
+#[cfg(target_os = "linux")]
+use power::PowerEvent;
+use control::{ControlServer, DaemonState};
+use exclusions::Exclusions;
+use power::{ClockWatch, Presence};
+use recorder::Recorder;
+use away::ReturnHook;
 
 pub fn run_daemon() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
@@ -20,9 +46,9 @@ pub fn run_daemon() -> Result<()> {
     // Register signal handlers
     let r = running.clone();
     signal_hook::flag::register(signal_hook::consts::SIGTERM, r.clone())
-        .map_err(|e| crate::error::TimelyError::Io(e))?;
+        .map_err(crate::error::TimelyError::Io)?;
     signal_hook::flag::register(signal_hook::consts::SIGINT, r)
-        .map_err(|e| crate::error::TimelyError::Io(e))?;
+        .map_err(crate::error::TimelyError::Io)?;
 
     let conn = db::open_default_db()?;
     db_categories::seed_builtin_categories(&conn)?;
@@ -50,41 +76,326 @@ pub fn run_daemon() -> Result<()> {
         eprintln!("sync enabled (interval: {}s)", sync_interval);
     }
 
-    let mut sync_counter: u64 = 0;
+    let mut registry = WatcherRegistry::from_config(&conn, afk_threshold(&conn))?;
+    eprintln!("watchers: {}", registry.names().join(", "));
+
+    let options = LoopOptions {
+        tick: Duration::from_secs(POLL_INTERVAL_SECS),
+        flush_interval: Duration::from_secs(FLUSH_INTERVAL_SECS),
+        sync_interval: sync_enabled.then(|| Duration::from_secs(sync_interval)),
+        logind: true,
+        sd_notify: true,
+        control_socket: Some(crate::config::control_socket_path()?),
+    };
+    run_loop(&conn, &device, &mut registry, &running, &options)?;
+
+    // Cleanup PID file
+    let _ = std::fs::remove_file(&pid_path);
+    eprintln!("timely daemon stopped");
+
+    Ok(())
+}
+
+/// Idle time after which the user counts as AFK (same meaning on every platform)
+fn afk_threshold(conn: &Connection) -> u64 {
+    config_store::get(conn, "afk.threshold_secs")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(AFK_DEFAULT_THRESHOLD_SECS)
+}
+
+/// Timing of the daemon loop. Tests shrink the tick to run it end-to-end quickly.
+pub struct LoopOptions {
+    pub tick: Duration,
+    /// Write the open event's extensions and the coverage interval this often;
+    /// activity changes are written immediately
+    pub flush_interval: Duration,
+    /// Push events to the sync server this often; None when sync is disabled
+    pub sync_interval: Option<Duration>,
+    /// Follow logind's sleep and lock signals (Linux)
+    pub logind: bool,
+    /// Report readiness and ping the watchdog when run by systemd (Linux)
+    pub sd_notify: bool,
+    /// Answer status/pause/resume/reload/snapshot requests on this socket
+    pub control_socket: Option<PathBuf>,
+}
+
+/// Poll the watchers and record heartbeats until `running` is cleared.
+/// Sleep and lock periods are recorded as non-activity rather than sampled,
+/// excluded activity is never written, and pauses are recorded as such.
+/// The run itself is recorded as a coverage interval, so time when the daemon
+/// was down can be told apart from time without activity. Writes are batched
+/// (see `recorder`), so the loop rarely contends with readers of the database.
+pub fn run_loop(
+    conn: &Connection,
+    device: &Device,
+    registry: &mut WatcherRegistry,
+    running: &AtomicBool,
+    options: &LoopOptions,
+) -> Result<()> {
+    let mut last_sync = Instant::now();
+    let mut last_flush = Instant::now();
+    let mut recorder = Recorder::new(conn, &device.id)?;
+    let mut presence = Presence::new();
+    let mut clock = ClockWatch::new();
+    let coverage_id = coverage::start_interval(conn, &device.id, &Utc::now())?;
+
+    let mut exclusions = Exclusions::from_config(conn)?;
+    let mut return_hook = ReturnHook::from_config(conn)?;
+
+    let mut state = DaemonState::new(&device.name);
+    if let Some(pause) = pause::load(conn, &Utc::now())? {
+        state.paused = true;
+        state.paused_until = pause.until;
+    }
+    let state = Arc::new(Mutex::new(state));
+    let _control = options.control_socket.as_ref().and_then(|path| {
+        ControlServer::bind(path, state.clone())
+            .map_err(|e| eprintln!("control socket unavailable: {}", e))
+            .ok()
+    });
+    // Open pause record while paused
+    let mut pause_id: Option<i64> = None;
+
+    #[cfg(target_os = "linux")]
+    let mut logind = if options.logind {
+        power::LogindMonitor::spawn()
+            .map_err(|e| eprintln!("logind signals unavailable ({}), detecting suspend from the clock", e))
+            .ok()
+    } else {
+        None
+    };
+    #[cfg(target_os = "linux")]
+    let mut notifier = if options.sd_notify { systemd::Notifier::from_env() } else { None };
+    #[cfg(target_os = "linux")]
+    if let Some(notifier) = &mut notifier {
+        if let Err(e) = notifier.ready() {
+            eprintln!("sd_notify error: {}", e);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (options.logind, options.sd_notify);
 
     while running.load(Ordering::Relaxed) {
-        match watchers::collect_snapshot() {
-            Ok(snapshot) => {
-                if let Err(e) = heartbeat::process_heartbeat(&conn, &device.id, &snapshot) {
-                    eprintln!("heartbeat error: {}", e);
+        // Power transitions first, so the focused app isn't sampled on a lock screen
+        #[cfg(target_os = "linux")]
+        if let Some(monitor) = &mut logind {
+            for (at, event) in monitor.poll() {
+                if let Err(e) = recorder
+                    .release(conn)
+                    .and_then(|_| presence.handle(conn, &device.id, &at, event))
+                {
+                    eprintln!("power event error: {}", e);
+                }
+                if event == PowerEvent::Wake {
+                    clock.reset();
+                }
+            }
+        }
+
+        let check = clock.check();
+        if let Some((from, secs)) = check.suspended {
+            eprintln!("resumed after {:.0}s of suspend", secs);
+            return_hook.away();
+            if let Err(e) = recorder
+                .release(conn)
+                .and_then(|_| presence.record_suspend(conn, &device.id, &from, secs))
+            {
+                eprintln!("power event error: {}", e);
+            }
+        }
+        if let Some(jump) = check.jump {
+            eprintln!("wall clock jumped by {:+.0}s", jump);
+            presence.break_next_event();
+        }
+
+        let now = Utc::now();
+        let (paused, pause_expired) = {
+            let mut state = control::lock(&state);
+            if std::mem::take(&mut state.reload_requested) {
+                match WatcherRegistry::from_config(conn, afk_threshold(conn)) {
+                    Ok(reloaded) => {
+                        *registry = reloaded;
+                        eprintln!("reloaded watchers: {}", registry.names().join(", "));
+                    }
+                    Err(e) => eprintln!("reload failed, keeping current watchers: {}", e),
+                }
+                match Exclusions::from_config(conn) {
+                    Ok(reloaded) => exclusions = reloaded,
+                    Err(e) => eprintln!("reload failed, keeping current exclusions: {}", e),
+                }
+                if let Err(e) = recorder.reload(conn) {
+                    eprintln!("reload failed, keeping current redaction rules: {}", e);
+                }
+                if let Err(e) = return_hook.reload(conn) {
+                    eprintln!("reload failed, keeping current afk.on_return hook: {}", e);
                 }
             }
-            Err(e) => {
-                eprintln!("watcher error: {}", e);
+            let expired = state.paused && state.paused_until.is_some_and(|until| until <= now);
+            if expired {
+                state.paused = false;
+                state.paused_until = None;
+            }
+            (state.paused, expired)
+        };
+        if pause_expired {
+            eprintln!("pause ended, tracking resumed");
+            if let Err(e) = pause::clear(conn) {
+                eprintln!("pause error: {}", e);
+            }
+        }
+        if pause_id.is_some() && !paused {
+            // Don't stretch the event from before the pause across it
+            presence.break_next_event();
+        }
+        if let Err(e) = record_pause(conn, &device.id, &mut pause_id, paused, &now) {
+            eprintln!("pause error: {}", e);
+        }
+
+        if paused {
+            // Nothing is recorded while paused
+        } else if presence.is_active() {
+            let collected = registry.collect();
+            let mut state = control::lock(&state);
+            state.watchers = registry.health();
+            match collected {
+                Ok(snapshot) if exclusions.excludes(&snapshot) => {
+                    // Never written; and the event before it must not stretch across it
+                    presence.break_next_event();
+                    state.excluded = true;
+                    state.snapshot = None;
+                    state.snapshot_at = Some(now);
+                    state.current = None;
+                }
+                Ok(snapshot) => {
+                    state.excluded = false;
+                    let merge_gap = presence.take_merge_gap(HEARTBEAT_MERGE_GAP_SECS);
+                    match recorder.heartbeat(conn, &snapshot, &now, merge_gap) {
+                        Ok(Some(_)) => {
+                            state.last_heartbeat_at = Some(now);
+                            state.last_error = None;
+                            state.current = recorder.open_event().cloned();
+                            if snapshot.is_afk {
+                                return_hook.away();
+                            } else {
+                                match return_hook.back(conn, &device.id, &now) {
+                                    Ok(Some(period)) => eprintln!("back after {}, ran afk.on_return", period.time),
+                                    Ok(None) => {}
+                                    Err(e) => eprintln!("afk.on_return error: {}", e),
+                                }
+                            }
+                        }
+                        Ok(None) => state.last_heartbeat_at = Some(now),
+                        Err(e) => {
+                            eprintln!("heartbeat error: {}", e);
+                            state.last_error = Some(format!("heartbeat: {}", e));
+                        }
+                    }
+                    if let Some(playback) = &snapshot.media {
+                        if let Err(e) = recorder.media_heartbeat(conn, playback, &now) {
+                            eprintln!("media heartbeat error: {}", e);
+                        }
+                    }
+                    state.snapshot = Some(snapshot);
+                    state.snapshot_at = Some(now);
+                }
+                Err(e) => {
+                    eprintln!("watcher error: {}", e);
+                    state.last_error = Some(e.to_string());
+                }
+            }
+        } else {
+            return_hook.away();
+            if let Err(e) = recorder
+                .release(conn)
+                .and_then(|_| presence.tick(conn, &device.id, &Utc::now()))
+            {
+                eprintln!("heartbeat error: {}", e);
+            }
+        }
+
+        if last_flush.elapsed() >= options.flush_interval {
+            last_flush = Instant::now();
+            if let Err(e) = flush(conn, &mut recorder, coverage_id, pause_id, &Utc::now()) {
+                eprintln!("flush error: {}", e);
             }
         }
 
         // Sync tick
-        sync_counter += POLL_INTERVAL_SECS;
-        if sync_enabled && sync_counter >= sync_interval {
-            sync_counter = 0;
-            if let Err(e) = sync::client::push_events(&conn, &device) {
-                eprintln!("sync error: {}", e);
+        if let Some(interval) = options.sync_interval {
+            if last_sync.elapsed() >= interval {
+                last_sync = Instant::now();
+                if let Err(e) = recorder.flush(conn).and_then(|_| sync::client::push_events(conn, device)) {
+                    eprintln!("sync error: {}", e);
+                }
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(notifier) = &mut notifier {
+            if let Err(e) = notifier.watchdog() {
+                eprintln!("sd_notify error: {}", e);
             }
         }
 
         // Sleep in small increments to check running flag
-        for _ in 0..(POLL_INTERVAL_SECS * 10) {
-            if !running.load(Ordering::Relaxed) {
+        let wake = Instant::now() + options.tick;
+        while running.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            if now >= wake || control::lock(&state).reload_requested {
                 break;
             }
-            thread::sleep(Duration::from_millis(100));
+            thread::sleep((wake - now).min(Duration::from_millis(100)));
         }
     }
 
-    // Cleanup PID file
-    let _ = std::fs::remove_file(&pid_path);
-    eprintln!("timely daemon stopped");
+    #[cfg(target_os = "linux")]
+    if let Some(notifier) = &notifier {
+        let _ = notifier.notify("STOPPING=1");
+    }
+
+    let now = Utc::now();
+    recorder.flush(conn)?;
+    record_pause(conn, &device.id, &mut pause_id, false, &now)?;
+    coverage::stop_interval(conn, coverage_id, &now)?;
+    Ok(())
+}
+
+/// Write everything the loop only advances in memory between flushes.
+fn flush(
+    conn: &Connection,
+    recorder: &mut Recorder,
+    coverage_id: i64,
+    pause_id: Option<i64>,
+    now: &chrono::DateTime<Utc>,
+) -> Result<()> {
+    recorder.flush(conn)?;
+    let tx = conn.unchecked_transaction()?;
+    coverage::touch_interval(&tx, coverage_id, now)?;
+    if let Some(id) = pause_id {
+        coverage::extend_pause(&tx, id, now)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
 
+/// Open or close the pause record; `flush` extends it while it stays open.
+fn record_pause(
+    conn: &Connection,
+    device_id: &str,
+    pause_id: &mut Option<i64>,
+    paused: bool,
+    now: &chrono::DateTime<Utc>,
+) -> Result<()> {
+    match (*pause_id, paused) {
+        (None, true) => *pause_id = Some(coverage::start_pause(conn, device_id, now)?),
+        (Some(_), true) => {}
+        (Some(id), false) => {
+            coverage::extend_pause(conn, id, now)?;
+            *pause_id = None;
+        }
+        (None, false) => {}
+    }
     Ok(())
 }
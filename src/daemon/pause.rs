@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use crate::db::config_store;
+use crate::error::Result;
+
+const PAUSE_KEY: &str = "pause.until";
+const INDEFINITE: &str = "indefinite";
+
+/// A pause kept in config (`pause.until`), so it outlives the daemon that
+/// enforces it: a paused daemon that restarts stays paused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pause {
+    /// None pauses until `timely resume`
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Pause {
+    pub fn is_over(&self, now: &DateTime<Utc>) -> bool {
+        self.until.is_some_and(|until| until <= *now)
+    }
+}
+
+/// The configured pause, if one is in effect at `now`.
+pub fn load(conn: &Connection, now: &DateTime<Utc>) -> Result<Option<Pause>> {
+    Ok(get(conn)?.filter(|p| !p.is_over(now)))
+}
+
+/// The configured pause, even one that is already over.
+pub fn get(conn: &Connection) -> Result<Option<Pause>> {
+    Ok(config_store::get(conn, PAUSE_KEY)?.and_then(|value| match value.as_str() {
+        INDEFINITE => Some(Pause { until: None }),
+        until => DateTime::parse_from_rfc3339(until).ok().map(|until| Pause {
+            until: Some(until.with_timezone(&Utc)),
+        }),
+    }))
+}
+
+pub fn save(conn: &Connection, pause: &Pause) -> Result<()> {
+    let value = pause.until.map(|until| until.to_rfc3339()).unwrap_or_else(|| INDEFINITE.to_string());
+    config_store::set(conn, PAUSE_KEY, &value)
+}
+
+pub fn clear(conn: &Connection) -> Result<()> {
+    config_store::delete(conn, PAUSE_KEY)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_round_trip_and_expiry() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+        let now = Utc::now();
+        assert_eq!(load(&conn, &now).unwrap(), None);
+
+        save(&conn, &Pause { until: None }).unwrap();
+        assert_eq!(load(&conn, &now).unwrap(), Some(Pause { until: None }));
+
+        let until = now + chrono::Duration::minutes(30);
+        save(&conn, &Pause { until: Some(until) }).unwrap();
+        assert!(load(&conn, &now).unwrap().is_some());
+        assert_eq!(load(&conn, &(until + chrono::Duration::seconds(1))).unwrap(), None);
+
+        clear(&conn).unwrap();
+        assert_eq!(load(&conn, &now).unwrap(), None);
+    }
+}
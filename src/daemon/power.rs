@@ -0,0 +1,468 @@
+//! Sleep, lock and clock-jump awareness for the daemon loop.
+//!
+//! logind signals (Linux) and a suspend-aware clock comparison (everywhere)
+//! end the open focus event at the transition and record the gap as an
+//! explicit AFK event (`System Sleep` / `Screen Locked`) instead of letting the
+//! focused app absorb it.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use super::{heartbeat, pause};
+use crate::error::Result;
+use crate::types::WatcherSnapshot;
+
+/// App name of events covering system suspend.
+pub const SLEEP_APP: &str = "System Sleep";
+/// App name of events covering a locked session.
+pub const LOCKED_APP: &str = "Screen Locked";
+
+/// Unaccounted suspend shorter than this is ignored (scheduler jitter, a slow tick).
+const SUSPEND_DETECT_SECS: f64 = 10.0;
+/// Wall-clock steps (NTP, manual changes) smaller than this are ignored.
+const CLOCK_JUMP_SECS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    Sleep,
+    Wake,
+    Lock,
+    Unlock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Active,
+    Asleep,
+    Locked,
+}
+
+fn idle_snapshot(app: &str) -> WatcherSnapshot {
+    WatcherSnapshot {
+        app: app.to_string(),
+        is_afk: true,
+        ..Default::default()
+    }
+}
+
+/// Whether the user can be active right now, and the non-activity events
+/// recorded for the periods they can't.
+pub struct Presence {
+    state: State,
+    /// The next focus heartbeat must start a new event (clock step, pause)
+    break_next: bool,
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Presence {
+    pub fn new() -> Self {
+        Self {
+            state: State::Active,
+            break_next: false,
+        }
+    }
+
+    /// Focus should be sampled only while the session is awake and unlocked.
+    pub fn is_active(&self) -> bool {
+        self.state == State::Active
+    }
+
+    pub fn handle(&mut self, conn: &Connection, device_id: &str, at: &DateTime<Utc>, event: PowerEvent) -> Result<()> {
+        match (event, self.state) {
+            (PowerEvent::Sleep, State::Asleep) | (PowerEvent::Lock, State::Locked | State::Asleep) => {}
+            (PowerEvent::Sleep, _) => {
+                record(conn, device_id, SLEEP_APP, at, 0.0, 0.0)?;
+                self.state = State::Asleep;
+            }
+            (PowerEvent::Lock, State::Active) => {
+                record(conn, device_id, LOCKED_APP, at, 0.0, 0.0)?;
+                self.state = State::Locked;
+            }
+            (PowerEvent::Wake, State::Asleep) => {
+                self.extend(conn, device_id, SLEEP_APP, at)?;
+                // Resuming usually lands on the lock screen, which logind reports separately
+                self.state = State::Active;
+            }
+            (PowerEvent::Unlock, State::Locked) => {
+                self.extend(conn, device_id, LOCKED_APP, at)?;
+                self.state = State::Active;
+            }
+            (PowerEvent::Wake | PowerEvent::Unlock, _) => {}
+        }
+        Ok(())
+    }
+
+    /// Keep the open non-activity event running up to `now`.
+    pub fn tick(&mut self, conn: &Connection, device_id: &str, now: &DateTime<Utc>) -> Result<()> {
+        match self.state {
+            State::Locked => self.extend(conn, device_id, LOCKED_APP, now),
+            State::Asleep => self.extend(conn, device_id, SLEEP_APP, now),
+            State::Active => Ok(()),
+        }
+    }
+
+    /// Record a suspend noticed only after the fact (no logind, or macOS).
+    pub fn record_suspend(&mut self, conn: &Connection, device_id: &str, from: &DateTime<Utc>, secs: f64) -> Result<()> {
+        if self.state == State::Asleep {
+            // logind announced this one; its Wake closes the event
+            return Ok(());
+        }
+        if self.state == State::Locked {
+            self.extend(conn, device_id, LOCKED_APP, from)?;
+        }
+        record(conn, device_id, SLEEP_APP, from, secs, 0.0)?;
+        if self.state == State::Locked {
+            // Still locked after resume: continue with a fresh lock event
+            let resumed = *from + chrono::Duration::milliseconds((secs * 1000.0) as i64);
+            record(conn, device_id, LOCKED_APP, &resumed, 0.0, 0.0)?;
+        }
+        Ok(())
+    }
+
+    /// Don't let the open event stretch across a discontinuity, such as a
+    /// stepped wall clock or a pause.
+    pub fn break_next_event(&mut self) {
+        self.break_next = true;
+    }
+
+    /// Merge gap for the next focus heartbeat (0 right after a discontinuity).
+    pub fn take_merge_gap(&mut self, default: f64) -> f64 {
+        if std::mem::take(&mut self.break_next) {
+            0.0
+        } else {
+            default
+        }
+    }
+
+    fn extend(&self, conn: &Connection, device_id: &str, app: &str, at: &DateTime<Utc>) -> Result<()> {
+        record(conn, device_id, app, at, 0.0, f64::INFINITY)
+    }
+}
+
+/// Write a sleep or lock heartbeat. These carry no title or URL, so they skip
+/// exclusions and redaction; only a pause stops them.
+fn record(conn: &Connection, device_id: &str, app: &str, at: &DateTime<Utc>, secs: f64, merge_gap: f64) -> Result<()> {
+    if pause::load(conn, at)?.is_none() {
+        heartbeat::record_heartbeat_at(conn, device_id, &idle_snapshot(app), at, secs, merge_gap)?;
+    }
+    Ok(())
+}
+
+/// Follows logind's `PrepareForSleep` and the session's `Lock`/`Unlock`
+/// signals through `gdbus monitor` on the system bus.
+#[cfg(target_os = "linux")]
+pub struct LogindMonitor {
+    child: std::process::Child,
+    events: std::sync::mpsc::Receiver<(DateTime<Utc>, PowerEvent)>,
+}
+
+#[cfg(target_os = "linux")]
+impl LogindMonitor {
+    pub fn spawn() -> Result<Self> {
+        use std::io::{BufRead, BufReader};
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new("gdbus")
+            .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        // Lock/Unlock are per session; only follow ours when we know it
+        let session_path = std::env::var("XDG_SESSION_ID").ok().map(|id| session_object_path(&id));
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (tx, events) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if let Some(event) = parse_monitor_line(&line, session_path.as_deref()) {
+                    // Stamp on receipt: the loop may only see it after resume
+                    if tx.send((Utc::now(), event)).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { child, events })
+    }
+
+    /// Events received since the last poll, oldest first.
+    pub fn poll(&mut self) -> Vec<(DateTime<Utc>, PowerEvent)> {
+        self.events.try_iter().collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for LogindMonitor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// D-Bus object path of a logind session: `2` -> `/org/freedesktop/login1/session/_32`.
+/// Bytes other than ASCII alphanumerics, and a leading digit, are escaped as `_xx`.
+pub fn session_object_path(id: &str) -> String {
+    let mut path = String::from("/org/freedesktop/login1/session/");
+    for (i, b) in id.bytes().enumerate() {
+        if b.is_ascii_alphabetic() || (i > 0 && b.is_ascii_digit()) {
+            path.push(b as char);
+        } else {
+            path.push_str(&format!("_{:02x}", b));
+        }
+    }
+    path
+}
+
+/// Parse one `gdbus monitor` line, e.g.
+/// `/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)`.
+pub fn parse_monitor_line(line: &str, session_path: Option<&str>) -> Option<PowerEvent> {
+    let (path, rest) = line.split_once(": ")?;
+    let (member, args) = rest.split_once(' ').unwrap_or((rest, ""));
+    match member {
+        "org.freedesktop.login1.Manager.PrepareForSleep" => {
+            if args.contains("true") {
+                Some(PowerEvent::Sleep)
+            } else {
+                Some(PowerEvent::Wake)
+            }
+        }
+        "org.freedesktop.login1.Session.Lock" | "org.freedesktop.login1.Session.Unlock" => {
+            if session_path.is_some_and(|own| own != path) {
+                return None;
+            }
+            if member.ends_with(".Lock") {
+                Some(PowerEvent::Lock)
+            } else {
+                Some(PowerEvent::Unlock)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// What changed between two loop iterations, beyond the time that should have passed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockCheck {
+    /// Seconds the machine spent suspended, with the wall time it started
+    pub suspended: Option<(DateTime<Utc>, f64)>,
+    /// Seconds the wall clock was stepped by (negative: backwards)
+    pub jump: Option<f64>,
+}
+
+/// Compares the wall clock, a monotonic clock that stops during suspend
+/// (`Instant`) and one that keeps counting through it.
+pub struct ClockWatch {
+    wall: DateTime<Utc>,
+    mono: Instant,
+    boot: Duration,
+}
+
+impl Default for ClockWatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockWatch {
+    pub fn new() -> Self {
+        Self {
+            wall: Utc::now(),
+            mono: Instant::now(),
+            boot: boot_clock(),
+        }
+    }
+
+    /// Forget the interval so far (e.g. a suspend already recorded from logind).
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn check(&mut self) -> ClockCheck {
+        let (wall, mono, boot) = (Utc::now(), Instant::now(), boot_clock());
+        let result = compare_clocks(
+            (wall - self.wall).num_milliseconds() as f64 / 1000.0,
+            mono.duration_since(self.mono).as_secs_f64(),
+            boot.saturating_sub(self.boot).as_secs_f64(),
+            &wall,
+        );
+        *self = Self { wall, mono, boot };
+        result
+    }
+}
+
+fn compare_clocks(wall_secs: f64, mono_secs: f64, boot_secs: f64, now: &DateTime<Utc>) -> ClockCheck {
+    let suspended = boot_secs - mono_secs;
+    let jump = wall_secs - boot_secs;
+    ClockCheck {
+        suspended: (suspended > SUSPEND_DETECT_SECS)
+            .then(|| (*now - chrono::Duration::milliseconds((suspended * 1000.0) as i64), suspended)),
+        jump: (jump.abs() > CLOCK_JUMP_SECS).then_some(jump),
+    }
+}
+
+/// Monotonic time including suspend: CLOCK_BOOTTIME on Linux, CLOCK_MONOTONIC
+/// on macOS (which, unlike `Instant`, keeps counting while asleep).
+fn boot_clock() -> Duration {
+    #[cfg(target_os = "linux")]
+    let clock = libc::CLOCK_BOOTTIME;
+    #[cfg(not(target_os = "linux"))]
+    let clock = libc::CLOCK_MONOTONIC;
+
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: ts is a valid, writable timespec
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::{categories as db_categories, events};
+
+    fn setup_db() -> (tempfile::NamedTempFile, Connection) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = db::open_db(tmp.path()).unwrap();
+        db_categories::seed_builtin_categories(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO devices (id, name, platform, last_sync) VALUES ('dev', 'test', 'linux', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        (tmp, conn)
+    }
+
+    fn all_events(conn: &Connection) -> Vec<(String, f64, bool)> {
+        let from = Utc::now() - chrono::Duration::days(1);
+        let to = Utc::now() + chrono::Duration::days(1);
+        let mut list = events::query_events(conn, &from, &to, None).unwrap();
+        list.sort_by_key(|e| e.id);
+        list.into_iter().map(|e| (e.app, e.duration, e.is_afk)).collect()
+    }
+
+    #[test]
+    fn test_sleep_closes_focus_event() {
+        let (_tmp, conn) = setup_db();
+        let t0 = Utc::now() - chrono::Duration::hours(1);
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let code = WatcherSnapshot {
+            app: "Code".into(),
+            title: "main.rs".into(),
+            ..Default::default()
+        };
+        let mut presence = Presence::new();
+
+        heartbeat::process_heartbeat_at(&conn, "dev", &code, &at(0), 0.0, 65.0).unwrap();
+        heartbeat::process_heartbeat_at(&conn, "dev", &code, &at(30), 0.0, 65.0).unwrap();
+        presence.handle(&conn, "dev", &at(32), PowerEvent::Sleep).unwrap();
+        assert!(!presence.is_active());
+        presence.handle(&conn, "dev", &at(62), PowerEvent::Wake).unwrap();
+        assert!(presence.is_active());
+        // Same app as before the (short) suspend, but it must not span it
+        heartbeat::process_heartbeat_at(&conn, "dev", &code, &at(65), 0.0, 65.0).unwrap();
+
+        assert_eq!(
+            all_events(&conn),
+            vec![
+                ("Code".to_string(), 30.0, false),
+                (SLEEP_APP.to_string(), 30.0, true),
+                ("Code".to_string(), 0.0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lock_records_non_activity() {
+        let (_tmp, conn) = setup_db();
+        let t0 = Utc::now() - chrono::Duration::hours(1);
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let mut presence = Presence::new();
+
+        presence.handle(&conn, "dev", &at(0), PowerEvent::Lock).unwrap();
+        presence.tick(&conn, "dev", &at(5)).unwrap();
+        presence.tick(&conn, "dev", &at(600)).unwrap();
+        presence.handle(&conn, "dev", &at(900), PowerEvent::Unlock).unwrap();
+        presence.tick(&conn, "dev", &at(905)).unwrap();
+
+        assert_eq!(all_events(&conn), vec![(LOCKED_APP.to_string(), 900.0, true)]);
+    }
+
+    #[test]
+    fn test_suspend_detected_while_locked() {
+        let (_tmp, conn) = setup_db();
+        let t0 = Utc::now() - chrono::Duration::hours(1);
+        let at = |secs: i64| t0 + chrono::Duration::seconds(secs);
+        let mut presence = Presence::new();
+
+        presence.handle(&conn, "dev", &at(0), PowerEvent::Lock).unwrap();
+        presence.record_suspend(&conn, "dev", &at(60), 3600.0).unwrap();
+        presence.tick(&conn, "dev", &at(3700)).unwrap();
+
+        assert_eq!(
+            all_events(&conn),
+            vec![
+                (LOCKED_APP.to_string(), 60.0, true),
+                (SLEEP_APP.to_string(), 3600.0, true),
+                (LOCKED_APP.to_string(), 40.0, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_monitor_line() {
+        let own = session_object_path("2");
+        assert_eq!(own, "/org/freedesktop/login1/session/_32");
+        assert_eq!(
+            parse_monitor_line("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)", None),
+            Some(PowerEvent::Sleep)
+        );
+        assert_eq!(
+            parse_monitor_line("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (false,)", None),
+            Some(PowerEvent::Wake)
+        );
+        assert_eq!(
+            parse_monitor_line("/org/freedesktop/login1/session/_32: org.freedesktop.login1.Session.Lock ()", Some(&own)),
+            Some(PowerEvent::Lock)
+        );
+        assert_eq!(
+            parse_monitor_line("/org/freedesktop/login1/session/_32: org.freedesktop.login1.Session.Unlock ()", None),
+            Some(PowerEvent::Unlock)
+        );
+        // Another user's session
+        assert_eq!(
+            parse_monitor_line("/org/freedesktop/login1/session/c1: org.freedesktop.login1.Session.Lock ()", Some(&own)),
+            None
+        );
+        assert_eq!(
+            parse_monitor_line("/org/freedesktop/login1: org.freedesktop.login1.Manager.SessionNew ('3', objectpath '/x')", None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compare_clocks() {
+        let now = Utc::now();
+        // Normal tick
+        assert_eq!(compare_clocks(5.0, 5.0, 5.0, &now), ClockCheck { suspended: None, jump: None });
+
+        // Suspended for an hour: Instant stopped, the boot clock and wall clock didn't
+        let check = compare_clocks(3605.0, 5.0, 3605.0, &now);
+        let (from, secs) = check.suspended.unwrap();
+        assert_eq!(secs, 3600.0);
+        assert_eq!(from, now - chrono::Duration::seconds(3600));
+        assert_eq!(check.jump, None);
+
+        // NTP stepped the clock back two minutes
+        assert_eq!(compare_clocks(-115.0, 5.0, 5.0, &now).jump, Some(-120.0));
+    }
+}
@@ -0,0 +1,305 @@
+//! The daemon's write path.
+//!
+//! The open focus and media events are kept in memory and extended there on
+//! each tick. The database only sees a write when the activity changes, on
+//! `flush` (every `FLUSH_INTERVAL_SECS` and at shutdown) and before other code
+//! writes events for the device; each of these is one transaction.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use super::engagement::Engagement;
+use super::heartbeat;
+use crate::db::{events, media};
+use crate::error::Result;
+use crate::normalize::TitleNormalizer;
+use crate::redact::Redactor;
+use crate::types::{Event, MediaEvent, MediaPlayback, WatcherSnapshot};
+
+pub struct Recorder {
+    device_id: String,
+    redactor: Redactor,
+    normalizer: TitleNormalizer,
+    engagement: Engagement,
+    /// The device's last event as it will be once flushed
+    open: Option<Event>,
+    /// `open` has changes the database doesn't have yet
+    dirty: bool,
+    /// Duration of `open` as stored, to notice when something else rewrote the row
+    stored_duration: f64,
+    open_media: Option<MediaEvent>,
+    media_dirty: bool,
+}
+
+impl Recorder {
+    pub fn new(conn: &Connection, device_id: &str) -> Result<Self> {
+        Ok(Self {
+            device_id: device_id.to_string(),
+            redactor: Redactor::from_config(conn)?,
+            normalizer: TitleNormalizer::from_config(conn)?,
+            engagement: Engagement::from_config(conn)?,
+            open: None,
+            dirty: false,
+            stored_duration: 0.0,
+            open_media: None,
+            media_dirty: false,
+        })
+    }
+
+    /// Pick up changed redaction and normalization rules and engaged categories.
+    pub fn reload(&mut self, conn: &Connection) -> Result<()> {
+        self.redactor = Redactor::from_config(conn)?;
+        self.normalizer = TitleNormalizer::from_config(conn)?;
+        self.engagement = Engagement::from_config(conn)?;
+        Ok(())
+    }
+
+    /// The open focus event, including extensions not flushed yet.
+    pub fn open_event(&self) -> Option<&Event> {
+        self.open.as_ref()
+    }
+
+    /// Record a focus heartbeat observed at `at`; see `heartbeat::process_heartbeat_at`.
+    /// Returns the id of the event it belongs to, or None for an empty snapshot.
+    pub fn heartbeat(
+        &mut self,
+        conn: &Connection,
+        snapshot: &WatcherSnapshot,
+        at: &DateTime<Utc>,
+        merge_gap: f64,
+    ) -> Result<Option<i64>> {
+        if snapshot.app.is_empty() {
+            return Ok(None);
+        }
+        let mut snapshot = snapshot.clone();
+        self.redactor.redact(&mut snapshot);
+        self.normalizer.normalize(&mut snapshot);
+        self.engagement.apply(conn, &mut snapshot)?;
+
+        if let Some(open) = &mut self.open {
+            let elapsed = seconds_since(&open.timestamp, at);
+            if heartbeat::same_activity(open, &snapshot) && elapsed >= 0.0 && elapsed < open.duration + merge_gap {
+                let id = open.id;
+                open.duration = open.duration.max(elapsed);
+                self.dirty = true;
+                if let Some(status) = snapshot.exit_status.filter(|s| open.exit_status != Some(*s)) {
+                    // A finished command is written right away
+                    open.exit_status = Some(status);
+                    self.flush(conn)?;
+                }
+                return Ok(Some(id));
+            }
+        }
+
+        // The activity changed: settle the open event and start the next one together
+        let tx = conn.unchecked_transaction()?;
+        self.write_pending(&tx)?;
+        let id = heartbeat::record_heartbeat_at(&tx, &self.device_id, &snapshot, at, 0.0, merge_gap)?;
+        let open = events::get_last_event(&tx, &self.device_id)?;
+        tx.commit()?;
+        self.stored_duration = open.as_ref().map_or(0.0, |e| e.duration);
+        self.open = open;
+        self.dirty = false;
+        self.media_dirty = false;
+        Ok(id)
+    }
+
+    /// Record that `playback` was playing at `at`; see `heartbeat::process_media_heartbeat_at`.
+    pub fn media_heartbeat(&mut self, conn: &Connection, playback: &MediaPlayback, at: &DateTime<Utc>) -> Result<i64> {
+        if let Some(open) = &mut self.open_media {
+            let elapsed = seconds_since(&open.timestamp, at);
+            if heartbeat::same_media(open, playback)
+                && elapsed >= 0.0
+                && elapsed < open.duration + crate::config::HEARTBEAT_MERGE_GAP_SECS
+            {
+                open.duration = open.duration.max(elapsed);
+                self.media_dirty = true;
+                return Ok(open.id);
+            }
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        self.write_pending(&tx)?;
+        let id = heartbeat::process_media_heartbeat_at(
+            &tx,
+            &self.device_id,
+            playback,
+            at,
+            crate::config::HEARTBEAT_MERGE_GAP_SECS,
+        )?;
+        let open = media::get_last_media_event(&tx, &self.device_id)?;
+        tx.commit()?;
+        self.open_media = open;
+        self.dirty = false;
+        self.media_dirty = false;
+        Ok(id)
+    }
+
+    /// Write pending extensions in one transaction.
+    pub fn flush(&mut self, conn: &Connection) -> Result<()> {
+        if !self.dirty && !self.media_dirty {
+            return Ok(());
+        }
+        let tx = conn.unchecked_transaction()?;
+        self.write_pending(&tx)?;
+        tx.commit()?;
+        self.dirty = false;
+        self.media_dirty = false;
+        Ok(())
+    }
+
+    /// Flush, and stop assuming the open focus event is the device's last:
+    /// called before something else (sleep and lock tracking) writes events.
+    pub fn release(&mut self, conn: &Connection) -> Result<()> {
+        self.flush(conn)?;
+        self.open = None;
+        Ok(())
+    }
+
+    fn write_pending(&mut self, conn: &Connection) -> Result<()> {
+        if let Some(open) = self.open.as_ref().filter(|_| self.dirty) {
+            let extended = events::extend_event_if_unchanged(
+                conn,
+                open.id,
+                &open.app,
+                self.stored_duration,
+                open.duration,
+            )?;
+            if extended {
+                if let Some(status) = open.exit_status {
+                    events::set_exit_status(conn, open.id, status)?;
+                }
+                self.stored_duration = open.duration;
+            } else {
+                // Relabeled or split meanwhile (`timely afk label`); the next
+                // heartbeat starts from what the database has now
+                self.open = None;
+            }
+        }
+        if let Some(open) = self.open_media.as_ref().filter(|_| self.media_dirty) {
+            media::extend_media_event(conn, open.id, open.duration)?;
+        }
+        Ok(())
+    }
+}
+
+fn seconds_since(start: &DateTime<Utc>, at: &DateTime<Utc>) -> f64 {
+    (*at - *start).num_milliseconds() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::categories as db_categories;
+
+    fn setup_db() -> (tempfile::NamedTempFile, Connection) {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = db::open_db(tmp.path()).unwrap();
+        db_categories::seed_builtin_categories(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO devices (id, name, platform, last_sync) VALUES ('dev', 'test', 'linux', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        (tmp, conn)
+    }
+
+    fn window(app: &str) -> WatcherSnapshot {
+        WatcherSnapshot {
+            app: app.to_string(),
+            title: "main.rs".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn stored_duration(conn: &Connection, id: i64) -> f64 {
+        conn.query_row("SELECT duration FROM events WHERE id = ?1", [id], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_extensions_are_held_until_flush() {
+        let (_tmp, conn) = setup_db();
+        let mut recorder = Recorder::new(&conn, "dev").unwrap();
+        let start = Utc::now();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        let id = recorder.heartbeat(&conn, &window("Code"), &at(0), 65.0).unwrap().unwrap();
+        assert_eq!(recorder.heartbeat(&conn, &window("Code"), &at(5), 65.0).unwrap(), Some(id));
+        assert_eq!(recorder.heartbeat(&conn, &window("Code"), &at(10), 65.0).unwrap(), Some(id));
+        assert_eq!(stored_duration(&conn, id), 0.0);
+        assert_eq!(recorder.open_event().unwrap().duration, 10.0);
+
+        recorder.flush(&conn).unwrap();
+        assert_eq!(stored_duration(&conn, id), 10.0);
+
+        // A change settles the open event along with the new one
+        recorder.heartbeat(&conn, &window("Code"), &at(15), 65.0).unwrap();
+        let next = recorder.heartbeat(&conn, &window("Firefox"), &at(20), 65.0).unwrap().unwrap();
+        assert_ne!(next, id);
+        assert_eq!(stored_duration(&conn, id), 15.0);
+    }
+
+    #[test]
+    fn test_label_while_afk_event_is_open() {
+        let (_tmp, conn) = setup_db();
+        let mut recorder = Recorder::new(&conn, "dev").unwrap();
+        let start = Utc::now() - chrono::Duration::minutes(30);
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+        let away = WatcherSnapshot {
+            is_afk: true,
+            ..window("Code")
+        };
+
+        let id = recorder.heartbeat(&conn, &away, &at(0), 65.0).unwrap().unwrap();
+        for secs in (30..=600).step_by(30) {
+            recorder.heartbeat(&conn, &away, &at(secs), 65.0).unwrap();
+        }
+        recorder.flush(&conn).unwrap();
+        recorder.heartbeat(&conn, &away, &at(630), 65.0).unwrap();
+
+        // Label part of the period the daemon still holds open
+        let range = crate::afk::LabelTarget::Range(at(60), at(300));
+        crate::afk::label(&conn, "dev", &range, "meetings", None).unwrap();
+        recorder.flush(&conn).unwrap();
+        assert_eq!(stored_duration(&conn, id), 60.0);
+        assert!(recorder.open_event().is_none());
+
+        // Still away: the AFK part after the label carries on
+        let rest = recorder.heartbeat(&conn, &away, &at(660), 65.0).unwrap().unwrap();
+        assert_ne!(rest, id);
+        let last = events::get_last_event(&conn, "dev").unwrap().unwrap();
+        assert_eq!((last.id, last.timestamp, last.is_afk), (rest, at(300), true));
+        assert_eq!(stored_duration(&conn, id), 60.0);
+        let offline: f64 = conn
+            .query_row("SELECT duration FROM events WHERE app = 'Offline'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(offline, 240.0);
+    }
+
+    #[test]
+    fn test_release_lets_others_write() {
+        let (_tmp, conn) = setup_db();
+        let mut recorder = Recorder::new(&conn, "dev").unwrap();
+        let start = Utc::now();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        let id = recorder.heartbeat(&conn, &window("Code"), &at(0), 65.0).unwrap().unwrap();
+        recorder.heartbeat(&conn, &window("Code"), &at(5), 65.0).unwrap();
+        recorder.release(&conn).unwrap();
+        assert_eq!(stored_duration(&conn, id), 5.0);
+
+        let locked = WatcherSnapshot {
+            app: "Screen Locked".to_string(),
+            is_afk: true,
+            ..Default::default()
+        };
+        heartbeat::process_heartbeat_at(&conn, "dev", &locked, &at(6), 30.0, 0.0).unwrap();
+
+        let after = recorder.heartbeat(&conn, &window("Code"), &at(40), 65.0).unwrap().unwrap();
+        assert_ne!(after, id);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 3);
+    }
+}
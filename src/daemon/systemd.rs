@@ -0,0 +1,237 @@
+//! Running the daemon as a systemd user service (Linux).
+//!
+//! `timely daemon start` writes `~/.config/systemd/user/timely.service` and
+//! drives `systemctl --user`; the daemon reports readiness and liveness over
+//! the sd_notify protocol so systemd restarts it if the loop hangs.
+
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::config::{SYSTEMD_UNIT_NAME, SYSTEMD_WATCHDOG_SECS};
+use crate::error::{Result, TimelyError};
+use crate::types::ServiceStatus;
+
+/// Session variables the watchers need to reach the display server. The user
+/// manager doesn't always have them, so `start` imports the ones that are set.
+const SESSION_ENV: &[&str] = &[
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XAUTHORITY",
+    "SWAYSOCK",
+    "HYPRLAND_INSTANCE_SIGNATURE",
+    "XDG_CURRENT_DESKTOP",
+];
+
+/// The unit file running `exe daemon run`.
+pub fn unit_file(exe: &Path) -> String {
+    format!(
+        "[Unit]
+Description=timely activity tracker
+After=graphical-session.target
+
+[Service]
+Type=notify
+NotifyAccess=main
+ExecStart={exe} daemon run
+Restart=on-failure
+RestartSec=5
+WatchdogSec={watchdog}
+
+[Install]
+WantedBy=default.target
+",
+        exe = quote_exec_arg(&exe.display().to_string()),
+        watchdog = SYSTEMD_WATCHDOG_SECS,
+    )
+}
+
+/// Quote a path for ExecStart, escaping systemd's `%` specifiers.
+fn quote_exec_arg(arg: &str) -> String {
+    let escaped = arg.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+/// `systemctl --user`, or a stand-in program with the same interface.
+pub struct Systemctl {
+    program: PathBuf,
+}
+
+impl Default for Systemctl {
+    fn default() -> Self {
+        Self::new("systemctl")
+    }
+}
+
+impl Systemctl {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self { program: program.into() }
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new(&self.program)
+            .arg("--user")
+            .args(args)
+            .output()
+            .map_err(|e| TimelyError::Generic(format!("Cannot run systemctl: {}", e)))?;
+        if !output.status.success() {
+            return Err(TimelyError::Generic(format!(
+                "systemctl --user {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Write the unit for `exe` to `unit_path`, then enable and start it.
+    pub fn start(&self, unit_path: &Path, exe: &Path) -> Result<()> {
+        std::fs::write(unit_path, unit_file(exe))?;
+        self.run(&["daemon-reload"])?;
+
+        let session_env: Vec<&str> = SESSION_ENV
+            .iter()
+            .copied()
+            .filter(|var| std::env::var_os(var).is_some())
+            .collect();
+        if !session_env.is_empty() {
+            let mut args = vec!["import-environment"];
+            args.extend(&session_env);
+            self.run(&args)?;
+        }
+
+        self.run(&["enable", "--now", SYSTEMD_UNIT_NAME])?;
+        Ok(())
+    }
+
+    /// Stop the service. It stays enabled, so it starts again at next login.
+    pub fn stop(&self) -> Result<()> {
+        self.run(&["stop", SYSTEMD_UNIT_NAME])?;
+        Ok(())
+    }
+
+    pub fn status(&self) -> Result<ServiceStatus> {
+        let output = self.run(&[
+            "show",
+            SYSTEMD_UNIT_NAME,
+            "--property=ActiveState,SubState,MainPID,NRestarts",
+        ])?;
+        let property = |name: &str| {
+            output
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+                .map(str::trim)
+        };
+        Ok(ServiceStatus {
+            unit: SYSTEMD_UNIT_NAME.to_string(),
+            active_state: property("ActiveState").unwrap_or("unknown").to_string(),
+            sub_state: property("SubState").unwrap_or("unknown").to_string(),
+            main_pid: property("MainPID").and_then(|p| p.parse().ok()).filter(|pid| *pid != 0),
+            restarts: property("NRestarts").and_then(|n| n.parse().ok()),
+        })
+    }
+}
+
+/// Sends sd_notify messages to the service manager.
+pub struct Notifier {
+    socket: UnixDatagram,
+    addr: SocketAddr,
+    /// Half of systemd's watchdog timeout, when the watchdog is enabled
+    watchdog_every: Option<Duration>,
+    last_ping: Instant,
+}
+
+impl Notifier {
+    /// The notifier systemd asked for via `NOTIFY_SOCKET` / `WATCHDOG_USEC`,
+    /// or None when not started by systemd.
+    pub fn from_env() -> Option<Self> {
+        let socket = std::env::var("NOTIFY_SOCKET").ok()?;
+        let for_us = std::env::var("WATCHDOG_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_none_or(|pid| pid == std::process::id());
+        let watchdog = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|_| for_us)
+            .map(Duration::from_micros);
+        Self::connect(&socket, watchdog)
+            .map_err(|e| eprintln!("sd_notify unavailable: {}", e))
+            .ok()
+    }
+
+    /// Notify on `socket` (a path, or `@name` for an abstract socket).
+    pub fn connect(socket: &str, watchdog: Option<Duration>) -> std::io::Result<Self> {
+        let addr = match socket.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(socket)?,
+        };
+        Ok(Self {
+            socket: UnixDatagram::unbound()?,
+            addr,
+            watchdog_every: watchdog.map(|timeout| timeout / 2),
+            last_ping: Instant::now(),
+        })
+    }
+
+    pub fn notify(&self, state: &str) -> std::io::Result<()> {
+        self.socket.send_to_addr(state.as_bytes(), &self.addr)?;
+        Ok(())
+    }
+
+    pub fn ready(&mut self) -> std::io::Result<()> {
+        self.last_ping = Instant::now();
+        self.notify("READY=1")
+    }
+
+    /// Ping the watchdog if it is due. Called once per loop iteration, so a
+    /// loop that stops making progress stops pinging.
+    pub fn watchdog(&mut self) -> std::io::Result<()> {
+        match self.watchdog_every {
+            Some(every) if self.last_ping.elapsed() >= every => {
+                self.last_ping = Instant::now();
+                self.notify("WATCHDOG=1")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let recv = || {
+            let mut buf = [0u8; 64];
+            let n = server.recv(&mut buf).unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        };
+
+        let mut notifier = Notifier::connect(path.to_str().unwrap(), Some(Duration::ZERO)).unwrap();
+        notifier.ready().unwrap();
+        assert_eq!(recv(), "READY=1");
+        notifier.watchdog().unwrap();
+        assert_eq!(recv(), "WATCHDOG=1");
+    }
+
+    #[test]
+    fn test_watchdog_not_due() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+        server.set_nonblocking(true).unwrap();
+
+        let mut notifier = Notifier::connect(path.to_str().unwrap(), Some(Duration::from_secs(60))).unwrap();
+        notifier.watchdog().unwrap();
+        let mut buf = [0u8; 64];
+        assert!(server.recv(&mut buf).is_err(), "no ping before half the timeout");
+    }
+}
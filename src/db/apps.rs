@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+use crate::categories::builtin::BUILTIN_APP_ALIASES;
+use crate::error::{Result, TimelyError};
+use crate::types::{AppAlias, AppInfo};
+
+/// Join that resolves `e.app` to its canonical name; use with `CANONICAL_APP`.
+pub const ALIAS_JOIN: &str = "LEFT JOIN app_aliases al ON al.alias = e.app";
+/// An event's app under its canonical name.
+pub const CANONICAL_APP: &str = "COALESCE(al.canonical, e.app)";
+
+/// Make `alias` another name for `canonical`. Aliases of `alias` move to
+/// `canonical`, and an aliased `canonical` resolves to its own canonical name,
+/// so every name points straight at the name it is grouped under.
+pub fn set_alias(conn: &Connection, alias: &str, canonical: &str) -> Result<AppAlias> {
+    let canonical = canonical_name(conn, canonical)?;
+    if alias.eq_ignore_ascii_case(&canonical) {
+        return Err(TimelyError::Generic(format!("'{}' can't be an alias of itself", alias)));
+    }
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE app_aliases SET canonical = ?1 WHERE canonical = ?2 COLLATE NOCASE",
+        rusqlite::params![canonical, alias],
+    )?;
+    tx.execute(
+        "INSERT INTO app_aliases (alias, canonical) VALUES (?1, ?2)
+         ON CONFLICT(alias) DO UPDATE SET canonical = excluded.canonical",
+        rusqlite::params![alias, canonical],
+    )?;
+    tx.commit()?;
+    Ok(AppAlias {
+        alias: alias.to_string(),
+        canonical,
+    })
+}
+
+/// Builtin aliases can be pointed elsewhere with `set_alias` but not removed;
+/// seeding would bring them back.
+pub fn remove_alias(conn: &Connection, alias: &str) -> Result<bool> {
+    if BUILTIN_APP_ALIASES.iter().any(|(builtin, _)| builtin.eq_ignore_ascii_case(alias)) {
+        return Err(TimelyError::Generic(format!(
+            "'{}' is a builtin alias; alias it to another name instead",
+            alias
+        )));
+    }
+    let affected = conn.execute("DELETE FROM app_aliases WHERE alias = ?1", rusqlite::params![alias])?;
+    Ok(affected > 0)
+}
+
+pub fn list_aliases(conn: &Connection) -> Result<Vec<AppAlias>> {
+    let mut stmt = conn.prepare("SELECT alias, canonical FROM app_aliases ORDER BY canonical, alias")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(AppAlias {
+            alias: row.get(0)?,
+            canonical: row.get(1)?,
+        })
+    })?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Lowercased alias → lowercased canonical name, for case-insensitive matching.
+pub fn alias_map(conn: &Connection) -> Result<HashMap<String, String>> {
+    Ok(list_aliases(conn)?
+        .into_iter()
+        .map(|a| (a.alias.to_lowercase(), a.canonical.to_lowercase()))
+        .collect())
+}
+
+pub fn canonical_name(conn: &Connection, app: &str) -> Result<String> {
+    let canonical = conn
+        .query_row(
+            "SELECT canonical FROM app_aliases WHERE alias = ?1",
+            rusqlite::params![app],
+            |row| row.get(0),
+        )
+        .ok();
+    Ok(canonical.unwrap_or_else(|| app.to_string()))
+}
+
+/// Every app name recorded, with its canonical name and the identity it was last seen with.
+pub fn list_apps(conn: &Connection) -> Result<Vec<AppInfo>> {
+    let sql = format!(
+        "SELECT e.app, {canonical}, e.app_id, e.app_path, MAX(e.timestamp), COUNT(*)
+         FROM events e
+         {join}
+         WHERE e.app != ''
+         GROUP BY e.app
+         ORDER BY {canonical}, e.app",
+        canonical = CANONICAL_APP,
+        join = ALIAS_JOIN,
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(AppInfo {
+            app: row.get(0)?,
+            canonical: row.get(1)?,
+            app_id: row.get(2)?,
+            app_path: row.get(3)?,
+            last_seen: row.get(4)?,
+            events: row.get(5)?,
+        })
+    })?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_point_at_the_final_name() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+
+        set_alias(&conn, "Code", "VS Code").unwrap();
+        set_alias(&conn, "Visual Studio Code", "code").unwrap();
+        assert_eq!(canonical_name(&conn, "visual studio code").unwrap(), "VS Code");
+
+        // Renaming the group moves its aliases along
+        set_alias(&conn, "VS Code", "Visual Studio Code").unwrap_err();
+        set_alias(&conn, "VS Code", "Codium").unwrap();
+        let aliases = list_aliases(&conn).unwrap();
+        assert!(aliases.iter().all(|a| a.canonical == "Codium"));
+        assert_eq!(aliases.len(), 3);
+
+        assert!(remove_alias(&conn, "VISUAL STUDIO CODE").unwrap());
+        assert_eq!(canonical_name(&conn, "Visual Studio Code").unwrap(), "Visual Studio Code");
+        // Builtin aliases only move
+        assert!(remove_alias(&conn, "code").is_err());
+    }
+}
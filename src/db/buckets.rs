@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Platform recorded for devices backing ActivityWatch buckets.
+pub const BUCKET_PLATFORM: &str = "activitywatch";
+
+/// An ActivityWatch bucket. Each bucket records onto its own device, named
+/// after the bucket id, so its events can be filtered with `--device`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+    pub id: String,
+    #[serde(skip)]
+    pub device_id: String,
+    #[serde(rename = "type")]
+    pub bucket_type: String,
+    pub client: String,
+    pub hostname: String,
+    pub created: DateTime<Utc>,
+    pub last_updated: Option<DateTime<Utc>>,
+    /// `data` of the most recent heartbeat
+    #[serde(skip)]
+    pub last_data: Option<serde_json::Value>,
+}
+
+/// Create a bucket and its device. Returns false if the bucket already exists.
+pub fn create_bucket(
+    conn: &Connection,
+    id: &str,
+    bucket_type: &str,
+    client: &str,
+    hostname: &str,
+) -> Result<bool> {
+    if get_bucket(conn, id)?.is_some() {
+        return Ok(false);
+    }
+
+    let device_id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO devices (id, name, platform, last_sync) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![device_id, id, BUCKET_PLATFORM, now],
+    )?;
+    conn.execute(
+        "INSERT INTO aw_buckets (id, device_id, type, client, hostname, created)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![id, device_id, bucket_type, client, hostname, now],
+    )?;
+    Ok(true)
+}
+
+pub fn get_bucket(conn: &Connection, id: &str) -> Result<Option<Bucket>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, device_id, type, client, hostname, created, last_updated, last_data
+         FROM aw_buckets WHERE id = ?1",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(bucket_from_row(row)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn list_buckets(conn: &Connection) -> Result<Vec<Bucket>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, device_id, type, client, hostname, created, last_updated, last_data
+         FROM aw_buckets ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], bucket_from_row)?;
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// Remember the latest heartbeat of a bucket.
+pub fn touch_bucket(conn: &Connection, id: &str, data: &serde_json::Value) -> Result<()> {
+    conn.execute(
+        "UPDATE aw_buckets SET last_updated = ?1, last_data = ?2 WHERE id = ?3",
+        rusqlite::params![Utc::now().to_rfc3339(), data.to_string(), id],
+    )?;
+    Ok(())
+}
+
+/// Latest `afkstatus` reported for a host, if it runs an AFK watcher.
+pub fn afk_status_for_host(conn: &Connection, hostname: &str) -> Result<Option<bool>> {
+    let mut stmt = conn.prepare(
+        "SELECT last_data FROM aw_buckets
+         WHERE hostname = ?1 AND type = 'afkstatus' AND last_data IS NOT NULL
+         ORDER BY last_updated DESC LIMIT 1",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![hostname])?;
+    match rows.next()? {
+        Some(row) => {
+            let data: String = row.get(0)?;
+            let data: serde_json::Value = serde_json::from_str(&data)?;
+            Ok(data["status"].as_str().map(|status| status == "afk"))
+        }
+        None => Ok(None),
+    }
+}
+
+fn bucket_from_row(row: &rusqlite::Row) -> rusqlite::Result<Bucket> {
+    let parse_ts = |s: String| {
+        DateTime::parse_from_rfc3339(&s)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now())
+    };
+    let created: String = row.get(5)?;
+    let last_updated: Option<String> = row.get(6)?;
+    let last_data: Option<String> = row.get(7)?;
+    Ok(Bucket {
+        id: row.get(0)?,
+        device_id: row.get(1)?,
+        bucket_type: row.get(2)?,
+        client: row.get(3)?,
+        hostname: row.get(4)?,
+        created: parse_ts(created),
+        last_updated: last_updated.map(parse_ts),
+        last_data: last_data.and_then(|d| serde_json::from_str(&d).ok()),
+    })
+}
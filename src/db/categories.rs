@@ -49,6 +49,21 @@ pub fn get_category_by_id(conn: &Connection, id: i64) -> Result<Option<Category>
     }
 }
 
+/// Look up a category by name, creating it (under its parent, for `parent/child`
+/// names) if it doesn't exist yet.
+pub fn get_or_create_category(conn: &Connection, name: &str) -> Result<Category> {
+    if let Some(category) = get_category_by_name(conn, name)? {
+        return Ok(category);
+    }
+    let parent_id = match name.split_once('/') {
+        Some((parent, _)) => get_category_by_name(conn, parent)?.map(|c| c.id),
+        None => None,
+    };
+    let id = insert_category(conn, name, parent_id, 0.0)?;
+    get_category_by_id(conn, id)?
+        .ok_or_else(|| crate::error::TimelyError::Generic("Failed to create category".into()))
+}
+
 pub fn list_categories(conn: &Connection) -> Result<Vec<Category>> {
     let mut stmt = conn.prepare(
         "SELECT id, name, parent_id, productivity_score FROM categories ORDER BY name",
@@ -109,6 +124,11 @@ pub fn list_rules(conn: &Connection) -> Result<Vec<CategoryRule>> {
     Ok(result)
 }
 
+/// Bumped (by triggers) on every change to the rules.
+pub fn rules_version(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row("SELECT version FROM rules_version WHERE id = 1", [], |row| row.get(0))?)
+}
+
 pub fn delete_rule(conn: &Connection, rule_id: i64) -> Result<bool> {
     let changed = conn.execute(
         "DELETE FROM category_rules WHERE id = ?1",
@@ -129,6 +149,16 @@ pub fn seed_builtin_categories(conn: &Connection) -> Result<()> {
         insert_category(conn, name, parent_id, *score)?;
     }
 
+    // Builtin aliases come before the rules that rely on them; an alias the
+    // user pointed elsewhere is left alone
+    use crate::categories::builtin::BUILTIN_APP_ALIASES;
+    for (alias, canonical) in BUILTIN_APP_ALIASES {
+        conn.execute(
+            "INSERT OR IGNORE INTO app_aliases (alias, canonical) VALUES (?1, ?2)",
+            rusqlite::params![alias, canonical],
+        )?;
+    }
+
     use crate::categories::builtin::BUILTIN_RULES;
 
     // Collect the canonical set of (field, pattern) from the source code
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use crate::error::Result;
+use crate::types::{CoverageInterval, PauseInterval};
+
+/// Open a coverage interval for a daemon starting at `at`.
+pub fn start_interval(conn: &Connection, device_id: &str, at: &DateTime<Utc>) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO coverage (device_id, started_at, last_alive_at) VALUES (?1, ?2, ?2)",
+        rusqlite::params![device_id, at.to_rfc3339()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record that the daemon owning interval `id` was still alive at `at`.
+pub fn touch_interval(conn: &Connection, id: i64, at: &DateTime<Utc>) -> Result<()> {
+    conn.execute(
+        "UPDATE coverage SET last_alive_at = ?1 WHERE id = ?2",
+        rusqlite::params![at.to_rfc3339(), id],
+    )?;
+    Ok(())
+}
+
+pub fn stop_interval(conn: &Connection, id: i64, at: &DateTime<Utc>) -> Result<()> {
+    conn.execute(
+        "UPDATE coverage SET last_alive_at = ?1, stopped_at = ?1 WHERE id = ?2",
+        rusqlite::params![at.to_rfc3339(), id],
+    )?;
+    Ok(())
+}
+
+/// Intervals overlapping `[from, to]`, oldest first.
+pub fn query_intervals(conn: &Connection, from: &DateTime<Utc>, to: &DateTime<Utc>) -> Result<Vec<CoverageInterval>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, device_id, started_at, COALESCE(stopped_at, last_alive_at), stopped_at IS NOT NULL
+         FROM coverage
+         WHERE started_at <= ?2 AND COALESCE(stopped_at, last_alive_at) >= ?1
+         ORDER BY started_at ASC",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![from.to_rfc3339(), to.to_rfc3339()], |row| {
+        Ok(CoverageInterval {
+            id: row.get(0)?,
+            device_id: row.get(1)?,
+            started_at: parse_timestamp(&row.get::<_, String>(2)?),
+            ended_at: parse_timestamp(&row.get::<_, String>(3)?),
+            stopped: row.get(4)?,
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+/// When coverage recording began. Earlier history has no coverage information.
+pub fn first_started_at(conn: &Connection) -> Result<Option<DateTime<Utc>>> {
+    let first: Option<String> = conn.query_row("SELECT MIN(started_at) FROM coverage", [], |row| row.get(0))?;
+    Ok(first.as_deref().map(parse_timestamp))
+}
+
+/// Open a pause starting at `at`; `extend_pause` moves its end as it continues.
+pub fn start_pause(conn: &Connection, device_id: &str, at: &DateTime<Utc>) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO pauses (device_id, started_at, ended_at) VALUES (?1, ?2, ?2)",
+        rusqlite::params![device_id, at.to_rfc3339()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn extend_pause(conn: &Connection, id: i64, at: &DateTime<Utc>) -> Result<()> {
+    conn.execute(
+        "UPDATE pauses SET ended_at = ?1 WHERE id = ?2",
+        rusqlite::params![at.to_rfc3339(), id],
+    )?;
+    Ok(())
+}
+
+/// Pauses overlapping `[from, to]`, oldest first.
+pub fn query_pauses(conn: &Connection, from: &DateTime<Utc>, to: &DateTime<Utc>) -> Result<Vec<PauseInterval>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, device_id, started_at, ended_at
+         FROM pauses
+         WHERE started_at <= ?2 AND ended_at >= ?1
+         ORDER BY started_at ASC",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![from.to_rfc3339(), to.to_rfc3339()], |row| {
+        Ok(PauseInterval {
+            id: row.get(0)?,
+            device_id: row.get(1)?,
+            started_at: parse_timestamp(&row.get::<_, String>(2)?),
+            ended_at: parse_timestamp(&row.get::<_, String>(3)?),
+        })
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+fn parse_timestamp(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
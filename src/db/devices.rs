@@ -53,6 +53,39 @@ pub fn get_or_create_device(conn: &Connection) -> Result<Device> {
     }
 }
 
+/// Get or create a device that isn't this machine, e.g. one per ingestion source.
+pub fn get_or_create_named_device(conn: &Connection, name: &str, platform: &str) -> Result<Device> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, platform, last_sync FROM devices WHERE name = ?1 AND platform = ?2",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![name, platform])?;
+    if let Some(row) = rows.next()? {
+        let ts_str: String = row.get(3)?;
+        let last_sync = chrono::DateTime::parse_from_rfc3339(&ts_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        return Ok(Device {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            platform: row.get(2)?,
+            last_sync,
+        });
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    conn.execute(
+        "INSERT INTO devices (id, name, platform, last_sync) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![id, name, platform, now.to_rfc3339()],
+    )?;
+    Ok(Device {
+        id,
+        name: name.to_string(),
+        platform: platform.to_string(),
+        last_sync: now,
+    })
+}
+
 pub fn list_devices(conn: &Connection) -> Result<Vec<Device>> {
     let mut stmt = conn.prepare("SELECT id, name, platform, last_sync FROM devices ORDER BY name")?;
     let rows = stmt.query_map([], |row| {
@@ -74,7 +107,7 @@ pub fn list_devices(conn: &Connection) -> Result<Vec<Device>> {
     Ok(result)
 }
 
-fn hostname() -> String {
+pub fn hostname() -> String {
     std::env::var("HOSTNAME")
         .or_else(|_| std::env::var("HOST"))
         .unwrap_or_else(|_| {
@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc};
 use rusqlite::Connection;
 use crate::error::Result;
-use crate::types::Event;
+use crate::query::SourceFilter;
+use crate::types::{Event, EventSource};
 
+#[allow(clippy::too_many_arguments)]
 pub fn insert_event(
     conn: &Connection,
     device_id: &str,
@@ -16,8 +18,8 @@ pub fn insert_event(
     is_afk: bool,
 ) -> Result<i64> {
     conn.execute(
-        "INSERT INTO events (device_id, timestamp, duration, app, title, url, url_domain, category_id, is_afk)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        "INSERT INTO events (device_id, timestamp, duration, app, title, url, url_domain, category_id, is_afk, is_idle)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
         rusqlite::params![
             device_id,
             timestamp.to_rfc3339(),
@@ -33,6 +35,75 @@ pub fn insert_event(
     Ok(conn.last_insert_rowid())
 }
 
+/// Insert `event` with all its columns in one statement. Its `id` and
+/// `category_name` are ignored.
+pub fn insert_full_event(conn: &Connection, event: &Event) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO events (device_id, timestamp, duration, app, title, url, url_domain, category_id, is_afk, is_idle,
+                             project, file, language, branch, command, cwd, exit_status, raw_title, app_id, app_path, source)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+        rusqlite::params![
+            event.device_id,
+            event.timestamp.to_rfc3339(),
+            event.duration,
+            event.app,
+            event.title,
+            event.url,
+            event.url_domain,
+            event.category_id,
+            event.is_afk as i32,
+            event.is_idle as i32,
+            event.project,
+            event.file,
+            event.language,
+            event.branch,
+            event.command,
+            event.cwd,
+            event.exit_status,
+            event.raw_title,
+            event.app_id,
+            event.app_path,
+            event.source.as_str(),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record how an event got into the database (events default to `watcher`).
+pub fn set_event_source(conn: &Connection, event_id: i64, source: EventSource) -> Result<()> {
+    conn.execute("UPDATE events SET source = ?1 WHERE id = ?2", rusqlite::params![source.as_str(), event_id])?;
+    Ok(())
+}
+
+/// Keep the title an event was first seen with, before normalization.
+pub fn set_raw_title(conn: &Connection, event_id: i64, raw_title: Option<&str>) -> Result<()> {
+    conn.execute("UPDATE events SET raw_title = ?1 WHERE id = ?2", rusqlite::params![raw_title, event_id])?;
+    Ok(())
+}
+
+/// Replace the free-text fields of an event (used by retroactive redaction).
+pub fn set_event_text(
+    conn: &Connection,
+    event_id: i64,
+    title: &str,
+    url: Option<&str>,
+    command: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE events SET title = ?1, url = ?2, command = ?3 WHERE id = ?4",
+        rusqlite::params![title, url, command, event_id],
+    )?;
+    Ok(())
+}
+
+pub fn set_exit_status(conn: &Connection, event_id: i64, exit_status: i32) -> Result<()> {
+    conn.execute(
+        "UPDATE events SET exit_status = ?1 WHERE id = ?2",
+        rusqlite::params![exit_status, event_id],
+    )?;
+    Ok(())
+}
+
 pub fn extend_event(conn: &Connection, event_id: i64, new_duration: f64) -> Result<()> {
     conn.execute(
         "UPDATE events SET duration = ?1 WHERE id = ?2",
@@ -41,15 +112,44 @@ pub fn extend_event(conn: &Connection, event_id: i64, new_duration: f64) -> Resu
     Ok(())
 }
 
+/// `extend_event`, unless the row no longer has the app and duration it was
+/// last known with (something else, like an AFK label, rewrote it). Returns
+/// whether it was extended.
+pub fn extend_event_if_unchanged(
+    conn: &Connection,
+    event_id: i64,
+    app: &str,
+    known_duration: f64,
+    new_duration: f64,
+) -> Result<bool> {
+    let affected = conn.execute(
+        "UPDATE events SET duration = ?1 WHERE id = ?2 AND app = ?3 AND duration = ?4",
+        rusqlite::params![new_duration, event_id, app, known_duration],
+    )?;
+    Ok(affected > 0)
+}
+
 pub fn get_last_event(conn: &Connection, device_id: &str) -> Result<Option<Event>> {
-    let mut stmt = conn.prepare(
+    last_event_where(conn, device_id, "")
+}
+
+/// `get_last_event`, skipping events without an app name.
+pub fn get_last_app_event(conn: &Connection, device_id: &str) -> Result<Option<Event>> {
+    last_event_where(conn, device_id, " AND e.app != ''")
+}
+
+fn last_event_where(conn: &Connection, device_id: &str, filter: &str) -> Result<Option<Event>> {
+    let sql = format!(
         "SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
-                e.category_id, c.name, e.is_afk
+                e.category_id, c.name, e.is_afk, e.project, e.file, e.language, e.branch,
+                e.command, e.cwd, e.exit_status, e.is_idle, e.raw_title, e.app_id, e.app_path, e.source
          FROM events e
          LEFT JOIN categories c ON c.id = e.category_id
-         WHERE e.device_id = ?1
-         ORDER BY e.id DESC LIMIT 1",
-    )?;
+         WHERE e.device_id = ?1{}
+         ORDER BY e.timestamp DESC, e.id DESC LIMIT 1",
+        filter
+    );
+    let mut stmt = conn.prepare(&sql)?;
     let mut rows = stmt.query(rusqlite::params![device_id])?;
     if let Some(row) = rows.next()? {
         Ok(Some(event_from_row(row)?))
@@ -63,20 +163,32 @@ pub fn query_events(
     from: &DateTime<Utc>,
     to: &DateTime<Utc>,
     limit: Option<i64>,
+) -> Result<Vec<Event>> {
+    query_events_filtered(conn, from, to, limit, &SourceFilter::default())
+}
+
+/// `query_events`, limited to the events `sources` covers.
+pub fn query_events_filtered(
+    conn: &Connection,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+    limit: Option<i64>,
+    sources: &SourceFilter,
 ) -> Result<Vec<Event>> {
     let lim = limit.unwrap_or(i64::MAX);
-    let sql = "SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
-                e.category_id, c.name, e.is_afk
+    let sql = format!("SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
+                e.category_id, c.name, e.is_afk, e.project, e.file, e.language, e.branch,
+                e.command, e.cwd, e.exit_status, e.is_idle, e.raw_title, e.app_id, e.app_path, e.source
          FROM events e
          LEFT JOIN categories c ON c.id = e.category_id
-         WHERE e.timestamp >= ?1 AND e.timestamp <= ?2
+         WHERE e.timestamp >= ?1 AND e.timestamp <= ?2{}
          ORDER BY e.timestamp DESC
-         LIMIT ?3";
+         LIMIT ?3", sources.sql());
 
-    let mut stmt = conn.prepare(sql)?;
+    let mut stmt = conn.prepare(&sql)?;
     let rows = stmt.query_map(
         rusqlite::params![from.to_rfc3339(), to.to_rfc3339(), lim],
-        |row| event_from_row(row),
+        event_from_row,
     )?;
 
     let mut result = Vec::new();
@@ -93,7 +205,8 @@ pub fn query_events_after_id(
     limit: i64,
 ) -> Result<Vec<Event>> {
     let sql = "SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
-                e.category_id, c.name, e.is_afk
+                e.category_id, c.name, e.is_afk, e.project, e.file, e.language, e.branch,
+                e.command, e.cwd, e.exit_status, e.is_idle, e.raw_title, e.app_id, e.app_path, e.source
          FROM events e
          LEFT JOIN categories c ON c.id = e.category_id
          WHERE e.device_id = ?1 AND e.id > ?2
@@ -103,7 +216,7 @@ pub fn query_events_after_id(
     let mut stmt = conn.prepare(sql)?;
     let rows = stmt.query_map(
         rusqlite::params![device_id, after_id, limit],
-        |row| event_from_row(row),
+        event_from_row,
     )?;
 
     let mut result = Vec::new();
@@ -113,6 +226,76 @@ pub fn query_events_after_id(
     Ok(result)
 }
 
+/// AFK events of `device_id` overlapping `[from, to]`, oldest first.
+pub fn query_afk_events(
+    conn: &Connection,
+    device_id: &str,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+) -> Result<Vec<Event>> {
+    // Bounded below by a day so the timestamp index still narrows the scan
+    let sql = "SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
+                e.category_id, c.name, e.is_afk, e.project, e.file, e.language, e.branch,
+                e.command, e.cwd, e.exit_status, e.is_idle, e.raw_title, e.app_id, e.app_path, e.source
+         FROM events e
+         LEFT JOIN categories c ON c.id = e.category_id
+         WHERE e.device_id = ?1 AND e.is_afk = 1
+           AND e.timestamp >= ?2 AND e.timestamp <= ?4
+           AND julianday(e.timestamp) + e.duration / 86400.0 >= julianday(?3)
+         ORDER BY e.timestamp ASC, e.id ASC";
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(
+        rusqlite::params![
+            device_id,
+            (*from - chrono::Duration::days(1)).to_rfc3339(),
+            from.to_rfc3339(),
+            to.to_rfc3339()
+        ],
+        event_from_row,
+    )?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+pub fn get_event(conn: &Connection, event_id: i64) -> Result<Option<Event>> {
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.device_id, e.timestamp, e.duration, e.app, e.title, e.url, e.url_domain,
+                e.category_id, c.name, e.is_afk, e.project, e.file, e.language, e.branch,
+                e.command, e.cwd, e.exit_status, e.is_idle, e.raw_title, e.app_id, e.app_path, e.source
+         FROM events e
+         LEFT JOIN categories c ON c.id = e.category_id
+         WHERE e.id = ?1",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![event_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(event_from_row(row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Turn an event into non-AFK activity done away from the computer.
+pub fn set_offline_activity(
+    conn: &Connection,
+    event_id: i64,
+    app: &str,
+    title: &str,
+    category_id: i64,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE events SET app = ?1, title = ?2, raw_title = NULL, app_id = NULL, app_path = NULL, url = NULL, url_domain = NULL, category_id = ?3, is_afk = 0,
+                project = NULL, file = NULL, language = NULL, branch = NULL, command = NULL, cwd = NULL,
+                exit_status = NULL, source = ?5
+         WHERE id = ?4",
+        rusqlite::params![app, title, category_id, event_id, EventSource::Label.as_str()],
+    )?;
+    Ok(())
+}
+
 pub fn update_event_category(conn: &Connection, event_id: i64, category_id: i64) -> Result<()> {
     conn.execute(
         "UPDATE events SET category_id = ?1 WHERE id = ?2",
@@ -139,5 +322,17 @@ fn event_from_row(row: &rusqlite::Row) -> rusqlite::Result<Event> {
         category_id: row.get(8)?,
         category_name: row.get(9)?,
         is_afk: row.get::<_, i32>(10)? != 0,
+        project: row.get(11)?,
+        file: row.get(12)?,
+        language: row.get(13)?,
+        branch: row.get(14)?,
+        command: row.get(15)?,
+        cwd: row.get(16)?,
+        exit_status: row.get(17)?,
+        is_idle: row.get::<_, i32>(18)? != 0,
+        raw_title: row.get(19)?,
+        app_id: row.get(20)?,
+        app_path: row.get(21)?,
+        source: EventSource::from_name(&row.get::<_, String>(22)?),
     })
 }
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use crate::error::Result;
+use crate::types::{MediaEvent, MediaPlayback};
+
+pub fn insert_media_event(
+    conn: &Connection,
+    device_id: &str,
+    timestamp: &DateTime<Utc>,
+    duration: f64,
+    media: &MediaPlayback,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO media_events (device_id, timestamp, duration, player, title, artist)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            device_id,
+            timestamp.to_rfc3339(),
+            duration,
+            media.player,
+            media.title,
+            media.artist,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn extend_media_event(conn: &Connection, id: i64, new_duration: f64) -> Result<()> {
+    conn.execute(
+        "UPDATE media_events SET duration = ?1 WHERE id = ?2",
+        rusqlite::params![new_duration, id],
+    )?;
+    Ok(())
+}
+
+pub fn get_last_media_event(conn: &Connection, device_id: &str) -> Result<Option<MediaEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, device_id, timestamp, duration, player, title, artist
+         FROM media_events
+         WHERE device_id = ?1
+         ORDER BY id DESC LIMIT 1",
+    )?;
+    let mut rows = stmt.query(rusqlite::params![device_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(media_event_from_row(row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Media events starting in `[from, to]`, oldest first.
+pub fn query_media_events(conn: &Connection, from: &DateTime<Utc>, to: &DateTime<Utc>) -> Result<Vec<MediaEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, device_id, timestamp, duration, player, title, artist
+         FROM media_events
+         WHERE timestamp >= ?1 AND timestamp <= ?2
+         ORDER BY timestamp ASC",
+    )?;
+    let rows = stmt.query_map(
+        rusqlite::params![from.to_rfc3339(), to.to_rfc3339()],
+        media_event_from_row,
+    )?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(result)
+}
+
+fn media_event_from_row(row: &rusqlite::Row) -> rusqlite::Result<MediaEvent> {
+    let ts_str: String = row.get(2)?;
+    let timestamp = DateTime::parse_from_rfc3339(&ts_str)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now());
+
+    Ok(MediaEvent {
+        id: row.get(0)?,
+        device_id: row.get(1)?,
+        timestamp,
+        duration: row.get(3)?,
+        player: row.get(4)?,
+        title: row.get(5)?,
+        artist: row.get(6)?,
+    })
+}
@@ -1,9 +1,13 @@
 pub mod schema;
 pub mod events;
 pub mod categories;
+pub mod apps;
 pub mod config_store;
 pub mod devices;
 pub mod sync;
+pub mod buckets;
+pub mod media;
+pub mod coverage;
 
 use rusqlite::Connection;
 use crate::error::Result;
@@ -54,6 +54,109 @@ const MIGRATIONS: &[&str] = &[
         last_synced_event_id INTEGER NOT NULL DEFAULT 0,
         last_sync_at TEXT NOT NULL DEFAULT (datetime('now'))
     );",
+    // Version 3: ActivityWatch-compatible buckets, each mapped to its own device
+    "CREATE TABLE IF NOT EXISTS aw_buckets (
+        id TEXT PRIMARY KEY,
+        device_id TEXT NOT NULL REFERENCES devices(id),
+        type TEXT NOT NULL,
+        client TEXT NOT NULL,
+        hostname TEXT NOT NULL,
+        created TEXT NOT NULL,
+        last_updated TEXT,
+        last_data TEXT
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_aw_buckets_hostname ON aw_buckets(hostname, type);",
+    // Version 4: Coding context (from editor plugins)
+    "ALTER TABLE events ADD COLUMN project TEXT;
+    ALTER TABLE events ADD COLUMN file TEXT;
+    ALTER TABLE events ADD COLUMN language TEXT;
+    ALTER TABLE events ADD COLUMN branch TEXT;
+
+    CREATE INDEX IF NOT EXISTS idx_events_project ON events(project);",
+    // Version 5: Shell command context (from shell hooks)
+    "ALTER TABLE events ADD COLUMN command TEXT;
+    ALTER TABLE events ADD COLUMN cwd TEXT;
+    ALTER TABLE events ADD COLUMN exit_status INTEGER;",
+    // Version 6: Background media playback, a stream separate from focus events
+    "CREATE TABLE IF NOT EXISTS media_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        device_id TEXT NOT NULL REFERENCES devices(id),
+        timestamp TEXT NOT NULL,
+        duration REAL NOT NULL DEFAULT 0.0,
+        player TEXT NOT NULL,
+        title TEXT,
+        artist TEXT
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_media_events_timestamp ON media_events(timestamp);",
+    // Version 7: Daemon run intervals, to tell untracked time apart from inactivity
+    "CREATE TABLE IF NOT EXISTS coverage (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        device_id TEXT NOT NULL REFERENCES devices(id),
+        started_at TEXT NOT NULL,
+        last_alive_at TEXT NOT NULL,
+        stopped_at TEXT
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_coverage_started_at ON coverage(started_at);",
+    // Version 8: Periods when tracking was paused by the user
+    "CREATE TABLE IF NOT EXISTS pauses (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        device_id TEXT NOT NULL REFERENCES devices(id),
+        started_at TEXT NOT NULL,
+        ended_at TEXT NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_pauses_started_at ON pauses(started_at);",
+    // Version 9: Counter bumped on every rule change, so compiled rule sets know when to rebuild
+    "CREATE TABLE IF NOT EXISTS rules_version (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        version INTEGER NOT NULL
+    );
+
+    INSERT OR IGNORE INTO rules_version (id, version) VALUES (1, 0);
+
+    CREATE TRIGGER IF NOT EXISTS category_rules_inserted AFTER INSERT ON category_rules
+    BEGIN UPDATE rules_version SET version = version + 1; END;
+
+    CREATE TRIGGER IF NOT EXISTS category_rules_updated AFTER UPDATE ON category_rules
+    BEGIN UPDATE rules_version SET version = version + 1; END;
+
+    CREATE TRIGGER IF NOT EXISTS category_rules_deleted AFTER DELETE ON category_rules
+    BEGIN UPDATE rules_version SET version = version + 1; END;",
+    // Version 10: Raw idle state, kept apart from is_afk for engaged activity (video calls)
+    "ALTER TABLE events ADD COLUMN is_idle INTEGER NOT NULL DEFAULT 0;
+
+    UPDATE events SET is_idle = is_afk;",
+    // Version 11: Window titles as seen, where `title` holds the normalized form
+    "ALTER TABLE events ADD COLUMN raw_title TEXT;",
+    // Version 12: App identity (bundle id / WM_CLASS, executable) and canonical app names.
+    // Aliases change how rules match, so they bump the rules version too.
+    "ALTER TABLE events ADD COLUMN app_id TEXT;
+    ALTER TABLE events ADD COLUMN app_path TEXT;
+
+    CREATE TABLE IF NOT EXISTS app_aliases (
+        alias TEXT PRIMARY KEY COLLATE NOCASE,
+        canonical TEXT NOT NULL
+    );
+
+    CREATE TRIGGER IF NOT EXISTS app_aliases_inserted AFTER INSERT ON app_aliases
+    BEGIN UPDATE rules_version SET version = version + 1; END;
+
+    CREATE TRIGGER IF NOT EXISTS app_aliases_updated AFTER UPDATE ON app_aliases
+    BEGIN UPDATE rules_version SET version = version + 1; END;
+
+    CREATE TRIGGER IF NOT EXISTS app_aliases_deleted AFTER DELETE ON app_aliases
+    BEGIN UPDATE rules_version SET version = version + 1; END;",
+    // Version 13: Event provenance. Earlier events are attributed by their device
+    // where that tells (ingest, WakaTime and ActivityWatch devices) and labeled
+    // offline time by its app; the rest is assumed to be the watcher's.
+    "ALTER TABLE events ADD COLUMN source TEXT NOT NULL DEFAULT 'watcher';
+    UPDATE events SET source = 'ingest' WHERE device_id IN (SELECT id FROM devices WHERE platform = 'ingest');
+    UPDATE events SET source = 'wakatime' WHERE device_id IN (SELECT id FROM devices WHERE platform = 'wakatime');
+    UPDATE events SET source = 'activitywatch' WHERE device_id IN (SELECT device_id FROM aw_buckets);
+    UPDATE events SET source = 'label' WHERE app = 'Offline' AND is_afk = 0;",
 ];
 
 pub fn run_migrations(conn: &Connection) -> Result<()> {
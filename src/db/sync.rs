@@ -1,42 +1,58 @@
 use rusqlite::Connection;
+use crate::db::events;
 use crate::error::Result;
+use crate::types::{Event, EventSource};
 
-pub fn upsert_remote_event(
-    conn: &Connection,
-    device_id: &str,
-    timestamp: &str,
-    duration: f64,
-    app: &str,
-    title: &str,
-    url: Option<&str>,
-    url_domain: Option<&str>,
-    category_id: Option<i64>,
-    is_afk: bool,
-) -> Result<bool> {
+/// Store an event pushed by another device, tagged as `sync`. Returns false
+/// for an event the hub already has.
+pub fn upsert_remote_event(conn: &Connection, event: &Event) -> Result<bool> {
+    let timestamp = event.timestamp.to_rfc3339();
     // Dedup key: (device_id, timestamp, app, title). On conflict, take MAX(duration).
     let existing: Option<(i64, f64)> = conn
         .query_row(
             "SELECT id, duration FROM events
              WHERE device_id = ?1 AND timestamp = ?2 AND app = ?3 AND title = ?4",
-            rusqlite::params![device_id, timestamp, app, title],
+            rusqlite::params![event.device_id, timestamp, event.app, event.title],
             |row| Ok((row.get(0)?, row.get(1)?)),
         )
         .ok();
 
     if let Some((id, existing_dur)) = existing {
-        if duration > existing_dur {
+        if event.duration > existing_dur {
             conn.execute(
-                "UPDATE events SET duration = ?1, url = ?2, url_domain = ?3,
-                        category_id = ?4, is_afk = ?5 WHERE id = ?6",
-                rusqlite::params![duration, url, url_domain, category_id, is_afk as i32, id],
+                "UPDATE events SET duration = ?1, url = ?2, url_domain = ?3, category_id = ?4, is_afk = ?5, is_idle = ?6,
+                        project = ?7, file = ?8, language = ?9, branch = ?10, command = ?11, cwd = ?12,
+                        exit_status = ?13, raw_title = ?14, app_id = ?15, app_path = ?16
+                 WHERE id = ?17",
+                rusqlite::params![
+                    event.duration,
+                    event.url,
+                    event.url_domain,
+                    event.category_id,
+                    event.is_afk as i32,
+                    event.is_idle as i32,
+                    event.project,
+                    event.file,
+                    event.language,
+                    event.branch,
+                    event.command,
+                    event.cwd,
+                    event.exit_status,
+                    event.raw_title,
+                    event.app_id,
+                    event.app_path,
+                    id,
+                ],
             )?;
         }
         Ok(false) // duplicate
     } else {
-        conn.execute(
-            "INSERT INTO events (device_id, timestamp, duration, app, title, url, url_domain, category_id, is_afk)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            rusqlite::params![device_id, timestamp, duration, app, title, url, url_domain, category_id, is_afk as i32],
+        events::insert_full_event(
+            conn,
+            &Event {
+                source: EventSource::Sync,
+                ..event.clone()
+            },
         )?;
         Ok(true) // new
     }
@@ -83,7 +99,10 @@ pub fn update_sync_log(conn: &Connection, device_id: &str, last_event_id: i64) -
     Ok(())
 }
 
-pub fn get_device_event_counts(conn: &Connection) -> Result<Vec<(String, String, String, Option<String>, i64)>> {
+/// (id, name, platform, last_sync, event_count)
+pub type DeviceEventCount = (String, String, String, Option<String>, i64);
+
+pub fn get_device_event_counts(conn: &Connection) -> Result<Vec<DeviceEventCount>> {
     let mut stmt = conn.prepare(
         "SELECT d.id, d.name, d.platform, d.last_sync,
                 (SELECT COUNT(*) FROM events WHERE device_id = d.id) as event_count
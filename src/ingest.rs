@@ -0,0 +1,144 @@
+//! Generic event ingestion for scripts and bots (`POST /api/events`, `timely ingest`).
+//!
+//! Events name their source (and optionally a device); each device name maps
+//! to its own device row, so ingested time never merges with the daemon's.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::config::HEARTBEAT_MERGE_GAP_SECS;
+use crate::daemon::heartbeat;
+use crate::db::devices;
+use crate::error::{Result, TimelyError};
+use crate::types::{EventSource, WatcherSnapshot};
+use crate::watchers::extract_domain;
+
+/// Platform recorded for devices created by ingestion.
+pub const INGEST_PLATFORM: &str = "ingest";
+
+/// One ingested event. With no `duration` it is a heartbeat (a point in time);
+/// with a duration it is a complete event. Either way it merges into the
+/// device's previous event when the activity matches and the gap is at most `pulsetime`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IngestEvent {
+    /// What produced the event, e.g. "ci-bot" or "call-logger"
+    pub source: Option<String>,
+    /// Device name to record onto (default: the source)
+    pub device: Option<String>,
+    /// Default: now
+    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub duration: f64,
+    pub app: String,
+    #[serde(default)]
+    pub title: String,
+    pub url: Option<String>,
+    pub url_domain: Option<String>,
+    #[serde(default)]
+    pub is_afk: bool,
+    pub project: Option<String>,
+    pub file: Option<String>,
+    pub language: Option<String>,
+    pub branch: Option<String>,
+    /// Merge gap in seconds (default: the daemon's)
+    pub pulsetime: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IngestResult {
+    pub accepted: usize,
+    /// Affected event id per input, in order
+    pub event_ids: Vec<Option<i64>>,
+}
+
+/// Validate, classify and store events, merging heartbeats per device.
+/// The batch is all-or-nothing: nothing is stored if any event is invalid.
+pub fn ingest_events(conn: &Connection, events: &[IngestEvent]) -> Result<IngestResult> {
+    let mut device_names = Vec::with_capacity(events.len());
+    for (i, event) in events.iter().enumerate() {
+        let device_name = event
+            .device
+            .as_deref()
+            .or(event.source.as_deref())
+            .filter(|name| !name.trim().is_empty())
+            .ok_or_else(|| TimelyError::Config(format!("event {}: a source or device is required", i + 1)))?;
+        if event.app.trim().is_empty() {
+            return Err(TimelyError::Config(format!("event {}: app is required", i + 1)));
+        }
+        if event.duration < 0.0 {
+            return Err(TimelyError::Config(format!("event {}: duration must not be negative", i + 1)));
+        }
+        device_names.push(device_name);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let pipeline = heartbeat::Pipeline::from_config(&tx)?;
+    let mut event_ids = Vec::with_capacity(events.len());
+    for (event, device_name) in events.iter().zip(device_names) {
+        let device = devices::get_or_create_named_device(&tx, device_name, INGEST_PLATFORM)?;
+        let url_domain = event
+            .url_domain
+            .clone()
+            .or_else(|| event.url.as_deref().map(extract_domain).filter(|d| !d.is_empty()));
+        let snapshot = WatcherSnapshot {
+            app: event.app.clone(),
+            title: event.title.clone(),
+            url: event.url.clone(),
+            url_domain,
+            is_afk: event.is_afk,
+            project: event.project.clone(),
+            file: event.file.clone(),
+            language: event.language.clone(),
+            branch: event.branch.clone(),
+            source: EventSource::Ingest,
+            ..Default::default()
+        };
+
+        event_ids.push(pipeline.process(
+            &tx,
+            &device.id,
+            &snapshot,
+            &event.timestamp.unwrap_or_else(Utc::now),
+            event.duration,
+            event.pulsetime.unwrap_or(HEARTBEAT_MERGE_GAP_SECS),
+        )?);
+    }
+    tx.commit()?;
+
+    Ok(IngestResult {
+        accepted: event_ids.len(),
+        event_ids,
+    })
+}
+
+/// Parse newline-delimited JSON events, skipping blank lines.
+pub fn parse_ndjson(input: &str) -> Result<Vec<IngestEvent>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| TimelyError::Config(format!("line {}: invalid event: {}", n + 1, e)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ndjson() {
+        let input = "{\"source\": \"ci\", \"app\": \"GitHub Actions\", \"duration\": 120}\n\n\
+                     {\"source\": \"phone\", \"app\": \"Phone\", \"title\": \"Call with Ana\"}\n";
+        let events = parse_ndjson(input).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].duration, 120.0);
+        assert_eq!(events[1].title, "Call with Ana");
+
+        let err = parse_ndjson("{\"app\": \"x\"}\nnot json\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+}
@@ -6,7 +6,11 @@ pub mod output;
 pub mod db;
 pub mod watchers;
 pub mod categories;
+pub mod redact;
+pub mod normalize;
+pub mod afk;
 pub mod daemon;
+pub mod ingest;
 pub mod query;
 pub mod cli;
 pub mod web;
@@ -1,8 +1,9 @@
 use clap::Parser;
 use std::process;
 
-use timely::cli::{self, Cli, Commands, DaemonAction, CategorizeAction, ConfigAction, DevicesAction, SyncAction};
+use timely::cli::{self, AfkAction, AppsAction, Cli, Commands, DaemonAction, CategorizeAction, ConfigAction, DevicesAction, ShellEventAction, SyncAction};
 use timely::output;
+use timely::query::SourceFilter;
 
 fn main() {
     let cli = Cli::parse();
@@ -12,22 +13,26 @@ fn main() {
             DaemonAction::Start { json } => cli::daemon::cmd_start(json),
             DaemonAction::Stop { json } => cli::daemon::cmd_stop(json),
             DaemonAction::Status { json } => cli::daemon::cmd_status(json),
+            DaemonAction::Reload { json } => cli::daemon::cmd_reload(json),
             DaemonAction::Run => cli::daemon::cmd_run(),
         },
         Commands::Now { json, all_devices, device } => {
             cli::now::cmd_now(json, all_devices, device.as_deref())
         }
-        Commands::Summary { from, to, by, exclude_afk, json, all_devices, device } => {
-            cli::summary::cmd_summary(&from, &to, &by, exclude_afk, json, all_devices, device.as_deref())
+        Commands::Summary { from, to, by, exclude_afk, json, all_devices, device, source, exclude_source } => {
+            let sources = SourceFilter { only: source, exclude: exclude_source };
+            cli::summary::cmd_summary(&from, &to, &by, exclude_afk, json, all_devices, device.as_deref(), &sources)
         }
-        Commands::Timeline { from, to, limit, json, all_devices, device } => {
-            cli::timeline::cmd_timeline(&from, &to, limit, json, all_devices, device.as_deref())
+        Commands::Timeline { from, to, limit, json, all_devices, device, source, exclude_source } => {
+            let sources = SourceFilter { only: source, exclude: exclude_source };
+            cli::timeline::cmd_timeline(&from, &to, limit, json, all_devices, device.as_deref(), &sources)
         }
         Commands::Categorize { action } => match action {
             CategorizeAction::Set { pattern, category, field, retroactive, json } => {
                 cli::categorize::cmd_set(&pattern, &category, &field, retroactive, json)
             }
             CategorizeAction::List { json } => cli::categorize::cmd_list(json),
+            CategorizeAction::Apply { json } => cli::categorize::cmd_apply(json),
             CategorizeAction::Delete { id, json } => cli::categorize::cmd_delete(id, json),
         },
         Commands::Config { action } => match action {
@@ -38,16 +43,47 @@ fn main() {
         Commands::Devices { action } => match action {
             DevicesAction::List { json } => cli::devices::cmd_list(json),
         },
-        Commands::Export { format, from, to } => {
-            cli::export::cmd_export(&format, &from, &to)
+        Commands::Export { format, from, to, source, exclude_source } => {
+            let sources = SourceFilter { only: source, exclude: exclude_source };
+            cli::export::cmd_export(&format, &from, &to, &sources)
         }
         Commands::Import { file, json } => cli::import_cmd::cmd_import(&file, json),
+        Commands::Ingest { source, device, json } => {
+            cli::ingest::cmd_ingest(source.as_deref(), device.as_deref(), json)
+        }
+        Commands::ShellHook { shell } => cli::shell_hook::cmd_shell_hook(&shell),
+        Commands::ShellEvent { action } => match action {
+            ShellEventAction::Start { pid, seq, cwd, command } => {
+                cli::shell_hook::cmd_start(pid, seq, &cwd, &command.join(" "))
+            }
+            ShellEventAction::End { pid, seq, status, cwd } => {
+                cli::shell_hook::cmd_end(pid, seq, status, &cwd)
+            }
+        },
         Commands::Dashboard { port } => cli::dashboard::cmd_dashboard(port),
         Commands::Sync { action } => match action {
             SyncAction::Setup { hub, key, json } => cli::sync_cmd::cmd_setup(&hub, key.as_deref(), json),
             SyncAction::Push { json } => cli::sync_cmd::cmd_push(json),
             SyncAction::Status { json } => cli::sync_cmd::cmd_status(json),
         },
+        Commands::Pause { spec, json } => cli::pause::cmd_pause(&spec, json),
+        Commands::Resume { json } => cli::pause::cmd_resume(json),
+        Commands::Redact { from, to, dry_run, json } => {
+            cli::redact::cmd_redact(&from, &to, dry_run, json)
+        }
+        Commands::Afk { action } => match action {
+            AfkAction::List { from, to, min_secs, json } => cli::afk::cmd_list(&from, &to, min_secs, json),
+            AfkAction::Label { target, category, note, json } => {
+                cli::afk::cmd_label(&target, &category, note.as_deref(), json)
+            }
+        },
+        Commands::Apps { action } => match action {
+            AppsAction::Alias { alias, canonical, retroactive, json } => {
+                cli::apps::cmd_alias(&alias, &canonical, retroactive, json)
+            }
+            AppsAction::Unalias { alias, retroactive, json } => cli::apps::cmd_unalias(&alias, retroactive, json),
+            AppsAction::List { json } => cli::apps::cmd_list(json),
+        },
         Commands::Focus { from, to, json } => {
             cli::focus::cmd_focus(&from, &to, json)
         }
@@ -0,0 +1,138 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use rusqlite::Connection;
+
+use crate::db::config_store;
+use crate::error::{Result, TimelyError};
+use crate::types::WatcherSnapshot;
+
+/// Noise that changes a window title without changing the activity.
+const BUILTINS: &[(&str, &str)] = &[
+    // Unread counters: "(3) Slack | general", "[12] Inbox", "Inbox (12) - me@example.com - Gmail".
+    // Elsewhere a parenthesized number is part of the title ("Report (2024) - Word").
+    (r"^\s*[(\[]\d+\+?[)\]]\s*", ""),
+    (r"\s\(\d+\+?\)(\s-\s[^\s@]+@\S+)", "$1"),
+    // Unsaved-changes markers: "● main.rs — project", "main.rs [+]", "*notes.txt - Notepad"
+    (r"^\s*[●•◉○]\s*", ""),
+    (r"\s*[●•]\s*$", ""),
+    (r"\s*\[\+\]", ""),
+    (r"^\*([^*]+)$", "$1"),
+    // Progress spinners in terminal titles: "⠋ cargo build", "◐ npm install"
+    (r"[\x{2800}-\x{28FF}◐◓◑◒◴◷◶◵⏳⌛]\s*", ""),
+];
+
+fn builtins() -> &'static [(Regex, &'static str)] {
+    static BUILTINS_RE: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    BUILTINS_RE.get_or_init(|| BUILTINS.iter().map(|(p, r)| (Regex::new(p).unwrap(), *r)).collect())
+}
+
+/// Rewrites window titles into the form events are merged and grouped by;
+/// the title as seen is kept as the event's `raw_title`. Configured with:
+/// - `normalize.rule.<name>.pattern` / `normalize.rule.<name>.replace`: regex
+///   replace rules (`$1`-style references allowed; the replacement defaults to empty)
+/// - `normalize.builtins`: strip unread counters, unsaved-changes markers and
+///   spinner characters (default true)
+#[derive(Default)]
+pub struct TitleNormalizer {
+    rules: Vec<(Regex, String)>,
+    builtins: bool,
+}
+
+impl TitleNormalizer {
+    pub fn from_config(conn: &Connection) -> Result<Self> {
+        let config = config_store::list(conn)?;
+        let get = |key: &str| config.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+        let mut rules = Vec::new();
+        for (key, pattern) in &config {
+            let Some(name) = key.strip_prefix("normalize.rule.").and_then(|k| k.strip_suffix(".pattern")) else {
+                continue;
+            };
+            let regex = Regex::new(pattern)
+                .map_err(|e| TimelyError::Config(format!("Invalid normalization rule '{}': {}", name, e)))?;
+            let replace = get(&format!("normalize.rule.{}.replace", name)).unwrap_or("");
+            rules.push((regex, replace.to_string()));
+        }
+
+        Ok(Self {
+            rules,
+            builtins: get("normalize.builtins") != Some("false"),
+        })
+    }
+
+    /// Normalize the title of `snapshot` in place, keeping the original in
+    /// `raw_title` when it changes.
+    pub fn normalize(&self, snapshot: &mut WatcherSnapshot) {
+        let title = self.normalize_title(&snapshot.title);
+        if title != snapshot.title {
+            snapshot.raw_title = Some(std::mem::replace(&mut snapshot.title, title));
+        }
+    }
+
+    pub fn normalize_title(&self, title: &str) -> String {
+        let mut title = title.to_string();
+        if self.builtins {
+            for (regex, replace) in builtins() {
+                if regex.is_match(&title) {
+                    title = regex.replace_all(&title, *replace).into_owned();
+                }
+            }
+        }
+        for (regex, replace) in &self.rules {
+            title = regex.replace_all(&title, replace.as_str()).into_owned();
+        }
+        title.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtins() {
+        let normalizer = TitleNormalizer {
+            builtins: true,
+            ..Default::default()
+        };
+        for (raw, normalized) in [
+            ("(3) Slack | general", "Slack | general"),
+            ("[12] Inbox", "Inbox"),
+            ("Inbox (12) - me@example.com - Gmail", "Inbox - me@example.com - Gmail"),
+            ("● main.rs — project", "main.rs — project"),
+            ("main.rs + (~/src/timely) - VIM [+]", "main.rs + (~/src/timely) - VIM"),
+            ("*notes.txt - Notepad", "notes.txt - Notepad"),
+            ("⠋ cargo build", "cargo build"),
+            ("*scratch*", "*scratch*"),
+            ("Report (2024) - Word", "Report (2024) - Word"),
+            ("Chapter (3) - Reader", "Chapter (3) - Reader"),
+            ("report (1).pdf", "report (1).pdf"),
+        ] {
+            assert_eq!(normalizer.normalize_title(raw), normalized, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_keeps_raw_title() {
+        let normalizer = TitleNormalizer {
+            rules: vec![(Regex::new(r" - Mozilla Firefox$").unwrap(), String::new())],
+            builtins: true,
+        };
+        let mut snapshot = WatcherSnapshot {
+            app: "Firefox".to_string(),
+            title: "(2) Feed - Mozilla Firefox".to_string(),
+            ..Default::default()
+        };
+        normalizer.normalize(&mut snapshot);
+        assert_eq!(snapshot.title, "Feed");
+        assert_eq!(snapshot.raw_title.as_deref(), Some("(2) Feed - Mozilla Firefox"));
+
+        let mut unchanged = WatcherSnapshot {
+            title: "Feed".to_string(),
+            ..Default::default()
+        };
+        normalizer.normalize(&mut unchanged);
+        assert_eq!(unchanged.raw_title, None);
+    }
+}
@@ -1,4 +1,5 @@
 use rusqlite::Connection;
+use crate::db::apps;
 use crate::error::Result;
 use crate::types::{format_duration, AppBreakdown};
 
@@ -8,23 +9,27 @@ pub fn build_apps(
     to_date: &str,
     limit: i64,
 ) -> Result<Vec<AppBreakdown>> {
-    let mut stmt = conn.prepare(
+    let sql = format!(
         "SELECT
            CASE
              WHEN e.url_domain IS NOT NULL AND e.url_domain != ''
              THEN e.url_domain
-             ELSE COALESCE(e.app, 'Unknown')
+             ELSE COALESCE({}, 'Unknown')
            END as label,
            COALESCE(c.name, 'uncategorized') as category,
            SUM(e.duration) as total_seconds,
            COUNT(*) as event_count
          FROM events e
          LEFT JOIN categories c ON e.category_id = c.id
+         {}
          WHERE e.timestamp >= ?1 AND e.timestamp <= ?2 AND e.is_afk = 0 AND e.duration > 0
          GROUP BY label
          ORDER BY total_seconds DESC
          LIMIT ?3",
-    )?;
+        apps::CANONICAL_APP,
+        apps::ALIAS_JOIN,
+    );
+    let mut stmt = conn.prepare(&sql)?;
 
     let rows = stmt.query_map(rusqlite::params![from_date, to_date, limit], |row| {
         Ok((
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use crate::config::COVERAGE_MIN_GAP_SECS;
+use crate::db::coverage;
+use crate::error::Result;
+use crate::types::{format_duration, CoveragePeriod, CoverageResponse};
+
+/// Periods in `[from, to]` when no daemon was running, oldest first.
+/// Only time after coverage recording began (and before now) is considered,
+/// and gaps shorter than `COVERAGE_MIN_GAP_SECS` are ignored.
+pub fn untracked_gaps(
+    conn: &Connection,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+    let Some(first) = coverage::first_started_at(conn)? else {
+        return Ok(Vec::new());
+    };
+    let start = (*from).max(first);
+    let end = (*to).min(Utc::now());
+    if end <= start {
+        return Ok(Vec::new());
+    }
+
+    let mut gaps = Vec::new();
+    let mut cursor = start;
+    for interval in coverage::query_intervals(conn, &start, &end)? {
+        if interval.started_at > cursor {
+            gaps.push((cursor, interval.started_at.min(end)));
+        }
+        cursor = cursor.max(interval.ended_at);
+    }
+    if cursor < end {
+        gaps.push((cursor, end));
+    }
+
+    gaps.retain(|(gap_start, gap_end)| seconds_between(gap_start, gap_end) >= COVERAGE_MIN_GAP_SECS);
+    Ok(gaps)
+}
+
+/// Periods in `[from, to]` when tracking was paused, clipped to the range and to now.
+pub fn paused_periods(
+    conn: &Connection,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+    let end = (*to).min(Utc::now());
+    Ok(coverage::query_pauses(conn, from, &end)?
+        .into_iter()
+        .map(|p| (p.started_at.max(*from), p.ended_at.min(end)))
+        .filter(|(start, end)| end > start)
+        .collect())
+}
+
+/// Tracked vs untracked time in `[from, to]`, with the untracked gaps and pauses.
+/// Time before coverage recording began or in the future counts as neither.
+pub fn build_coverage(conn: &Connection, from: &DateTime<Utc>, to: &DateTime<Utc>) -> Result<CoverageResponse> {
+    let gaps = untracked_gaps(conn, from, to)?;
+    let untracked_seconds: f64 = gaps.iter().map(|(s, e)| seconds_between(s, e)).sum();
+    let pauses = paused_periods(conn, from, to)?;
+    let paused_seconds: f64 = pauses.iter().map(|(s, e)| seconds_between(s, e)).sum();
+
+    let tracked_seconds = match coverage::first_started_at(conn)? {
+        Some(first) => {
+            let start = (*from).max(first);
+            let end = (*to).min(Utc::now());
+            (seconds_between(&start, &end) - untracked_seconds).max(0.0)
+        }
+        None => 0.0,
+    };
+
+    Ok(CoverageResponse {
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        tracked_seconds,
+        tracked_time: format_duration(tracked_seconds),
+        untracked_seconds,
+        untracked_time: format_duration(untracked_seconds),
+        paused_seconds,
+        paused_time: format_duration(paused_seconds),
+        gaps: gaps.iter().map(period).collect(),
+        pauses: pauses.iter().map(period).collect(),
+    })
+}
+
+fn period((start, end): &(DateTime<Utc>, DateTime<Utc>)) -> CoveragePeriod {
+    CoveragePeriod {
+        from: start.to_rfc3339(),
+        to: end.to_rfc3339(),
+        seconds: seconds_between(start, end),
+        time: format_duration(seconds_between(start, end)),
+    }
+}
+
+fn seconds_between(start: &DateTime<Utc>, end: &DateTime<Utc>) -> f64 {
+    (*end - *start).num_milliseconds() as f64 / 1000.0
+}
@@ -171,7 +171,7 @@ pub fn build_focus(
             total_seconds,
         })
         .collect();
-    top_distractions.sort_by(|a, b| b.switches_to.cmp(&a.switches_to));
+    top_distractions.sort_by_key(|d| std::cmp::Reverse(d.switches_to));
     top_distractions.truncate(5);
 
     // Focus score: deep_work_ratio * 0.7 + (1.0 - switch_penalty) * 0.3
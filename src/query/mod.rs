@@ -5,9 +5,42 @@ pub mod productivity;
 pub mod trends;
 pub mod current;
 pub mod focus;
+pub mod coverage;
 
 use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
 use crate::error::{Result, TimelyError};
+use crate::types::EventSource;
+
+/// Which event sources a query covers (`--source` / `--exclude-source`).
+/// Empty lists don't restrict anything.
+#[derive(Debug, Clone, Default)]
+pub struct SourceFilter {
+    /// Only events from these sources
+    pub only: Vec<EventSource>,
+    /// No events from these sources
+    pub exclude: Vec<EventSource>,
+}
+
+impl SourceFilter {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty()
+    }
+
+    /// SQL condition on `e.source`, starting with " AND ", or empty.
+    pub fn sql(&self) -> String {
+        let list = |sources: &[EventSource]| {
+            sources.iter().map(|s| format!("'{}'", s.as_str())).collect::<Vec<_>>().join(", ")
+        };
+        let mut sql = String::new();
+        if !self.only.is_empty() {
+            sql.push_str(&format!(" AND e.source IN ({})", list(&self.only)));
+        }
+        if !self.exclude.is_empty() {
+            sql.push_str(&format!(" AND e.source NOT IN ({})", list(&self.exclude)));
+        }
+        sql
+    }
+}
 
 /// Parse flexible time specifications:
 /// - "now" → current time
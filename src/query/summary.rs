@@ -1,13 +1,21 @@
 use chrono::{DateTime, Utc};
 use rusqlite::Connection;
+use std::collections::HashMap;
+
+use crate::db::{apps, events, media};
+use crate::query::{coverage, SourceFilter};
 use crate::error::{Result, TimelyError};
-use crate::types::{format_duration, SummaryGroup, SummaryResponse};
+use crate::types::{format_duration, MediaOverlap, MediaSummary, SummaryGroup, SummaryResponse};
 
 #[derive(Debug, Clone, Copy)]
 pub enum GroupBy {
     Category,
     App,
     Url,
+    /// Coding time per project (only events with a known project)
+    Project,
+    /// Coding time per language (only events with a known language)
+    Language,
 }
 
 pub fn build_summary(
@@ -16,8 +24,21 @@ pub fn build_summary(
     to: &DateTime<Utc>,
     group_by: GroupBy,
     exclude_afk: bool,
+) -> Result<SummaryResponse> {
+    build_summary_filtered(conn, from, to, group_by, exclude_afk, &SourceFilter::default())
+}
+
+/// `build_summary` over only the events `sources` covers.
+pub fn build_summary_filtered(
+    conn: &Connection,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+    group_by: GroupBy,
+    exclude_afk: bool,
+    sources: &SourceFilter,
 ) -> Result<SummaryResponse> {
     let afk_filter = if exclude_afk { " AND e.is_afk = 0" } else { "" };
+    let afk_filter = format!("{}{}", afk_filter, sources.sql());
 
     let sql = match group_by {
         GroupBy::Category =>
@@ -25,6 +46,7 @@ pub fn build_summary(
                             SUM(e.duration) as total_dur,
                             SUM(CASE WHEN e.is_afk = 0 THEN e.duration ELSE 0 END) as engaged_dur,
                             SUM(CASE WHEN e.is_afk = 1 THEN e.duration ELSE 0 END) as afk_dur,
+                            SUM(CASE WHEN e.is_idle = 1 THEN e.duration ELSE 0 END) as idle_dur,
                             COUNT(*) as cnt,
                             COALESCE(c.productivity_score, 0.0) as score
                      FROM events e
@@ -33,22 +55,25 @@ pub fn build_summary(
                      GROUP BY grp
                      ORDER BY total_dur DESC", afk_filter),
         GroupBy::App =>
-            format!("SELECT e.app as grp,
+            format!("SELECT {} as grp,
                             SUM(e.duration) as total_dur,
                             SUM(CASE WHEN e.is_afk = 0 THEN e.duration ELSE 0 END) as engaged_dur,
                             SUM(CASE WHEN e.is_afk = 1 THEN e.duration ELSE 0 END) as afk_dur,
+                            SUM(CASE WHEN e.is_idle = 1 THEN e.duration ELSE 0 END) as idle_dur,
                             COUNT(*) as cnt,
                             COALESCE(c.productivity_score, 0.0) as score
                      FROM events e
                      LEFT JOIN categories c ON c.id = e.category_id
+                     {}
                      WHERE e.timestamp >= ?1 AND e.timestamp <= ?2{}
-                     GROUP BY e.app
-                     ORDER BY total_dur DESC", afk_filter),
+                     GROUP BY grp
+                     ORDER BY total_dur DESC", apps::CANONICAL_APP, apps::ALIAS_JOIN, afk_filter),
         GroupBy::Url =>
             format!("SELECT COALESCE(e.url_domain, e.app) as grp,
                             SUM(e.duration) as total_dur,
                             SUM(CASE WHEN e.is_afk = 0 THEN e.duration ELSE 0 END) as engaged_dur,
                             SUM(CASE WHEN e.is_afk = 1 THEN e.duration ELSE 0 END) as afk_dur,
+                            SUM(CASE WHEN e.is_idle = 1 THEN e.duration ELSE 0 END) as idle_dur,
                             COUNT(*) as cnt,
                             COALESCE(c.productivity_score, 0.0) as score
                      FROM events e
@@ -56,6 +81,32 @@ pub fn build_summary(
                      WHERE e.timestamp >= ?1 AND e.timestamp <= ?2{}
                      GROUP BY grp
                      ORDER BY total_dur DESC", afk_filter),
+        GroupBy::Project =>
+            format!("SELECT e.project as grp,
+                            SUM(e.duration) as total_dur,
+                            SUM(CASE WHEN e.is_afk = 0 THEN e.duration ELSE 0 END) as engaged_dur,
+                            SUM(CASE WHEN e.is_afk = 1 THEN e.duration ELSE 0 END) as afk_dur,
+                            SUM(CASE WHEN e.is_idle = 1 THEN e.duration ELSE 0 END) as idle_dur,
+                            COUNT(*) as cnt,
+                            COALESCE(c.productivity_score, 0.0) as score
+                     FROM events e
+                     LEFT JOIN categories c ON c.id = e.category_id
+                     WHERE e.timestamp >= ?1 AND e.timestamp <= ?2 AND e.project IS NOT NULL{}
+                     GROUP BY e.project
+                     ORDER BY total_dur DESC", afk_filter),
+        GroupBy::Language =>
+            format!("SELECT e.language as grp,
+                            SUM(e.duration) as total_dur,
+                            SUM(CASE WHEN e.is_afk = 0 THEN e.duration ELSE 0 END) as engaged_dur,
+                            SUM(CASE WHEN e.is_afk = 1 THEN e.duration ELSE 0 END) as afk_dur,
+                            SUM(CASE WHEN e.is_idle = 1 THEN e.duration ELSE 0 END) as idle_dur,
+                            COUNT(*) as cnt,
+                            COALESCE(c.productivity_score, 0.0) as score
+                     FROM events e
+                     LEFT JOIN categories c ON c.id = e.category_id
+                     WHERE e.timestamp >= ?1 AND e.timestamp <= ?2 AND e.language IS NOT NULL{}
+                     GROUP BY e.language
+                     ORDER BY total_dur DESC", afk_filter),
     };
 
     let mut stmt = conn.prepare(&sql)?;
@@ -67,8 +118,9 @@ pub fn build_summary(
                 row.get::<_, f64>(1)?,
                 row.get::<_, f64>(2)?,
                 row.get::<_, f64>(3)?,
-                row.get::<_, i64>(4)?,
-                row.get::<_, f64>(5)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, f64>(6)?,
             ))
         },
     )?;
@@ -77,20 +129,24 @@ pub fn build_summary(
     let mut total_seconds: f64 = 0.0;
     let mut engaged_total_seconds: f64 = 0.0;
     let mut afk_total_seconds: f64 = 0.0;
+    let mut idle_total_seconds: f64 = 0.0;
     let mut weighted_score: f64 = 0.0;
 
     // Collect all rows
-    let mut data: Vec<(String, f64, f64, f64, i64, f64)> = Vec::new();
+    let mut data: Vec<(String, f64, f64, f64, f64, i64, f64)> = Vec::new();
     for row in rows {
-        let (label, dur, engaged_dur, afk_dur, cnt, score) = row?;
+        let (label, dur, engaged_dur, afk_dur, idle_dur, cnt, score) = row?;
         total_seconds += dur;
         engaged_total_seconds += engaged_dur;
         afk_total_seconds += afk_dur;
+        idle_total_seconds += idle_dur;
         weighted_score += dur * score;
-        data.push((label, dur, engaged_dur, afk_dur, cnt, score));
+        data.push((label, dur, engaged_dur, afk_dur, idle_dur, cnt, score));
     }
 
-    if data.is_empty() {
+    // An empty range is still worth reporting if timely wasn't running or was paused
+    let coverage = coverage::build_coverage(conn, from, to)?;
+    if data.is_empty() && coverage.untracked_seconds == 0.0 && coverage.paused_seconds == 0.0 {
         return Err(TimelyError::NoData);
     }
 
@@ -101,7 +157,7 @@ pub fn build_summary(
     };
 
     // Build groups with percentages
-    for (label, dur, engaged_dur, afk_dur, cnt, score) in &data {
+    for (label, dur, engaged_dur, afk_dur, idle_dur, cnt, score) in &data {
         let pct = if total_seconds > 0.0 {
             (*dur / total_seconds) * 100.0
         } else {
@@ -115,6 +171,8 @@ pub fn build_summary(
             engaged_time: format_duration(*engaged_dur),
             afk_seconds: *afk_dur,
             afk_time: format_duration(*afk_dur),
+            idle_seconds: *idle_dur,
+            idle_time: format_duration(*idle_dur),
             percentage: (pct * 10.0).round() / 10.0,
             productivity_score: Some(*score),
             event_count: *cnt,
@@ -130,7 +188,84 @@ pub fn build_summary(
         engaged_total_time: format_duration(engaged_total_seconds),
         afk_total_seconds,
         afk_total_time: format_duration(afk_total_seconds),
+        idle_total_seconds,
+        idle_total_time: format_duration(idle_total_seconds),
         productivity_score: (productivity_score * 100.0).round() / 100.0,
         groups,
+        media: build_media_summary(conn, from, to)?,
+        tracked_seconds: coverage.tracked_seconds,
+        tracked_time: coverage.tracked_time,
+        untracked_seconds: coverage.untracked_seconds,
+        untracked_time: coverage.untracked_time,
+        paused_seconds: coverage.paused_seconds,
+        paused_time: coverage.paused_time,
     })
 }
+
+/// Background media time per player, split by the focus category it overlapped.
+pub fn build_media_summary(conn: &Connection, from: &DateTime<Utc>, to: &DateTime<Utc>) -> Result<Vec<MediaSummary>> {
+    let media_events = media::query_media_events(conn, from, to)?;
+    if media_events.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Focused time by start, oldest first (media events already are)
+    let end_of =
+        |timestamp: DateTime<Utc>, duration: f64| timestamp + chrono::Duration::milliseconds((duration * 1000.0) as i64);
+    let mut focus: Vec<_> = events::query_events(conn, from, to, None)?
+        .into_iter()
+        .filter(|e| !e.is_afk)
+        .map(|e| (e.timestamp, end_of(e.timestamp, e.duration), e.category_name))
+        .collect();
+    focus.reverse();
+
+    let mut players: Vec<(String, f64, HashMap<String, f64>)> = Vec::new();
+    // Focus events before `first` ended before the current media event started
+    let mut first = 0;
+    for m in &media_events {
+        let idx = match players.iter().position(|(p, _, _)| *p == m.player) {
+            Some(idx) => idx,
+            None => {
+                players.push((m.player.clone(), 0.0, HashMap::new()));
+                players.len() - 1
+            }
+        };
+        let (_, total, alongside) = &mut players[idx];
+        *total += m.duration;
+
+        let m_start = m.timestamp;
+        let m_end = end_of(m_start, m.duration);
+        while first < focus.len() && focus[first].1 <= m_start {
+            first += 1;
+        }
+        for (e_start, e_end, category) in focus[first..].iter().take_while(|(e_start, _, _)| *e_start < m_end) {
+            let overlap = (m_end.min(*e_end) - m_start.max(*e_start)).num_milliseconds();
+            if overlap > 0 {
+                let category = category.clone().unwrap_or_else(|| "uncategorized".to_string());
+                *alongside.entry(category).or_default() += overlap as f64 / 1000.0;
+            }
+        }
+    }
+
+    let mut summaries: Vec<MediaSummary> = players
+        .into_iter()
+        .map(|(player, seconds, alongside)| {
+            let mut alongside: Vec<MediaOverlap> = alongside
+                .into_iter()
+                .map(|(category, seconds)| MediaOverlap {
+                    category,
+                    seconds,
+                    time: format_duration(seconds),
+                })
+                .collect();
+            alongside.sort_by(|a, b| b.seconds.total_cmp(&a.seconds));
+            MediaSummary {
+                player,
+                seconds,
+                time: format_duration(seconds),
+                alongside,
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.seconds.total_cmp(&a.seconds));
+    Ok(summaries)
+}
@@ -2,24 +2,47 @@ use chrono::{DateTime, Utc};
 use rusqlite::Connection;
 use crate::db::events;
 use crate::error::{Result, TimelyError};
+use crate::query::{coverage, SourceFilter};
 use crate::types::{format_duration, TimelineEntry, TimelineResponse};
 
+/// App name of the synthetic entries covering time when timely was not running
+pub const UNTRACKED_APP: &str = "untracked";
+/// App name of the synthetic entries covering time when tracking was paused
+pub const PAUSED_APP: &str = "paused";
+
+/// Events in the range, oldest first, interleaved with synthetic `untracked`
+/// and `paused` entries for periods when timely was not running or was
+/// paused. With a limit, the most recent entries are kept.
 pub fn build_timeline(
     conn: &Connection,
     from: &DateTime<Utc>,
     to: &DateTime<Utc>,
     limit: Option<i64>,
 ) -> Result<TimelineResponse> {
-    let mut event_list = events::query_events(conn, from, to, limit)?;
+    build_timeline_filtered(conn, from, to, limit, &SourceFilter::default())
+}
+
+/// `build_timeline` with only the events `sources` covers; the synthetic
+/// entries are kept either way.
+pub fn build_timeline_filtered(
+    conn: &Connection,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+    limit: Option<i64>,
+    sources: &SourceFilter,
+) -> Result<TimelineResponse> {
+    let mut event_list = events::query_events_filtered(conn, from, to, limit, sources)?;
+    let gaps = coverage::untracked_gaps(conn, from, to)?;
+    let pauses = coverage::paused_periods(conn, from, to)?;
 
-    if event_list.is_empty() {
+    if event_list.is_empty() && gaps.is_empty() && pauses.is_empty() {
         return Err(TimelyError::NoData);
     }
 
     // Reverse to chronological order (query returns DESC)
     event_list.reverse();
 
-    let entries: Vec<TimelineEntry> = event_list
+    let mut entries: Vec<TimelineEntry> = event_list
         .iter()
         .map(|e| TimelineEntry {
             timestamp: e.timestamp.to_rfc3339(),
@@ -27,13 +50,40 @@ pub fn build_timeline(
             duration_time: format_duration(e.duration),
             app: e.app.clone(),
             title: e.title.clone(),
+            raw_title: e.raw_title.clone(),
             url: e.url.clone(),
             category: e.category_name.clone(),
             productivity_score: None, // Could join if needed
             is_afk: e.is_afk,
+            is_idle: e.is_idle,
+            source: Some(e.source),
+            command: e.command.clone(),
+            cwd: e.cwd.clone(),
+            exit_status: e.exit_status,
+            untracked: false,
+            paused: false,
         })
         .collect();
 
+    for (start, end) in gaps {
+        entries.push(TimelineEntry {
+            untracked: true,
+            ..synthetic_entry(&start, &end, UNTRACKED_APP, "timely was not running")
+        });
+    }
+    for (start, end) in pauses {
+        entries.push(TimelineEntry {
+            paused: true,
+            ..synthetic_entry(&start, &end, PAUSED_APP, "tracking paused")
+        });
+    }
+    // Both timestamps are UTC RFC 3339, so they sort chronologically as strings
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    if let Some(limit) = limit.filter(|l| *l >= 0) {
+        let excess = entries.len().saturating_sub(limit as usize);
+        entries.drain(..excess);
+    }
+
     Ok(TimelineResponse {
         from: from.to_rfc3339(),
         to: to.to_rfc3339(),
@@ -41,3 +91,26 @@ pub fn build_timeline(
         entries,
     })
 }
+
+fn synthetic_entry(start: &DateTime<Utc>, end: &DateTime<Utc>, app: &str, title: &str) -> TimelineEntry {
+    let seconds = (*end - *start).num_milliseconds() as f64 / 1000.0;
+    TimelineEntry {
+        timestamp: start.to_rfc3339(),
+        duration_seconds: seconds,
+        duration_time: format_duration(seconds),
+        app: app.to_string(),
+        title: title.to_string(),
+        raw_title: None,
+        url: None,
+        category: None,
+        productivity_score: None,
+        is_afk: false,
+        is_idle: false,
+        source: None,
+        command: None,
+        cwd: None,
+        exit_status: None,
+        untracked: false,
+        paused: false,
+    }
+}
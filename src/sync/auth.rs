@@ -1,12 +1,53 @@
-use axum::extract::Request;
-use axum::http::StatusCode;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{header, StatusCode};
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Json, Response};
 
 use crate::db;
 use crate::db::config_store;
 
+/// Sync routes: require a matching `X-API-Key` when `sync.api_key` is set,
+/// and allow all requests (open mode) when it isn't.
 pub async fn require_api_key(req: Request, next: Next) -> Response {
+    check_api_key(req, next, true).await
+}
+
+/// Routes that write arbitrary events: always require a matching `X-API-Key`,
+/// and refuse everything while `sync.api_key` is unset.
+pub async fn require_configured_api_key(req: Request, next: Next) -> Response {
+    check_api_key(req, next, false).await
+}
+
+/// Routes for local watchers that can't send a key (ActivityWatch clients):
+/// only requests from this machine, and none from web pages. Browser
+/// extensions (aw-watcher-web) send their own origin and are allowed.
+pub async fn require_local_client(req: Request, next: Next) -> Response {
+    let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+    let origin = req.headers().get(header::ORIGIN).and_then(|v| v.to_str().ok());
+    if local_client_allowed(peer, origin) {
+        return next.run(req).await;
+    }
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({
+            "ok": false,
+            "error": "Only watchers on this machine may use this endpoint",
+            "error_code": "forbidden",
+        })),
+    )
+        .into_response()
+}
+
+fn local_client_allowed(peer: Option<SocketAddr>, origin: Option<&str>) -> bool {
+    const EXTENSION_SCHEMES: [&str; 3] = ["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+    let local = peer.is_some_and(|peer| peer.ip().is_loopback());
+    let from_page = origin.is_some_and(|origin| !EXTENSION_SCHEMES.iter().any(|s| origin.starts_with(s)));
+    local && !from_page
+}
+
+async fn check_api_key(req: Request, next: Next, open_without_key: bool) -> Response {
     // Check if hub has an API key configured
     let stored_key = tokio::task::spawn_blocking(|| {
         let conn = match db::open_default_db() {
@@ -18,11 +59,21 @@ pub async fn require_api_key(req: Request, next: Next) -> Response {
     .await
     .unwrap_or(None);
 
-    // No key configured on hub → open mode, allow all requests
-    if stored_key.is_none() {
-        return next.run(req).await;
-    }
-    let stored_key = stored_key.unwrap();
+    let Some(stored_key) = stored_key.filter(|k| !k.is_empty()) else {
+        if open_without_key {
+            // No key configured on hub → open mode, allow all requests
+            return next.run(req).await;
+        }
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "ok": false,
+                "error": "No API key configured; set sync.api_key to enable this endpoint",
+                "error_code": "forbidden",
+            })),
+        )
+            .into_response();
+    };
 
     // Key is configured → require matching X-API-Key header
     let client_key = req
@@ -44,3 +95,22 @@ pub async fn require_api_key(req: Request, next: Next) -> Response {
             .into_response(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_client_allowed() {
+        let local: SocketAddr = "127.0.0.1:50123".parse().unwrap();
+        let lan: SocketAddr = "192.168.1.20:50123".parse().unwrap();
+        assert!(local_client_allowed(Some(local), None));
+        assert!(local_client_allowed(Some("[::1]:50123".parse().unwrap()), None));
+        assert!(local_client_allowed(Some(local), Some("moz-extension://4f1c6a2e")));
+        assert!(!local_client_allowed(Some(lan), None));
+        assert!(!local_client_allowed(None, None));
+        // A web page open in the user's browser
+        assert!(!local_client_allowed(Some(local), Some("https://evil.example")));
+        assert!(!local_client_allowed(Some(local), Some("null")));
+    }
+}
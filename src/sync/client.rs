@@ -32,6 +32,17 @@ struct PushEventBody {
     url_domain: Option<String>,
     category_name: Option<String>,
     is_afk: bool,
+    is_idle: bool,
+    project: Option<String>,
+    file: Option<String>,
+    language: Option<String>,
+    branch: Option<String>,
+    command: Option<String>,
+    cwd: Option<String>,
+    exit_status: Option<i32>,
+    raw_title: Option<String>,
+    app_id: Option<String>,
+    app_path: Option<String>,
 }
 
 #[derive(Debug)]
@@ -85,6 +96,17 @@ pub fn push_events(conn: &Connection, device: &Device) -> Result<SyncPushResult>
                 url_domain: e.url_domain.clone(),
                 category_name: e.category_name.clone(),
                 is_afk: e.is_afk,
+                is_idle: e.is_idle,
+                project: e.project.clone(),
+                file: e.file.clone(),
+                language: e.language.clone(),
+                branch: e.branch.clone(),
+                command: e.command.clone(),
+                cwd: e.cwd.clone(),
+                exit_status: e.exit_status,
+                raw_title: e.raw_title.clone(),
+                app_id: e.app_id.clone(),
+                app_path: e.app_path.clone(),
             })
             .collect();
 
@@ -1,11 +1,12 @@
 use axum::extract::Json;
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::db;
 use crate::db::categories::get_category_by_name;
 use crate::db::sync as db_sync;
+use crate::types::{Event, EventSource};
 
 #[derive(Debug, Deserialize)]
 pub struct PushRequest {
@@ -22,7 +23,7 @@ pub struct PushDevice {
 
 #[derive(Debug, Deserialize)]
 pub struct PushEvent {
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
     pub duration: f64,
     pub app: String,
     pub title: String,
@@ -30,6 +31,29 @@ pub struct PushEvent {
     pub url_domain: Option<String>,
     pub category_name: Option<String>,
     pub is_afk: bool,
+    // Older clients don't send these
+    #[serde(default)]
+    pub is_idle: bool,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub exit_status: Option<i32>,
+    #[serde(default)]
+    pub raw_title: Option<String>,
+    #[serde(default)]
+    pub app_id: Option<String>,
+    #[serde(default)]
+    pub app_path: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -60,16 +84,16 @@ pub struct SyncStatusResponse {
     pub total_events: i64,
 }
 
-fn sync_error(msg: String) -> (StatusCode, axum::response::Response) {
+fn sync_error(msg: String) -> (StatusCode, Json<serde_json::Value>) {
     (
         StatusCode::INTERNAL_SERVER_ERROR,
-        Json(serde_json::json!({ "ok": false, "error": msg, "error_code": "sync_error" })).into_response(),
+        Json(serde_json::json!({ "ok": false, "error": msg, "error_code": "sync_error" })),
     )
 }
 
 pub async fn handle_push(
     Json(body): Json<PushRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, axum::response::Response)> {
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     let device = body.device;
     let events = body.events;
 
@@ -96,15 +120,31 @@ pub async fn handle_push(
 
             let is_new = db_sync::upsert_remote_event(
                 &conn,
-                &device.id,
-                &event.timestamp,
-                event.duration,
-                &event.app,
-                &event.title,
-                event.url.as_deref(),
-                event.url_domain.as_deref(),
-                category_id,
-                event.is_afk,
+                &Event {
+                    id: 0,
+                    device_id: device.id.clone(),
+                    timestamp: event.timestamp,
+                    duration: event.duration,
+                    app: event.app.clone(),
+                    title: event.title.clone(),
+                    raw_title: event.raw_title.clone(),
+                    app_id: event.app_id.clone(),
+                    app_path: event.app_path.clone(),
+                    url: event.url.clone(),
+                    url_domain: event.url_domain.clone(),
+                    category_id,
+                    category_name: None,
+                    is_afk: event.is_afk,
+                    is_idle: event.is_idle,
+                    project: event.project.clone(),
+                    file: event.file.clone(),
+                    language: event.language.clone(),
+                    branch: event.branch.clone(),
+                    command: event.command.clone(),
+                    cwd: event.cwd.clone(),
+                    exit_status: event.exit_status,
+                    source: EventSource::Sync,
+                },
             )
             .map_err(|e| sync_error(e.to_string()))?;
 
@@ -129,7 +169,7 @@ pub async fn handle_push(
 
 pub async fn handle_register(
     Json(body): Json<RegisterRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, axum::response::Response)> {
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     tokio::task::spawn_blocking(move || {
         let conn = db::open_default_db().map_err(|e| sync_error(e.to_string()))?;
 
@@ -150,7 +190,7 @@ pub async fn handle_register(
 }
 
 pub async fn handle_status(
-) -> Result<Json<serde_json::Value>, (StatusCode, axum::response::Response)> {
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     tokio::task::spawn_blocking(move || {
         let conn = db::open_default_db().map_err(|e| sync_error(e.to_string()))?;
 
@@ -9,11 +9,88 @@ pub struct Event {
     pub duration: f64,
     pub app: String,
     pub title: String,
+    /// The title as first seen, when normalization changed it
+    #[serde(default)]
+    pub raw_title: Option<String>,
+    /// Bundle id (macOS) or WM_CLASS (Linux) of the app, when known
+    #[serde(default)]
+    pub app_id: Option<String>,
+    /// The app's executable or bundle path, when known
+    #[serde(default)]
+    pub app_path: Option<String>,
     pub url: Option<String>,
     pub url_domain: Option<String>,
     pub category_id: Option<i64>,
     pub category_name: Option<String>,
     pub is_afk: bool,
+    /// No input, even if the activity still counts as engaged (`is_afk` false)
+    #[serde(default)]
+    pub is_idle: bool,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub exit_status: Option<i32>,
+    /// How the event got into the database
+    #[serde(default)]
+    pub source: EventSource,
+}
+
+/// How an event got into the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSource {
+    /// This machine's daemon: window, idle, sleep and lock tracking
+    #[default]
+    Watcher,
+    /// `timely import`
+    Import,
+    /// Pushed by another device (`timely sync`)
+    Sync,
+    /// `timely ingest` and `POST /api/events`
+    Ingest,
+    /// The ActivityWatch-compatible API
+    #[value(name = "activitywatch")]
+    ActivityWatch,
+    /// The WakaTime-compatible API
+    #[value(name = "wakatime")]
+    WakaTime,
+    /// AFK time labeled as offline activity (`timely afk label`)
+    Label,
+}
+
+impl EventSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventSource::Watcher => "watcher",
+            EventSource::Import => "import",
+            EventSource::Sync => "sync",
+            EventSource::Ingest => "ingest",
+            EventSource::ActivityWatch => "activitywatch",
+            EventSource::WakaTime => "wakatime",
+            EventSource::Label => "label",
+        }
+    }
+
+    /// The source stored as `name`; unknown names read as `Watcher`.
+    pub fn from_name(name: &str) -> Self {
+        <Self as clap::ValueEnum>::from_str(name, false).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for EventSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,13 +112,84 @@ pub struct CategoryRule {
     pub priority: i32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WatcherSnapshot {
     pub app: String,
     pub title: String,
+    /// The title before normalization, when it changed
+    pub raw_title: Option<String>,
+    /// Stable identity of the app: bundle id (macOS) or WM_CLASS (Linux)
+    pub app_id: Option<String>,
+    /// The app's executable (Linux) or bundle (macOS) path
+    pub app_path: Option<String>,
+    /// Process id of the focused window, when the platform reports it
+    #[serde(skip)]
+    pub pid: Option<u32>,
     pub url: Option<String>,
     pub url_domain: Option<String>,
     pub is_afk: bool,
+    /// No input; set alongside `is_afk`, and kept when engaged activity clears it
+    #[serde(default)]
+    pub is_idle: bool,
+    /// A private/incognito browser window, when the reporter says so
+    #[serde(default)]
+    pub is_private: bool,
+    /// Coding context, when known (editor plugins, repository detection)
+    pub project: Option<String>,
+    pub file: Option<String>,
+    pub language: Option<String>,
+    pub branch: Option<String>,
+    /// Working directory of the focused terminal's foreground process
+    pub cwd: Option<String>,
+    /// Shell command running in the focused terminal (from shell hooks)
+    pub command: Option<String>,
+    /// Exit status of `command`, reported once when it finishes
+    pub exit_status: Option<i32>,
+    /// Media playing in the background; recorded separately from focus
+    pub media: Option<MediaPlayback>,
+    /// What reported the snapshot
+    #[serde(default)]
+    pub source: EventSource,
+}
+
+/// What a media player is currently playing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaPlayback {
+    /// Player name, e.g. "Spotify"
+    pub player: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaEvent {
+    pub id: i64,
+    pub device_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub duration: f64,
+    pub player: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+/// A period during which the daemon was running on a device. `ended_at` is the
+/// stop time, or the last heartbeat if the daemon is still running or crashed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageInterval {
+    pub id: i64,
+    pub device_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub stopped: bool,
+}
+
+/// A period during which tracking was paused by the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseInterval {
+    pub id: i64,
+    pub device_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +209,11 @@ pub struct SummaryGroup {
     pub engaged_time: String,
     pub afk_seconds: f64,
     pub afk_time: String,
+    /// Time without input, whether AFK or engaged
+    #[serde(default)]
+    pub idle_seconds: f64,
+    #[serde(default)]
+    pub idle_time: String,
     pub percentage: f64,
     pub productivity_score: Option<f64>,
     pub event_count: i64,
@@ -76,8 +229,46 @@ pub struct SummaryResponse {
     pub engaged_total_time: String,
     pub afk_total_seconds: f64,
     pub afk_total_time: String,
+    /// Time without input, including idle time that counts as engaged
+    #[serde(default)]
+    pub idle_total_seconds: f64,
+    #[serde(default)]
+    pub idle_total_time: String,
     pub productivity_score: f64,
     pub groups: Vec<SummaryGroup>,
+    /// Background media per player, with the focus categories it played alongside
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<MediaSummary>,
+    /// Time in the range the daemon was running
+    #[serde(default)]
+    pub tracked_seconds: f64,
+    #[serde(default)]
+    pub tracked_time: String,
+    /// Time in the range the daemon was not running (e.g. stopped or crashed)
+    #[serde(default)]
+    pub untracked_seconds: f64,
+    #[serde(default)]
+    pub untracked_time: String,
+    /// Time in the range tracking was paused
+    #[serde(default)]
+    pub paused_seconds: f64,
+    #[serde(default)]
+    pub paused_time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaSummary {
+    pub player: String,
+    pub seconds: f64,
+    pub time: String,
+    pub alongside: Vec<MediaOverlap>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaOverlap {
+    pub category: String,
+    pub seconds: f64,
+    pub time: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,10 +278,31 @@ pub struct TimelineEntry {
     pub duration_time: String,
     pub app: String,
     pub title: String,
+    /// The title as seen, when normalization changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_title: Option<String>,
     pub url: Option<String>,
     pub category: Option<String>,
     pub productivity_score: Option<f64>,
     pub is_afk: bool,
+    /// No input, though the activity may count as engaged (a video call)
+    #[serde(default)]
+    pub is_idle: bool,
+    /// How the event got into the database; none for synthetic entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<EventSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_status: Option<i32>,
+    /// Synthetic entry for a period when timely was not running
+    #[serde(default)]
+    pub untracked: bool,
+    /// Synthetic entry for a period when tracking was paused
+    #[serde(default)]
+    pub paused: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,6 +313,29 @@ pub struct TimelineResponse {
     pub entries: Vec<TimelineEntry>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveragePeriod {
+    pub from: String,
+    pub to: String,
+    pub seconds: f64,
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageResponse {
+    pub from: String,
+    pub to: String,
+    pub tracked_seconds: f64,
+    pub tracked_time: String,
+    pub untracked_seconds: f64,
+    pub untracked_time: String,
+    /// Time the daemon was running but paused (part of the tracked time)
+    pub paused_seconds: f64,
+    pub paused_time: String,
+    pub gaps: Vec<CoveragePeriod>,
+    pub pauses: Vec<CoveragePeriod>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NowResponse {
     pub app: String,
@@ -113,6 +348,9 @@ pub struct NowResponse {
     pub duration_time: String,
     pub is_afk: bool,
     pub stale: bool,
+    /// Tracking is paused, so this activity is not being recorded
+    #[serde(default)]
+    pub paused: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,10 +359,108 @@ pub struct DaemonStatus {
     pub pid: Option<u32>,
     pub uptime_seconds: Option<f64>,
     pub uptime_time: Option<String>,
+    /// Whether the daemon answered on its control socket. The fields below
+    /// are only known when it did.
+    #[serde(default)]
+    pub responding: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
+    /// When a timed pause ends; absent for an indefinite pause
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_until: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_heartbeat_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watchers: Vec<WatcherHealth>,
+    /// The service manager's view of the daemon, when it runs as a systemd user service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<ServiceStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub unit: String,
+    /// systemd ActiveState, e.g. "active", "activating", "failed"
+    pub active_state: String,
+    pub sub_state: String,
+    pub main_pid: Option<u32>,
+    /// How often systemd has restarted the daemon (crashes, watchdog timeouts)
+    pub restarts: Option<u32>,
+}
+
+/// A stretch of time away from the computer: consecutive AFK events on one device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AfkPeriod {
+    /// Id of the period's first event; `timely afk label <id>` labels the whole period
+    pub id: i64,
+    pub event_ids: Vec<i64>,
+    pub from: String,
+    pub to: String,
+    pub seconds: f64,
+    pub time: String,
+    /// "idle", "sleep" or "locked"
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AfkLabelResult {
+    pub category: String,
+    pub note: Option<String>,
+    /// AFK events (or parts of them) turned into offline activity
+    pub events: usize,
+    pub seconds: f64,
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherHealth {
+    pub name: String,
+    pub interval_secs: f64,
+    pub last_poll_at: Option<String>,
+    /// Error from the most recent poll, if it failed
+    pub error: Option<String>,
+}
+
+/// What the running daemon sees right now, from its control socket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveSnapshot {
+    pub paused: bool,
+    /// The last snapshot matched an exclusion and was not recorded
+    #[serde(default)]
+    pub excluded: bool,
+    /// When the watchers were last collected
+    pub at: Option<String>,
+    pub snapshot: Option<WatcherSnapshot>,
+    /// The event the last heartbeat was recorded on
+    pub current: Option<Event>,
 }
 
 // --- Web API response types (match Express dashboard shapes exactly) ---
 
+/// Another name for an app, grouped under `canonical`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppAlias {
+    pub alias: String,
+    pub canonical: String,
+}
+
+/// An app name as recorded, with what identifies it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppInfo {
+    pub app: String,
+    pub canonical: String,
+    /// Bundle id (macOS) or WM_CLASS (Linux), as last seen
+    pub app_id: Option<String>,
+    /// Executable or bundle path, as last seen
+    pub app_path: Option<String>,
+    pub last_seen: String,
+    pub events: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppBreakdown {
     pub app: String,
@@ -0,0 +1,165 @@
+use crate::error::Result;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Last observed input-interrupt fingerprint and when it last changed.
+/// Only used by the `/proc/interrupts` fallback, which has no idle counter of its own.
+static INPUT_ACTIVITY: Mutex<Option<(u64, Instant)>> = Mutex::new(None);
+
+/// Idle state reported by systemd-logind for the current session.
+#[derive(Debug, PartialEq)]
+struct LogindState {
+    idle_hint: bool,
+    locked_hint: bool,
+    /// IdleSinceHint, in microseconds since the Unix epoch (0 if unset)
+    idle_since_us: u64,
+}
+
+/// Returns true when the user has been idle for longer than `threshold_secs`.
+///
+/// Sources are tried in order, first one that answers wins:
+/// 1. X11 MIT-SCREEN-SAVER idle time (via `xprintidle`)
+/// 2. logind session `IdleHint` / `LockedHint` (via `loginctl`, which talks D-Bus)
+/// 3. Input-device activity inferred from `/proc/interrupts`
+pub fn is_afk(threshold_secs: u64) -> Result<bool> {
+    let threshold = threshold_secs as f64;
+
+    if let Some(idle) = x11_idle_secs() {
+        return Ok(idle > threshold);
+    }
+
+    if let Some(state) = logind_state() {
+        if state.locked_hint {
+            return Ok(true);
+        }
+        if !state.idle_hint {
+            return Ok(false);
+        }
+        let now_us = chrono::Utc::now().timestamp_micros().max(0) as u64;
+        let idle = now_us.saturating_sub(state.idle_since_us) as f64 / 1_000_000.0;
+        return Ok(idle > threshold);
+    }
+
+    Ok(input_idle_secs().is_some_and(|idle| idle > threshold))
+}
+
+fn x11_idle_secs() -> Option<f64> {
+    std::env::var_os("DISPLAY")?;
+    let output = Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let ms: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(ms as f64 / 1000.0)
+}
+
+fn logind_state() -> Option<LogindState> {
+    // `auto` resolves to the caller's session on systemd >= 243
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = Command::new("loginctl")
+        .args(["show-session", &session, "-p", "IdleHint", "-p", "LockedHint", "-p", "IdleSinceHint"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_logind_state(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `loginctl show-session` output (`Key=value` lines).
+fn parse_logind_state(output: &str) -> Option<LogindState> {
+    let mut idle_hint = None;
+    let mut locked_hint = false;
+    let mut idle_since_us = 0;
+
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some(("IdleHint", v)) => idle_hint = Some(v.trim() == "yes"),
+            Some(("LockedHint", v)) => locked_hint = v.trim() == "yes",
+            Some(("IdleSinceHint", v)) => idle_since_us = v.trim().parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    Some(LogindState {
+        idle_hint: idle_hint?,
+        locked_hint,
+        idle_since_us,
+    })
+}
+
+/// Seconds since input-related interrupt counters last changed.
+/// Returns None if no input controller could be found in `/proc/interrupts`.
+fn input_idle_secs() -> Option<f64> {
+    let content = std::fs::read_to_string("/proc/interrupts").ok()?;
+    let fingerprint = input_interrupt_count(&content)?;
+    let now = Instant::now();
+
+    let mut last = INPUT_ACTIVITY.lock().ok()?;
+    match *last {
+        Some((prev, since)) if prev == fingerprint => Some(now.duration_since(since).as_secs_f64()),
+        _ => {
+            *last = Some((fingerprint, now));
+            Some(0.0)
+        }
+    }
+}
+
+/// Controllers keyboards, mice and touchpads hang off: PS/2, HID-over-I2C and
+/// the USB host controllers.
+const INPUT_CONTROLLERS: &[&str] = &["i8042", "i2c_hid", "i2c-hid", "xhci", "ehci", "ohci", "uhci"];
+
+/// Sum interrupt counts across all CPUs for the `INPUT_CONTROLLERS`. USB host
+/// controllers are counted so USB keyboards and mice aren't missed, at the
+/// cost that other busy USB devices (storage, webcams) also count as input;
+/// that errs towards active rather than AFK.
+fn input_interrupt_count(content: &str) -> Option<u64> {
+    let mut total = None;
+    for line in content.lines().skip(1) {
+        let lower = line.to_lowercase();
+        if !INPUT_CONTROLLERS.iter().any(|name| lower.contains(name)) {
+            continue;
+        }
+        let counts: u64 = line
+            .split_whitespace()
+            .skip(1)
+            .map_while(|field| field.parse::<u64>().ok())
+            .sum();
+        *total.get_or_insert(0) += counts;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logind_state() {
+        let state = parse_logind_state("IdleHint=yes\nLockedHint=no\nIdleSinceHint=1700000000000000\n").unwrap();
+        assert_eq!(
+            state,
+            LogindState {
+                idle_hint: true,
+                locked_hint: false,
+                idle_since_us: 1_700_000_000_000_000,
+            }
+        );
+        assert!(parse_logind_state("").is_none());
+    }
+
+    #[test]
+    fn test_input_interrupt_count() {
+        let content = "           CPU0       CPU1\n\
+                         1:        120         30   IO-APIC   1-edge      i8042\n\
+                        12:       5000         10   IO-APIC  12-edge      i8042\n\
+                       127:        900        900   PCI-MSI 327680-edge      xhci_hcd\n\
+                       NMI:          0          0   Non-maskable interrupts\n";
+        assert_eq!(input_interrupt_count(content), Some(6960));
+        // USB-only input, e.g. a desktop without PS/2
+        let usb = "           CPU0\n  16:  42  IO-APIC  16-fasteoi  ehci_hcd:usb1\n";
+        assert_eq!(input_interrupt_count(usb), Some(42));
+        assert_eq!(input_interrupt_count("           CPU0\n  0:  5  IO-APIC  2-edge  timer\n"), None);
+    }
+}
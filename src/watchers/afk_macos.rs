@@ -1,9 +1,9 @@
 use crate::error::Result;
 use std::process::Command;
 
-const AFK_THRESHOLD_NS: u64 = 180_000_000_000; // 3 minutes in nanoseconds
+pub fn is_afk(threshold_secs: u64) -> Result<bool> {
+    let threshold_ns = threshold_secs.saturating_mul(1_000_000_000);
 
-pub fn is_afk() -> Result<bool> {
     let output = Command::new("ioreg")
         .args(["-c", "IOHIDSystem", "-d", "4"])
         .output()?;
@@ -16,7 +16,7 @@ pub fn is_afk() -> Result<bool> {
             if let Some(val_str) = line.split('=').nth(1) {
                 let val_str = val_str.trim();
                 if let Ok(idle_ns) = val_str.parse::<u64>() {
-                    return Ok(idle_ns > AFK_THRESHOLD_NS);
+                    return Ok(idle_ns > threshold_ns);
                 }
             }
         }
@@ -1,3 +1,4 @@
+use super::extract_domain;
 use crate::error::Result;
 use std::process::Command;
 
@@ -59,19 +60,3 @@ end tell"#
         Ok(None)
     }
 }
-
-fn extract_domain(url: &str) -> String {
-    // Simple domain extraction without pulling in the url crate
-    let without_scheme = url
-        .strip_prefix("https://")
-        .or_else(|| url.strip_prefix("http://"))
-        .unwrap_or(url);
-    without_scheme
-        .split('/')
-        .next()
-        .unwrap_or("")
-        .split(':')
-        .next()
-        .unwrap_or("")
-        .to_string()
-}
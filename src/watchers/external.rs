@@ -0,0 +1,283 @@
+use std::io::{BufRead, BufReader};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::registry::{SnapshotPatch, Watcher};
+use crate::error::{Result, TimelyError};
+use crate::types::WatcherSnapshot;
+
+const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// A plugin that stayed up this long before exiting restarts without delay growth.
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+enum State {
+    Running { child: Child, started: Instant },
+    Waiting { until: Instant },
+}
+
+/// Watcher backed by an external executable that prints one JSON snapshot per
+/// line on stdout (any subset of `app`, `title`, `url`, `url_domain`, `is_afk`,
+/// `project`, `file`, `language`, `branch`, `media`).
+/// The latest line is the plugin's current contribution; `{}` clears it.
+///
+/// Configured with `watchers.<name>.command`, run with `sh -c`, and optionally
+/// `watchers.<name>.apps` (comma-separated) to only apply while one of those
+/// apps is focused. Plugins that exit are restarted with exponential backoff.
+pub struct ExternalWatcher {
+    name: String,
+    command: String,
+    apps: Vec<String>,
+    latest: Arc<Mutex<Option<SnapshotPatch>>>,
+    state: State,
+    backoff: Duration,
+    backoff_initial: Duration,
+    backoff_max: Duration,
+}
+
+impl ExternalWatcher {
+    pub fn new(name: &str, command: &str, apps: Vec<String>) -> Result<Self> {
+        if command.trim().is_empty() {
+            return Err(TimelyError::Config(format!("watchers.{}.command is empty", name)));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            command: command.to_string(),
+            apps,
+            latest: Arc::new(Mutex::new(None)),
+            state: State::Waiting { until: Instant::now() },
+            backoff: BACKOFF_INITIAL,
+            backoff_initial: BACKOFF_INITIAL,
+            backoff_max: BACKOFF_MAX,
+        })
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.backoff_initial = initial;
+        self.backoff_max = max;
+        self
+    }
+
+    /// Spawn the plugin if it isn't running and its backoff has elapsed,
+    /// or notice that it exited and schedule the restart.
+    fn supervise(&mut self) {
+        let now = Instant::now();
+        match &mut self.state {
+            State::Running { child, started } => {
+                let exited = match child.try_wait() {
+                    Ok(Some(status)) => Some(status.to_string()),
+                    Ok(None) => None,
+                    Err(e) => Some(e.to_string()),
+                };
+                if let Some(status) = exited {
+                    if now.duration_since(*started) >= STABLE_RUN {
+                        self.backoff = self.backoff_initial;
+                    }
+                    eprintln!(
+                        "watcher {}: plugin exited ({}), restarting in {:?}",
+                        self.name, status, self.backoff
+                    );
+                    *self.latest.lock().unwrap() = None;
+                    self.state = State::Waiting { until: now + self.backoff };
+                    self.backoff = (self.backoff * 2).min(self.backoff_max);
+                }
+            }
+            State::Waiting { until } => {
+                if now < *until {
+                    return;
+                }
+                match self.spawn() {
+                    Ok(child) => self.state = State::Running { child, started: now },
+                    Err(e) => {
+                        eprintln!(
+                            "watcher {}: cannot start {} ({}), retrying in {:?}",
+                            self.name, self.command, e, self.backoff
+                        );
+                        self.state = State::Waiting { until: now + self.backoff };
+                        self.backoff = (self.backoff * 2).min(self.backoff_max);
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn(&self) -> std::io::Result<Child> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            // Its own process group, so the plugin goes down with the shell
+            .process_group(0)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let latest = self.latest.clone();
+        let name = self.name.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<SnapshotPatch>(&line) {
+                    Ok(patch) => *latest.lock().unwrap() = Some(patch),
+                    Err(e) => eprintln!("watcher {}: ignoring invalid line: {}", name, e),
+                }
+            }
+        });
+
+        Ok(child)
+    }
+}
+
+impl Watcher for ExternalWatcher {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn enrich(&mut self, snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        self.supervise();
+
+        if !self.apps.is_empty() && !self.apps.iter().any(|a| a.eq_ignore_ascii_case(&snapshot.app)) {
+            return Ok(SnapshotPatch::default());
+        }
+        Ok(self.latest.lock().unwrap().clone().unwrap_or_default())
+    }
+}
+
+impl Drop for ExternalWatcher {
+    fn drop(&mut self) {
+        if let State::Running { child, .. } = &mut self.state {
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+
+    fn write_script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn empty_snapshot(app: &str) -> WatcherSnapshot {
+        WatcherSnapshot {
+            app: app.to_string(),
+            title: String::new(),
+            url: None,
+            url_domain: None,
+            is_afk: false,
+            ..Default::default()
+        }
+    }
+
+    /// Poll until the plugin contributes something, or give up after 5s.
+    fn wait_for_patch(watcher: &mut ExternalWatcher, app: &str) -> SnapshotPatch {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let patch = watcher.enrich(&empty_snapshot(app)).unwrap();
+            if patch != SnapshotPatch::default() || Instant::now() > deadline {
+                return patch;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_reads_latest_json_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = write_script(
+            dir.path(),
+            "vm-viewer",
+            r#"echo 'not json'
+echo '{"app": "virt-manager", "title": "win11"}'
+echo '{"app": "Windows 11 VM", "title": "Excel"}'
+exec sleep 30"#,
+        );
+        let mut watcher = ExternalWatcher::new("vm", script.to_str().unwrap(), vec![]).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut patch = wait_for_patch(&mut watcher, "virt-manager");
+        while patch.app.as_deref() != Some("Windows 11 VM") && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+            patch = watcher.enrich(&empty_snapshot("virt-manager")).unwrap();
+        }
+        assert_eq!(patch.app.as_deref(), Some("Windows 11 VM"));
+        assert_eq!(patch.title.as_deref(), Some("Excel"));
+    }
+
+    #[test]
+    fn test_command_runs_in_a_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugins = dir.path().join("my plugins");
+        std::fs::create_dir(&plugins).unwrap();
+        let script = write_script(
+            &plugins,
+            "titler",
+            r#"echo "{\"title\": \"$1\"}"
+exec sleep 30"#,
+        );
+        let command = format!("'{}' 'two words'", script.display());
+        let mut watcher = ExternalWatcher::new("titler", &command, vec![]).unwrap();
+
+        let patch = wait_for_patch(&mut watcher, "");
+        assert_eq!(patch.title.as_deref(), Some("two words"));
+    }
+
+    #[test]
+    fn test_app_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = write_script(
+            dir.path(),
+            "ide",
+            r#"echo '{"url": "file:///src/main.rs"}'
+exec sleep 30"#,
+        );
+        let mut watcher =
+            ExternalWatcher::new("ide", script.to_str().unwrap(), vec!["MyIDE".into()]).unwrap();
+
+        let patch = wait_for_patch(&mut watcher, "myide");
+        assert_eq!(patch.url.as_deref(), Some("file:///src/main.rs"));
+        assert_eq!(watcher.enrich(&empty_snapshot("Firefox")).unwrap(), SnapshotPatch::default());
+    }
+
+    #[test]
+    fn test_restarts_crashed_plugin_with_backoff() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("starts");
+        let script = write_script(
+            dir.path(),
+            "crashy",
+            &format!("echo start >> {}\nexit 1", log.display()),
+        );
+        let mut watcher = ExternalWatcher::new("crashy", script.to_str().unwrap(), vec![])
+            .unwrap()
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(40));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let starts = || std::fs::read_to_string(&log).unwrap_or_default().lines().count();
+        while starts() < 3 && Instant::now() < deadline {
+            watcher.enrich(&empty_snapshot("")).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(starts() >= 3);
+        assert_eq!(watcher.backoff, Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_empty_command_rejected() {
+        assert!(ExternalWatcher::new("empty", "  ", vec![]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use std::process::Command;
+use std::time::Duration;
+
+use super::registry::{SnapshotPatch, Watcher};
+use crate::error::Result;
+use crate::types::{MediaPlayback, WatcherSnapshot};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Background media from MPRIS players on the session bus (Spotify, browsers,
+/// mpv, ...). Reports the first player whose `PlaybackStatus` is `Playing`.
+pub struct MediaWatcher;
+
+impl Watcher for MediaWatcher {
+    fn name(&self) -> &str {
+        "media"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        Ok(SnapshotPatch {
+            media: playing_media(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Query every MPRIS player via `busctl --user` and return what is playing, if anything.
+pub fn playing_media() -> Option<MediaPlayback> {
+    let names = busctl(&[
+        "call",
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        "ListNames",
+    ])?;
+
+    parse_player_names(&names).into_iter().find_map(|name| {
+        let output = busctl(&[
+            "get-property",
+            &name,
+            MPRIS_PATH,
+            "org.mpris.MediaPlayer2.Player",
+            "PlaybackStatus",
+            "Metadata",
+        ])?;
+        let (status, title, artist) = parse_player_state(&output)?;
+        if status != "Playing" {
+            return None;
+        }
+
+        let player = busctl(&["get-property", &name, MPRIS_PATH, "org.mpris.MediaPlayer2", "Identity"])
+            .and_then(|out| json_data(&out).and_then(|v| v.as_str().map(String::from)))
+            .unwrap_or_else(|| player_from_bus_name(&name));
+        Some(MediaPlayback { player, title, artist })
+    })
+}
+
+fn busctl(args: &[&str]) -> Option<String> {
+    let output = Command::new("busctl")
+        .args(["--user", "--json=short"])
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `data` of a busctl JSON reply (`{"type": "s", "data": ...}`).
+fn json_data(line: &str) -> Option<serde_json::Value> {
+    let mut value: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    Some(value.get_mut("data")?.take())
+}
+
+/// MPRIS bus names from a `ListNames` reply, sorted for a stable pick.
+fn parse_player_names(output: &str) -> Vec<String> {
+    let Some(data) = json_data(output) else {
+        return Vec::new();
+    };
+    // Method replies wrap the out-arguments in an array: [["name", ...]]
+    let mut names: Vec<String> = data
+        .get(0)
+        .and_then(|names| names.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.as_str())
+        .filter(|name| name.starts_with(MPRIS_PREFIX))
+        .map(String::from)
+        .collect();
+    names.sort();
+    names
+}
+
+/// (status, title, artists) from the two-line `get-property PlaybackStatus Metadata` reply.
+fn parse_player_state(output: &str) -> Option<(String, Option<String>, Option<String>)> {
+    let mut lines = output.lines().filter(|l| !l.trim().is_empty());
+    let status = json_data(lines.next()?)?.as_str()?.to_string();
+    let metadata = lines.next().and_then(json_data).unwrap_or_default();
+
+    let title = metadata
+        .pointer("/xesam:title/data")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from);
+    let artist = metadata
+        .pointer("/xesam:artist/data")
+        .and_then(|v| v.as_array())
+        .map(|artists| {
+            artists
+                .iter()
+                .filter_map(|a| a.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .filter(|s| !s.is_empty());
+    Some((status, title, artist))
+}
+
+/// `org.mpris.MediaPlayer2.firefox.instance_1_42` -> `firefox`
+fn player_from_bus_name(name: &str) -> String {
+    let rest = name.strip_prefix(MPRIS_PREFIX).unwrap_or(name);
+    rest.split('.').next().unwrap_or(rest).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_player_names() {
+        let output = r#"{"type":"as","data":[["org.freedesktop.DBus","org.mpris.MediaPlayer2.spotify",":1.7","org.mpris.MediaPlayer2.firefox.instance_1_42"]]}"#;
+        assert_eq!(
+            parse_player_names(output),
+            vec!["org.mpris.MediaPlayer2.firefox.instance_1_42", "org.mpris.MediaPlayer2.spotify"]
+        );
+        assert!(parse_player_names("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_player_state() {
+        let output = r#"{"type":"s","data":"Playing"}
+{"type":"a{sv}","data":{"xesam:title":{"type":"s","data":"Teardrop"},"xesam:artist":{"type":"as","data":["Massive Attack","Elizabeth Fraser"]},"mpris:length":{"type":"x","data":330000000}}}
+"#;
+        let (status, title, artist) = parse_player_state(output).unwrap();
+        assert_eq!(status, "Playing");
+        assert_eq!(title.as_deref(), Some("Teardrop"));
+        assert_eq!(artist.as_deref(), Some("Massive Attack, Elizabeth Fraser"));
+
+        let paused = "{\"type\":\"s\",\"data\":\"Paused\"}\n{\"type\":\"a{sv}\",\"data\":{}}\n";
+        assert_eq!(parse_player_state(paused), Some(("Paused".to_string(), None, None)));
+    }
+
+    #[test]
+    fn test_player_from_bus_name() {
+        assert_eq!(player_from_bus_name("org.mpris.MediaPlayer2.spotify"), "spotify");
+        assert_eq!(player_from_bus_name("org.mpris.MediaPlayer2.firefox.instance_1_42"), "firefox");
+    }
+
+    #[test]
+    #[ignore = "requires a D-Bus session running tests/fixtures/mock_mpris.py"]
+    fn test_playing_media_against_session_bus() {
+        let media = playing_media().unwrap();
+        assert_eq!(media.player, "Mock Player");
+        assert_eq!(media.title.as_deref(), Some("Song"));
+        assert_eq!(media.artist.as_deref(), Some("Band"));
+    }
+}
@@ -1,3 +1,10 @@
+pub mod external;
+pub mod native;
+pub mod project;
+pub mod shell;
+pub mod registry;
+pub mod replay;
+pub mod tui;
 #[cfg(target_os = "macos")]
 pub mod afk_macos;
 #[cfg(target_os = "macos")]
@@ -6,45 +13,84 @@ pub mod window_macos;
 pub mod browser_macos;
 #[cfg(target_os = "macos")]
 pub mod tui_macos;
+#[cfg(target_os = "linux")]
+pub mod window_linux;
+#[cfg(target_os = "linux")]
+pub mod afk_linux;
+#[cfg(target_os = "linux")]
+pub mod window_sway;
+#[cfg(target_os = "linux")]
+pub mod window_hyprland;
+#[cfg(target_os = "linux")]
+pub mod tui_linux;
+#[cfg(target_os = "linux")]
+pub mod media_linux;
 
-use crate::error::Result;
-use crate::types::WatcherSnapshot;
+pub use registry::{SnapshotPatch, Watcher, WatcherRegistry};
 
-pub fn collect_snapshot() -> Result<WatcherSnapshot> {
-    #[cfg(target_os = "macos")]
-    {
-        let window = window_macos::get_active_window()?;
-        let is_afk = afk_macos::is_afk().unwrap_or(false);
+/// Host part of a URL (`https://github.com:443/x` -> `github.com`).
+pub fn extract_domain(url: &str) -> String {
+    // Simple domain extraction without pulling in the url crate
+    let without_scheme = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    without_scheme
+        .split('/')
+        .next()
+        .unwrap_or("")
+        .split(':')
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
 
-        let (url, url_domain) = match browser_macos::get_browser_tab(&window.app) {
-            Ok(Some(tab)) => (Some(tab.url), Some(tab.domain)),
-            _ => (None, None),
-        };
+/// Which active-window source to use on Linux.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinuxBackend {
+    /// sway's i3-ipc socket (`$SWAYSOCK`)
+    Sway,
+    /// Hyprland's request socket (`$HYPRLAND_INSTANCE_SIGNATURE`)
+    Hyprland,
+    /// EWMH properties via `xprop` (X11 sessions and XWayland fallback)
+    X11,
+}
 
-        // If the active app is a terminal, try to detect the TUI process inside it
-        let app = if tui_macos::is_terminal_app(&window.app) {
-            if let Some(tui) = tui_macos::detect_tui_process() {
-                tui.app_name
-            } else {
-                window.app
-            }
-        } else {
-            window.app
-        };
+#[cfg(target_os = "linux")]
+impl LinuxBackend {
+    /// Pick the backend from the session environment. Wayland has no global
+    /// active-window API, so compositor IPC takes precedence over X11.
+    pub fn detect() -> Self {
+        Self::from_env(
+            std::env::var("XDG_CURRENT_DESKTOP").ok().as_deref(),
+            std::env::var_os("SWAYSOCK").is_some(),
+            std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some(),
+        )
+    }
 
-        Ok(WatcherSnapshot {
-            app,
-            title: window.title,
-            url,
-            url_domain,
-            is_afk,
-        })
+    fn from_env(current_desktop: Option<&str>, has_swaysock: bool, has_hyprland_sig: bool) -> Self {
+        let desktop = current_desktop.unwrap_or_default().to_lowercase();
+        if has_hyprland_sig || desktop.split(':').any(|d| d == "hyprland") {
+            LinuxBackend::Hyprland
+        } else if has_swaysock || desktop.split(':').any(|d| d == "sway") {
+            LinuxBackend::Sway
+        } else {
+            LinuxBackend::X11
+        }
     }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        Err(crate::error::TimelyError::PlatformNotSupported(
-            std::env::consts::OS.to_string(),
-        ))
+    #[test]
+    fn test_linux_backend_detection() {
+        assert_eq!(LinuxBackend::from_env(Some("sway"), true, false), LinuxBackend::Sway);
+        assert_eq!(LinuxBackend::from_env(Some("Hyprland"), false, true), LinuxBackend::Hyprland);
+        assert_eq!(LinuxBackend::from_env(None, true, false), LinuxBackend::Sway);
+        assert_eq!(LinuxBackend::from_env(Some("ubuntu:GNOME"), false, false), LinuxBackend::X11);
+        assert_eq!(LinuxBackend::from_env(None, false, false), LinuxBackend::X11);
     }
 }
@@ -0,0 +1,163 @@
+#[cfg(target_os = "macos")]
+use std::time::Duration;
+
+use super::registry::{SnapshotPatch, Watcher};
+use crate::error::Result;
+use crate::types::WatcherSnapshot;
+
+/// Watchers enabled when `watchers.enabled` is not set.
+#[cfg(target_os = "macos")]
+pub const DEFAULT_WATCHERS: &[&str] = &["window", "afk", "browser", "shell", "project"];
+#[cfg(target_os = "linux")]
+pub const DEFAULT_WATCHERS: &[&str] = &["window", "afk", "shell", "project", "media"];
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub const DEFAULT_WATCHERS: &[&str] = &["window", "afk", "shell", "project"];
+
+/// Build one of the platform watchers by name.
+pub fn build(name: &str, afk_threshold_secs: u64) -> Option<Box<dyn Watcher>> {
+    match name {
+        "window" => Some(Box::new(WindowWatcher)),
+        "afk" => Some(Box::new(AfkWatcher {
+            threshold_secs: afk_threshold_secs,
+        })),
+        #[cfg(target_os = "macos")]
+        "browser" => Some(Box::new(BrowserWatcher)),
+        "project" => Some(Box::new(super::project::ProjectWatcher)),
+        #[cfg(target_os = "linux")]
+        "media" => Some(Box::new(super::media_linux::MediaWatcher)),
+        _ => None,
+    }
+}
+
+/// Foreground app and window title. In a terminal, the app is replaced by the
+/// TUI running in its foreground (e.g. "Neovim"), and the foreground process's
+/// working directory is reported for project detection.
+pub struct WindowWatcher;
+
+impl Watcher for WindowWatcher {
+    fn name(&self) -> &str {
+        "window"
+    }
+
+    #[cfg(target_os = "macos")]
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        use super::{tui, tui_macos, window_macos};
+
+        let window = window_macos::get_active_window()?;
+        let foreground = if tui::is_terminal_app(&window.app) {
+            tui_macos::inspect_terminal()
+        } else {
+            Default::default()
+        };
+
+        // A TUI in a terminal has no identity of its own
+        let (app, app_id, app_path) = match foreground.tui {
+            Some(tui) => (tui.app_name, None, None),
+            None => (window.app, window.bundle_id, window.bundle_path),
+        };
+        Ok(SnapshotPatch {
+            app: Some(app),
+            app_id,
+            app_path,
+            title: Some(window.title),
+            cwd: foreground.cwd,
+            ..Default::default()
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        use super::{tui, tui_linux, window_hyprland, window_linux, window_sway, LinuxBackend};
+
+        let window = match LinuxBackend::detect() {
+            LinuxBackend::Sway => window_sway::get_active_window()?,
+            LinuxBackend::Hyprland => window_hyprland::get_active_window()?,
+            LinuxBackend::X11 => window_linux::get_active_window()?,
+        };
+
+        // If the active app is a terminal, resolve the foreground process of its pty
+        let foreground = match window.pid {
+            Some(pid) if tui::is_terminal_app(&window.app) => tui_linux::inspect_terminal(pid),
+            _ => Default::default(),
+        };
+
+        // A TUI in a terminal has no identity of its own
+        let (app, app_id, app_path) = match foreground.tui {
+            Some(tui) => (tui.app_name, None, None),
+            None => (window.app.clone(), window.app_id(), window.app_path()),
+        };
+        Ok(SnapshotPatch {
+            app: Some(app),
+            app_id,
+            app_path,
+            pid: window.pid,
+            title: Some(window.title),
+            cwd: foreground.cwd,
+            ..Default::default()
+        })
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        Err(crate::error::TimelyError::PlatformNotSupported(
+            std::env::consts::OS.to_string(),
+        ))
+    }
+}
+
+/// Whether the user has been idle for longer than `threshold_secs`
+/// (config key `afk.threshold_secs`).
+pub struct AfkWatcher {
+    threshold_secs: u64,
+}
+
+impl Watcher for AfkWatcher {
+    fn name(&self) -> &str {
+        "afk"
+    }
+
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        #[cfg(target_os = "macos")]
+        let is_afk = super::afk_macos::is_afk(self.threshold_secs).unwrap_or(false);
+        #[cfg(target_os = "linux")]
+        let is_afk = super::afk_linux::is_afk(self.threshold_secs).unwrap_or(false);
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        let is_afk = {
+            let _ = self.threshold_secs;
+            false
+        };
+
+        Ok(SnapshotPatch {
+            is_afk: Some(is_afk),
+            ..Default::default()
+        })
+    }
+}
+
+/// URL of the active browser tab, via AppleScript. Scripting the browser is
+/// slow, so this polls less often than the window watcher; a tab switch still
+/// changes the window title, which triggers an immediate re-poll.
+#[cfg(target_os = "macos")]
+pub struct BrowserWatcher;
+
+#[cfg(target_os = "macos")]
+impl Watcher for BrowserWatcher {
+    fn name(&self) -> &str {
+        "browser"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn enrich(&mut self, snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        Ok(match super::browser_macos::get_browser_tab(&snapshot.app) {
+            Ok(Some(tab)) => SnapshotPatch {
+                url: Some(tab.url),
+                url_domain: Some(tab.domain),
+                ..Default::default()
+            },
+            _ => SnapshotPatch::default(),
+        })
+    }
+}
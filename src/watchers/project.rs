@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+
+use super::registry::{SnapshotPatch, Watcher};
+use crate::error::Result;
+use crate::types::WatcherSnapshot;
+
+/// A git repository enclosing the current activity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repo {
+    /// Repository directory name, used as the project
+    pub name: String,
+    pub root: PathBuf,
+    /// Checked-out branch, or the short commit id when detached
+    pub branch: Option<String>,
+}
+
+/// Resolves the focused terminal's working directory, or a path in an editor's
+/// window title, to the enclosing git repository and records it as the
+/// event's project and branch.
+pub struct ProjectWatcher;
+
+impl Watcher for ProjectWatcher {
+    fn name(&self) -> &str {
+        "project"
+    }
+
+    fn enrich(&mut self, snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        let home = dirs::home_dir();
+        let repo = snapshot
+            .cwd
+            .as_deref()
+            .and_then(|cwd| find_repo(Path::new(cwd), home.as_deref()))
+            .or_else(|| {
+                paths_in_title(&snapshot.title, home.as_deref())
+                    .iter()
+                    .find_map(|path| find_repo(path, home.as_deref()))
+            });
+
+        Ok(match repo {
+            Some(repo) => SnapshotPatch {
+                project: Some(repo.name),
+                branch: repo.branch,
+                ..Default::default()
+            },
+            None => SnapshotPatch::default(),
+        })
+    }
+}
+
+/// Find the git repository containing `path` (a file or directory). A repo at
+/// `home` itself (dotfiles) doesn't count, or every shell would be a project.
+pub fn find_repo(path: &Path, home: Option<&Path>) -> Option<Repo> {
+    for dir in path.ancestors() {
+        if Some(dir) == home {
+            return None;
+        }
+        let dot_git = dir.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if dot_git.is_file() {
+            // Worktrees and submodules: ".git" is a file containing "gitdir: <path>"
+            let content = std::fs::read_to_string(&dot_git).ok()?;
+            let target = content.trim().strip_prefix("gitdir:")?.trim();
+            dir.join(target)
+        } else {
+            continue;
+        };
+
+        return Some(Repo {
+            name: dir.file_name()?.to_string_lossy().to_string(),
+            root: dir.to_path_buf(),
+            branch: read_branch(&git_dir),
+        });
+    }
+    None
+}
+
+fn read_branch(git_dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string()),
+        None if head.len() >= 7 => Some(head[..7].to_string()),
+        None => None,
+    }
+}
+
+/// Absolute or `~/` paths mentioned in a window title, e.g.
+/// `main.rs (~/src/timely/src) - VIM` or `~/src/timely/README.md - Sublime Text`.
+/// Only paths whose nearest existing ancestor exists on disk are returned.
+pub fn paths_in_title(title: &str, home: Option<&Path>) -> Vec<PathBuf> {
+    title
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '"' | '\''))
+        .map(|token| token.trim_end_matches([':', ',', ';']))
+        .filter_map(|token| {
+            if let Some(rest) = token.strip_prefix("~/") {
+                home.map(|h| h.join(rest))
+            } else if token.starts_with('/') && token.len() > 1 {
+                Some(PathBuf::from(token))
+            } else {
+                None
+            }
+        })
+        .filter(|path| path.ancestors().any(|a| a != Path::new("/") && a.exists()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_repo(root: &Path, head: &str) {
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".git/HEAD"), head).unwrap();
+    }
+
+    #[test]
+    fn test_find_repo_from_subdirectory() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("timely");
+        make_repo(&root, "ref: refs/heads/feature/linux\n");
+        std::fs::create_dir_all(root.join("src/watchers")).unwrap();
+
+        let repo = find_repo(&root.join("src/watchers"), None).unwrap();
+        assert_eq!(repo.name, "timely");
+        assert_eq!(repo.root, root);
+        assert_eq!(repo.branch.as_deref(), Some("feature/linux"));
+
+        // Files that don't exist yet still resolve through their directory
+        assert!(find_repo(&root.join("src/new_file.rs"), None).is_some());
+    }
+
+    #[test]
+    fn test_detached_head_and_worktree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("api");
+        make_repo(&main, "9fceb02d0ae598e95dc970b74767f19372d61af8\n");
+        assert_eq!(find_repo(&main, None).unwrap().branch.as_deref(), Some("9fceb02"));
+
+        let worktree_git = main.join(".git/worktrees/api-hotfix");
+        std::fs::create_dir_all(&worktree_git).unwrap();
+        std::fs::write(worktree_git.join("HEAD"), "ref: refs/heads/hotfix\n").unwrap();
+        let worktree = tmp.path().join("api-hotfix");
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(worktree.join(".git"), format!("gitdir: {}\n", worktree_git.display())).unwrap();
+
+        let repo = find_repo(&worktree, None).unwrap();
+        assert_eq!(repo.name, "api-hotfix");
+        assert_eq!(repo.branch.as_deref(), Some("hotfix"));
+    }
+
+    #[test]
+    fn test_home_repo_is_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        make_repo(tmp.path(), "ref: refs/heads/main\n");
+        std::fs::create_dir_all(tmp.path().join("Downloads")).unwrap();
+        assert!(find_repo(&tmp.path().join("Downloads"), Some(tmp.path())).is_none());
+    }
+
+    #[test]
+    fn test_paths_in_title() {
+        let tmp = tempfile::tempdir().unwrap();
+        let src = tmp.path().join("src/timely/src");
+        std::fs::create_dir_all(&src).unwrap();
+
+        let vim = "main.rs (~/src/timely/src) - VIM";
+        assert_eq!(paths_in_title(vim, Some(tmp.path())), vec![src.clone()]);
+
+        let sublime = format!("{}/main.rs - Sublime Text", src.display());
+        assert_eq!(paths_in_title(&sublime, None), vec![src.join("main.rs")]);
+
+        assert!(paths_in_title("main.rs — timely — Visual Studio Code", None).is_empty());
+        assert!(paths_in_title("/nonexistent/dir/file.rs - Zed", None).is_empty());
+    }
+}
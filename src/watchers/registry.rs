@@ -0,0 +1,352 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use crate::config::POLL_INTERVAL_SECS;
+use crate::db::config_store;
+use crate::error::{Result, TimelyError};
+use crate::types::{MediaPlayback, WatcherHealth, WatcherSnapshot};
+
+/// A source of activity data. Each watcher sees the snapshot built so far by the
+/// watchers registered before it (e.g. the browser watcher needs the active app)
+/// and returns the fields it contributes.
+pub trait Watcher: Send {
+    /// Stable name, used in config keys (`watchers.<name>.interval_secs`) and logs.
+    fn name(&self) -> &str;
+
+    /// How often this watcher should be polled. Between polls its last
+    /// contribution is reused as long as the app/title it was based on is unchanged.
+    fn interval(&self) -> Duration {
+        Duration::from_secs(POLL_INTERVAL_SECS)
+    }
+
+    fn enrich(&mut self, snapshot: &WatcherSnapshot) -> Result<SnapshotPatch>;
+}
+
+/// Partial update to a `WatcherSnapshot`. Unset fields leave the snapshot untouched.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SnapshotPatch {
+    pub app: Option<String>,
+    pub app_id: Option<String>,
+    pub app_path: Option<String>,
+    pub pid: Option<u32>,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub url_domain: Option<String>,
+    pub is_afk: Option<bool>,
+    pub project: Option<String>,
+    pub file: Option<String>,
+    pub language: Option<String>,
+    pub branch: Option<String>,
+    pub cwd: Option<String>,
+    pub command: Option<String>,
+    pub exit_status: Option<i32>,
+    pub media: Option<MediaPlayback>,
+}
+
+impl SnapshotPatch {
+    pub fn apply(&self, snapshot: &mut WatcherSnapshot) {
+        if let Some(ref app) = self.app {
+            snapshot.app = app.clone();
+            // Identity belongs to the app it came with
+            snapshot.app_id = self.app_id.clone();
+            snapshot.app_path = self.app_path.clone();
+            snapshot.pid = self.pid;
+        }
+        if let Some(ref title) = self.title {
+            snapshot.title = title.clone();
+        }
+        if self.url.is_some() {
+            snapshot.url = self.url.clone();
+        }
+        if self.url_domain.is_some() {
+            snapshot.url_domain = self.url_domain.clone();
+        }
+        if let Some(is_afk) = self.is_afk {
+            snapshot.is_afk = is_afk;
+        }
+        if self.project.is_some() {
+            snapshot.project = self.project.clone();
+        }
+        if self.file.is_some() {
+            snapshot.file = self.file.clone();
+        }
+        if self.language.is_some() {
+            snapshot.language = self.language.clone();
+        }
+        if self.branch.is_some() {
+            snapshot.branch = self.branch.clone();
+        }
+        if self.cwd.is_some() {
+            snapshot.cwd = self.cwd.clone();
+        }
+        if self.command.is_some() {
+            snapshot.command = self.command.clone();
+        }
+        if self.exit_status.is_some() {
+            snapshot.exit_status = self.exit_status;
+        }
+        if self.media.is_some() {
+            snapshot.media = self.media.clone();
+        }
+    }
+}
+
+struct WatcherEntry {
+    watcher: Box<dyn Watcher>,
+    interval: Duration,
+    last_poll: Option<Instant>,
+    /// (app, title) the cached patch was computed against
+    last_context: Option<(String, String)>,
+    last_patch: SnapshotPatch,
+    last_polled_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// Ordered set of watchers, polled once per daemon tick.
+#[derive(Default)]
+pub struct WatcherRegistry {
+    entries: Vec<WatcherEntry>,
+}
+
+impl WatcherRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a watcher using its own poll interval.
+    pub fn register(&mut self, watcher: Box<dyn Watcher>) {
+        let interval = watcher.interval();
+        self.register_with_interval(watcher, interval);
+    }
+
+    pub fn register_with_interval(&mut self, watcher: Box<dyn Watcher>, interval: Duration) {
+        self.entries.push(WatcherEntry {
+            watcher,
+            interval,
+            last_poll: None,
+            last_context: None,
+            last_patch: SnapshotPatch::default(),
+            last_polled_at: None,
+            last_error: None,
+        });
+    }
+
+    /// Compose the registry from config:
+    /// - `watchers.enabled`: comma-separated watcher names (default: the platform
+    ///   watchers followed by every configured external plugin)
+    /// - `watchers.<name>.interval_secs`: per-watcher poll interval override
+    /// - `watchers.<name>.command`: registers `<name>` as an external plugin
+    pub fn from_config(conn: &Connection, afk_threshold_secs: u64) -> Result<Self> {
+        let names: Vec<String> = match config_store::get(conn, "watchers.enabled")? {
+            Some(list) => list
+                .split(',')
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty())
+                .collect(),
+            None => {
+                let mut names: Vec<String> =
+                    super::native::DEFAULT_WATCHERS.iter().map(|n| n.to_string()).collect();
+                for (key, _) in config_store::list(conn)? {
+                    if let Some(name) = key.strip_prefix("watchers.").and_then(|k| k.strip_suffix(".command")) {
+                        if !names.iter().any(|n| n == name) {
+                            names.push(name.to_string());
+                        }
+                    }
+                }
+                names
+            }
+        };
+
+        let mut registry = Self::new();
+        for name in &names {
+            let watcher = build_watcher(conn, name, afk_threshold_secs)?;
+            let interval = config_store::get(conn, &format!("watchers.{}.interval_secs", name))?
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|secs| *secs > 0.0)
+                .map(Duration::from_secs_f64)
+                .unwrap_or_else(|| watcher.interval());
+            registry.register_with_interval(watcher, interval);
+        }
+        Ok(registry)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.iter().map(|e| e.watcher.name()).collect()
+    }
+
+    /// When each watcher was last polled and whether that poll failed.
+    pub fn health(&self) -> Vec<WatcherHealth> {
+        self.entries
+            .iter()
+            .map(|e| WatcherHealth {
+                name: e.watcher.name().to_string(),
+                interval_secs: e.interval.as_secs_f64(),
+                last_poll_at: e.last_polled_at.map(|t| t.to_rfc3339()),
+                error: e.last_error.clone(),
+            })
+            .collect()
+    }
+
+    /// Build the snapshot for this tick by running every watcher that is due
+    /// and replaying cached contributions for the rest.
+    pub fn collect(&mut self) -> Result<WatcherSnapshot> {
+        let now = Instant::now();
+        let mut snapshot = WatcherSnapshot::default();
+        let mut first_error = None;
+
+        for entry in &mut self.entries {
+            let context = (snapshot.app.clone(), snapshot.title.clone());
+            let due = match entry.last_poll {
+                Some(last) => now.duration_since(last) >= entry.interval,
+                None => true,
+            };
+
+            if due || entry.last_context.as_ref() != Some(&context) {
+                entry.last_poll = Some(now);
+                entry.last_polled_at = Some(Utc::now());
+                entry.last_context = Some(context);
+                entry.last_error = None;
+                entry.last_patch = match entry.watcher.enrich(&snapshot) {
+                    Ok(patch) => patch,
+                    Err(e) => {
+                        entry.last_error = Some(e.to_string());
+                        first_error.get_or_insert(TimelyError::Generic(format!(
+                            "{}: {}",
+                            entry.watcher.name(),
+                            e
+                        )));
+                        SnapshotPatch::default()
+                    }
+                };
+            }
+
+            entry.last_patch.apply(&mut snapshot);
+        }
+
+        // Enrichers failing is not fatal, but no app at all means nothing usable was collected
+        match first_error {
+            Some(e) if snapshot.app.is_empty() => Err(e),
+            _ => Ok(snapshot),
+        }
+    }
+}
+
+fn build_watcher(conn: &Connection, name: &str, afk_threshold_secs: u64) -> Result<Box<dyn Watcher>> {
+    if let Some(command) = config_store::get(conn, &format!("watchers.{}.command", name))? {
+        let apps = config_store::get(conn, &format!("watchers.{}.apps", name))?
+            .map(|list| {
+                list.split(',')
+                    .map(|a| a.trim().to_string())
+                    .filter(|a| !a.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        return Ok(Box::new(super::external::ExternalWatcher::new(name, &command, apps)?));
+    }
+
+    match name {
+        "replay" => {
+            let file = config_store::get(conn, "watchers.replay.file")?.ok_or_else(|| {
+                TimelyError::Config("watchers.replay.file must be set to use the replay watcher".into())
+            })?;
+            Ok(Box::new(super::replay::ReplayWatcher::from_file(std::path::Path::new(&file))?))
+        }
+        "shell" => Ok(Box::new(super::shell::ShellWatcher::new(crate::config::shell_socket_path()?))),
+        _ => super::native::build(name, afk_threshold_secs)
+            .ok_or_else(|| TimelyError::Config(format!("Unknown watcher: {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Returns a fixed patch and counts how often it was polled.
+    struct CountingWatcher {
+        patch: SnapshotPatch,
+        polls: Arc<AtomicUsize>,
+    }
+
+    impl Watcher for CountingWatcher {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.patch.clone())
+        }
+    }
+
+    fn counting(patch: SnapshotPatch) -> (Box<dyn Watcher>, Arc<AtomicUsize>) {
+        let polls = Arc::new(AtomicUsize::new(0));
+        (Box::new(CountingWatcher { patch, polls: polls.clone() }), polls)
+    }
+
+    #[test]
+    fn test_patches_compose_in_order() {
+        let mut registry = WatcherRegistry::new();
+        let (window, _) = counting(SnapshotPatch {
+            app: Some("Firefox".into()),
+            title: Some("Rust".into()),
+            ..Default::default()
+        });
+        let (browser, _) = counting(SnapshotPatch {
+            url: Some("https://doc.rust-lang.org/book".into()),
+            url_domain: Some("doc.rust-lang.org".into()),
+            ..Default::default()
+        });
+        registry.register(window);
+        registry.register(browser);
+
+        let snapshot = registry.collect().unwrap();
+        assert_eq!(snapshot.app, "Firefox");
+        assert_eq!(snapshot.url_domain.as_deref(), Some("doc.rust-lang.org"));
+    }
+
+    #[test]
+    fn test_slow_watcher_reuses_cached_patch() {
+        let mut registry = WatcherRegistry::new();
+        let (window, window_polls) = counting(SnapshotPatch {
+            app: Some("Firefox".into()),
+            ..Default::default()
+        });
+        let (browser, browser_polls) = counting(SnapshotPatch {
+            url_domain: Some("github.com".into()),
+            ..Default::default()
+        });
+        registry.register_with_interval(window, Duration::ZERO);
+        registry.register_with_interval(browser, Duration::from_secs(3600));
+
+        for _ in 0..3 {
+            let snapshot = registry.collect().unwrap();
+            assert_eq!(snapshot.url_domain.as_deref(), Some("github.com"));
+        }
+        assert_eq!(window_polls.load(Ordering::SeqCst), 3);
+        assert_eq!(browser_polls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_from_config_rejects_unknown_watcher() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+        config_store::set(&conn, "watchers.enabled", "nonexistent").unwrap();
+        assert!(WatcherRegistry::from_config(&conn, 180).is_err());
+    }
+
+    #[test]
+    fn test_from_config_appends_external_plugins() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let conn = crate::db::open_db(tmp.path()).unwrap();
+        config_store::set(&conn, "watchers.rdp.command", "/usr/local/bin/rdp-watch --json").unwrap();
+
+        let registry = WatcherRegistry::from_config(&conn, 180).unwrap();
+        assert_eq!(registry.names().last(), Some(&"rdp"));
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use super::registry::{SnapshotPatch, Watcher};
+use crate::error::{Result, TimelyError};
+use crate::types::WatcherSnapshot;
+
+/// Replays recorded snapshots from a JSON file, one per poll, so the daemon
+/// loop can run without a desktop session. The file holds an array of objects
+/// with any of `app`, `title`, `url`, `url_domain`, `is_afk`. Once the
+/// recording is exhausted it reports no focused window.
+pub struct ReplayWatcher {
+    snapshots: VecDeque<SnapshotPatch>,
+}
+
+impl ReplayWatcher {
+    pub fn new(snapshots: Vec<SnapshotPatch>) -> Self {
+        Self {
+            snapshots: snapshots.into(),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            TimelyError::Config(format!("Cannot read replay file {}: {}", path.display(), e))
+        })?;
+        Ok(Self::new(serde_json::from_str(&content)?))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.snapshots.len()
+    }
+}
+
+impl Watcher for ReplayWatcher {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        Ok(self.snapshots.pop_front().unwrap_or_default())
+    }
+}
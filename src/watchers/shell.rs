@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::registry::{SnapshotPatch, Watcher};
+use super::tui;
+#[cfg(target_os = "linux")]
+use super::tui_linux::parent_pid;
+use crate::error::{Result, TimelyError};
+use crate::types::WatcherSnapshot;
+
+/// macOS caps Unix datagrams at 2048 bytes by default; leave room for the envelope.
+const MAX_COMMAND_BYTES: usize = 1024;
+
+/// What a shell hook reports. `seq` counts commands per shell so a late
+/// `start` (hooks send in the background) can't resurrect a finished command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum ShellMessage {
+    Start { pid: u32, seq: u64, command: String, cwd: String },
+    End { pid: u32, seq: u64, exit_status: i32, cwd: String },
+}
+
+/// Send a hook message to the daemon's shell socket.
+pub fn send(socket: &Path, message: &ShellMessage) -> Result<()> {
+    let mut message = message.clone();
+    if let ShellMessage::Start { command, .. } = &mut message {
+        if command.len() > MAX_COMMAND_BYTES {
+            let mut end = MAX_COMMAND_BYTES;
+            while !command.is_char_boundary(end) {
+                end -= 1;
+            }
+            command.truncate(end);
+        }
+    }
+    let payload = serde_json::to_vec(&message)?;
+    UnixDatagram::unbound()?.send_to(&payload, socket)?;
+    Ok(())
+}
+
+/// preexec/precmd hook code for `shell`, calling back into `exe`.
+pub fn hook_script(shell: &str, exe: &str) -> Result<String> {
+    let template = match shell {
+        "zsh" => ZSH_HOOK,
+        "bash" => BASH_HOOK,
+        "fish" => FISH_HOOK,
+        _ => {
+            return Err(TimelyError::Config(format!(
+                "Unsupported shell: {} (expected zsh, bash or fish)",
+                shell
+            )))
+        }
+    };
+    let quoted = format!("'{}'", exe.replace('\'', r"'\''"));
+    Ok(template.replace("@TIMELY@", &quoted))
+}
+
+const ZSH_HOOK: &str = r#"# timely shell integration (zsh)
+typeset -gi _timely_seq=0 _timely_ended=0
+_timely_preexec() {
+  (( _timely_seq++ ))
+  @TIMELY@ shell-event start --pid $$ --seq $_timely_seq --cwd "$PWD" -- "$1" >/dev/null 2>&1 &!
+}
+_timely_precmd() {
+  local s=$?
+  (( _timely_seq == _timely_ended )) && return
+  _timely_ended=$_timely_seq
+  @TIMELY@ shell-event end --pid $$ --seq $_timely_seq --status $s --cwd "$PWD" >/dev/null 2>&1 &!
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec _timely_preexec
+precmd_functions=(_timely_precmd ${precmd_functions:#_timely_precmd})
+"#;
+
+const BASH_HOOK: &str = r#"# timely shell integration (bash)
+_timely_seq=0
+_timely_ended=0
+_timely_interactive=
+_timely_preexec() {
+  [ -n "$COMP_LINE" ] && return
+  [ -z "$_timely_interactive" ] && return
+  # An empty command line runs PROMPT_COMMAND straight away
+  [ "$BASH_COMMAND" = _timely_precmd ] && return
+  _timely_interactive=
+  _timely_seq=$((_timely_seq + 1))
+  local cmd
+  cmd=$(HISTTIMEFORMAT= builtin history 1)
+  [[ $cmd =~ ^[[:space:]]*[0-9]+[*[:space:]]+(.*)$ ]] && cmd=${BASH_REMATCH[1]}
+  (@TIMELY@ shell-event start --pid $$ --seq $_timely_seq --cwd "$PWD" -- "$cmd" >/dev/null 2>&1 &)
+}
+_timely_precmd() {
+  local s=$?
+  _timely_interactive=
+  if [ "$_timely_seq" != "$_timely_ended" ]; then
+    _timely_ended=$_timely_seq
+    (@TIMELY@ shell-event end --pid $$ --seq $_timely_seq --status $s --cwd "$PWD" >/dev/null 2>&1 &)
+  fi
+}
+trap '_timely_preexec' DEBUG
+PROMPT_COMMAND="_timely_precmd${PROMPT_COMMAND:+; $PROMPT_COMMAND}; _timely_interactive=1"
+"#;
+
+const FISH_HOOK: &str = r#"# timely shell integration (fish)
+set -g _timely_seq 0
+set -g _timely_ended 0
+function _timely_preexec --on-event fish_preexec
+    set -g _timely_seq (math $_timely_seq + 1)
+    @TIMELY@ shell-event start --pid $fish_pid --seq $_timely_seq --cwd "$PWD" -- "$argv" >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+function _timely_postexec --on-event fish_postexec
+    set -l s $status
+    test "$_timely_seq" = "$_timely_ended"; and return
+    set -g _timely_ended $_timely_seq
+    @TIMELY@ shell-event end --pid $fish_pid --seq $_timely_seq --status $s --cwd "$PWD" >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+"#;
+
+struct Command {
+    seq: u64,
+    command: String,
+    cwd: String,
+}
+
+#[derive(Default)]
+struct Session {
+    running: Option<Command>,
+    /// Just-finished command, reported once with its exit status
+    finished: Option<(Command, i32)>,
+    ended_seq: u64,
+    last_seen: Option<Instant>,
+}
+
+/// Receives shell hook messages on a Unix datagram socket and attaches the
+/// command running in the focused terminal (with its cwd) to the snapshot.
+/// Only shells started by the focused terminal, or sitting in its foreground
+/// cwd, qualify; among those the most recently active wins.
+pub struct ShellWatcher {
+    path: PathBuf,
+    socket: Option<UnixDatagram>,
+    sessions: HashMap<u32, Session>,
+}
+
+impl ShellWatcher {
+    /// The socket is bound on the first poll.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            socket: None,
+            sessions: HashMap::new(),
+        }
+    }
+
+    fn bind(&mut self) -> Result<()> {
+        if self.socket.is_none() {
+            match UnixDatagram::unbound()?.connect(&self.path) {
+                Ok(()) => {
+                    return Err(TimelyError::Generic(format!(
+                        "daemon already running ({} is in use)",
+                        self.path.display()
+                    )))
+                }
+                // A socket file left behind by a daemon that didn't shut down cleanly
+                Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    let _ = std::fs::remove_file(&self.path);
+                }
+                Err(_) => {}
+            }
+            let socket = UnixDatagram::bind(&self.path)?;
+            socket.set_nonblocking(true)?;
+            self.socket = Some(socket);
+        }
+        Ok(())
+    }
+
+    fn drain(&mut self) {
+        let Some(socket) = &self.socket else { return };
+        let mut buf = [0u8; 65536];
+        let mut messages = Vec::new();
+        while let Ok(n) = socket.recv(&mut buf) {
+            match serde_json::from_slice::<ShellMessage>(&buf[..n]) {
+                Ok(message) => messages.push(message),
+                Err(e) => eprintln!("watcher shell: ignoring invalid message: {}", e),
+            }
+        }
+        for message in messages {
+            self.handle(message);
+        }
+    }
+
+    fn handle(&mut self, message: ShellMessage) {
+        match message {
+            ShellMessage::Start { pid, seq, command, cwd } => {
+                let session = self.sessions.entry(pid).or_default();
+                if seq <= session.ended_seq {
+                    return;
+                }
+                session.running = Some(Command { seq, command, cwd });
+                session.last_seen = Some(Instant::now());
+            }
+            ShellMessage::End { pid, seq, exit_status, .. } => {
+                let session = self.sessions.entry(pid).or_default();
+                session.ended_seq = session.ended_seq.max(seq);
+                if let Some(running) = session.running.take_if(|r| r.seq <= seq) {
+                    if running.seq == seq {
+                        session.finished = Some((running, exit_status));
+                    }
+                }
+                session.last_seen = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Forget shells that have exited.
+    fn prune(&mut self) {
+        self.sessions.retain(|pid, _| {
+            // Signal 0 only checks that the process exists
+            let alive = unsafe { libc::kill(*pid as libc::pid_t, 0) } == 0;
+            alive || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+        });
+    }
+
+    fn focused_patch(&self, snapshot: &WatcherSnapshot) -> SnapshotPatch {
+        if snapshot.cwd.is_none() && !tui::is_terminal_app(&snapshot.app) {
+            return SnapshotPatch::default();
+        }
+
+        let active = self
+            .sessions
+            .iter()
+            .filter_map(|(pid, s)| {
+                let (command, exit_status) = match (&s.finished, &s.running) {
+                    (Some((command, status)), _) => (command, Some(*status)),
+                    (None, Some(command)) => (command, None),
+                    (None, None) => return None,
+                };
+                let focused = Some(&command.cwd) == snapshot.cwd.as_ref()
+                    || snapshot.pid.is_some_and(|terminal| in_process_tree(*pid, terminal));
+                focused.then_some((command, exit_status, s.last_seen))
+            })
+            .max_by_key(|(_, _, last_seen)| *last_seen);
+
+        match active {
+            Some((command, exit_status, _)) => SnapshotPatch {
+                command: Some(command.command.clone()),
+                cwd: Some(command.cwd.clone()),
+                exit_status,
+                ..Default::default()
+            },
+            None => SnapshotPatch::default(),
+        }
+    }
+}
+
+/// Whether `pid` is `root` or one of its descendants.
+fn in_process_tree(mut pid: u32, root: u32) -> bool {
+    // Bounded in case pid reuse ever produces a cycle
+    for _ in 0..64 {
+        if pid == root {
+            return true;
+        }
+        match parent_pid(pid) {
+            Some(parent) if parent != 0 => pid = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// macOS reports no window pid, so only the cwd match applies there.
+#[cfg(not(target_os = "linux"))]
+fn parent_pid(_pid: u32) -> Option<u32> {
+    None
+}
+
+impl Watcher for ShellWatcher {
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    /// Poll every tick so finished commands are reported promptly.
+    fn interval(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn enrich(&mut self, snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        self.bind()?;
+        self.drain();
+        self.prune();
+
+        let patch = self.focused_patch(snapshot);
+        // Finished commands are reported once; in unfocused shells they're dropped
+        for session in self.sessions.values_mut() {
+            session.finished = None;
+        }
+        Ok(patch)
+    }
+}
+
+impl Drop for ShellWatcher {
+    fn drop(&mut self) {
+        if self.socket.is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terminal() -> WatcherSnapshot {
+        WatcherSnapshot {
+            app: "Alacritty".into(),
+            title: "~/src/foo".into(),
+            pid: Some(std::process::id()),
+            ..Default::default()
+        }
+    }
+
+    fn start(seq: u64, command: &str) -> ShellMessage {
+        ShellMessage::Start {
+            pid: std::process::id(),
+            seq,
+            command: command.into(),
+            cwd: "/home/me/src/foo".into(),
+        }
+    }
+
+    fn end(seq: u64, exit_status: i32) -> ShellMessage {
+        ShellMessage::End {
+            pid: std::process::id(),
+            seq,
+            exit_status,
+            cwd: "/home/me/src/foo".into(),
+        }
+    }
+
+    #[test]
+    fn test_running_then_finished_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shell.sock");
+        let mut watcher = ShellWatcher::new(path.clone());
+        assert_eq!(watcher.enrich(&terminal()).unwrap(), SnapshotPatch::default());
+
+        send(&path, &start(1, "cargo test")).unwrap();
+        let patch = watcher.enrich(&terminal()).unwrap();
+        assert_eq!(patch.command.as_deref(), Some("cargo test"));
+        assert_eq!(patch.cwd.as_deref(), Some("/home/me/src/foo"));
+        assert_eq!(patch.exit_status, None);
+
+        // Not attached while another app is focused
+        let browser = WatcherSnapshot {
+            app: "Firefox".into(),
+            ..Default::default()
+        };
+        assert_eq!(watcher.enrich(&browser).unwrap(), SnapshotPatch::default());
+
+        send(&path, &end(1, 101)).unwrap();
+        let patch = watcher.enrich(&terminal()).unwrap();
+        assert_eq!(patch.command.as_deref(), Some("cargo test"));
+        assert_eq!(patch.exit_status, Some(101));
+
+        // Back at the prompt
+        assert_eq!(watcher.enrich(&terminal()).unwrap(), SnapshotPatch::default());
+        drop(watcher);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_bind_leaves_a_live_socket_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shell.sock");
+
+        // Stale: the socket is gone but its file is not
+        drop(UnixDatagram::bind(&path).unwrap());
+        let mut watcher = ShellWatcher::new(path.clone());
+        watcher.enrich(&terminal()).unwrap();
+
+        let mut second = ShellWatcher::new(path.clone());
+        assert!(second.enrich(&terminal()).is_err());
+        drop(second);
+        send(&path, &start(1, "cargo test")).unwrap();
+        assert_eq!(watcher.enrich(&terminal()).unwrap().command.as_deref(), Some("cargo test"));
+    }
+
+    #[test]
+    fn test_late_start_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shell.sock");
+        let mut watcher = ShellWatcher::new(path.clone());
+        watcher.enrich(&terminal()).unwrap();
+
+        send(&path, &end(1, 0)).unwrap();
+        send(&path, &start(1, "ls")).unwrap();
+        assert_eq!(watcher.enrich(&terminal()).unwrap(), SnapshotPatch::default());
+    }
+
+    #[test]
+    fn test_only_the_focused_terminals_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shell.sock");
+        let mut watcher = ShellWatcher::new(path.clone());
+        watcher.enrich(&terminal()).unwrap();
+
+        // A shell in another terminal, elsewhere on disk
+        send(
+            &path,
+            &ShellMessage::Start {
+                pid: 1,
+                seq: 1,
+                command: "make".into(),
+                cwd: "/srv/other".into(),
+            },
+        )
+        .unwrap();
+        assert_eq!(watcher.enrich(&terminal()).unwrap(), SnapshotPatch::default());
+
+        // Attached once the focused terminal sits in the same directory
+        let same_cwd = WatcherSnapshot {
+            pid: None,
+            cwd: Some("/srv/other".into()),
+            ..terminal()
+        };
+        assert_eq!(watcher.enrich(&same_cwd).unwrap().command.as_deref(), Some("make"));
+
+        // Shells anywhere below the terminal process count as its own
+        send(&path, &start(1, "cargo build")).unwrap();
+        #[cfg(target_os = "linux")]
+        let terminal_pid = std::os::unix::process::parent_id();
+        #[cfg(not(target_os = "linux"))]
+        let terminal_pid = std::process::id();
+        let parent = WatcherSnapshot {
+            pid: Some(terminal_pid),
+            ..terminal()
+        };
+        assert_eq!(watcher.enrich(&parent).unwrap().command.as_deref(), Some("cargo build"));
+    }
+
+    #[test]
+    fn test_hook_script() {
+        let zsh = hook_script("zsh", "/opt/it's/timely").unwrap();
+        assert!(zsh.contains(r"'/opt/it'\''s/timely' shell-event start"));
+        assert!(!hook_script("bash", "timely").unwrap().contains("@TIMELY@"));
+        assert!(!hook_script("fish", "timely").unwrap().contains("@TIMELY@"));
+        assert!(hook_script("tcsh", "timely").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+/// Known terminal emulator app names (must match what AppleScript returns on
+/// macOS, or the WM_CLASS / Wayland app_id on Linux).
+const TERMINAL_APPS: &[&str] = &[
+    "Terminal",
+    "iTerm2",
+    "Alacritty",
+    "kitty",
+    "Warp",
+    "WezTerm",
+    "Ghostty",
+    // Linux
+    "XTerm",
+    "Gnome-terminal",
+    "org.gnome.Terminal",
+    "org.gnome.Ptyxis",
+    "konsole",
+    "foot",
+    "Tilix",
+    "Terminator",
+    "Xfce4-terminal",
+    "org.wezfurlong.wezterm",
+    "com.mitchellh.ghostty",
+];
+
+/// Known TUI/CLI tools we want to detect running inside terminals.
+/// Maps process comm name → friendly display name.
+const KNOWN_TUI_APPS: &[(&str, &str)] = &[
+    // AI coding assistants
+    ("claude", "Claude Code"),
+    ("codex", "Codex CLI"),
+    ("aider", "Aider"),
+    ("cursor-cli", "Cursor CLI"),
+    // Editors
+    ("nvim", "Neovim"),
+    ("vim", "Vim"),
+    ("emacs", "Emacs"),
+    ("nano", "nano"),
+    ("helix", "Helix"),
+    // Dev tools
+    ("lazygit", "Lazygit"),
+    ("lazydocker", "Lazydocker"),
+    ("k9s", "k9s"),
+    ("tig", "Tig"),
+    // System tools
+    ("htop", "htop"),
+    ("btop", "btop"),
+    ("top", "top"),
+    // Multiplexers
+    ("tmux", "tmux"),
+    ("screen", "screen"),
+    ("zellij", "Zellij"),
+    // Other
+    ("python", "Python REPL"),
+    ("node", "Node REPL"),
+    ("irb", "Ruby IRB"),
+    ("iex", "Elixir IEx"),
+    ("ghci", "GHCi"),
+];
+
+pub struct TuiInfo {
+    /// Display name of the TUI app (e.g. "Claude Code")
+    pub app_name: String,
+    /// Raw process name (e.g. "claude")
+    pub process_name: String,
+}
+
+/// What runs in the foreground of the focused terminal.
+#[derive(Default)]
+pub struct TerminalForeground {
+    /// Known TUI app, if the foreground process is one
+    pub tui: Option<TuiInfo>,
+    /// Working directory of the foreground process
+    pub cwd: Option<String>,
+}
+
+/// Returns true if the given app name is a known terminal emulator.
+pub fn is_terminal_app(app: &str) -> bool {
+    TERMINAL_APPS.iter().any(|t| t.eq_ignore_ascii_case(app))
+}
+
+/// Map a process comm name (e.g. "nvim") to its TUI display name, if known.
+pub fn lookup_tui(comm: &str) -> Option<TuiInfo> {
+    KNOWN_TUI_APPS
+        .iter()
+        .find(|(proc_name, _)| comm.eq_ignore_ascii_case(proc_name))
+        .map(|(_, display_name)| TuiInfo {
+            app_name: display_name.to_string(),
+            process_name: comm.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_terminal_app() {
+        assert!(is_terminal_app("iTerm2"));
+        assert!(is_terminal_app("Terminal"));
+        assert!(is_terminal_app("Ghostty"));
+        assert!(is_terminal_app("Warp"));
+        assert!(is_terminal_app("konsole"));
+        assert!(!is_terminal_app("Code"));
+        assert!(!is_terminal_app("Google Chrome"));
+    }
+
+    #[test]
+    fn test_every_terminal_has_a_category_rule() {
+        use crate::categories::builtin::BUILTIN_RULES;
+        for app in TERMINAL_APPS {
+            assert!(
+                BUILTIN_RULES.contains(&("work/terminal", "app", app, 90)),
+                "no work/terminal rule for {}",
+                app
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_tui() {
+        assert_eq!(lookup_tui("claude").unwrap().app_name, "Claude Code");
+        assert_eq!(lookup_tui("nvim").unwrap().app_name, "Neovim");
+        assert!(lookup_tui("bash").is_none());
+    }
+}
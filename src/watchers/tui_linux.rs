@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::tui::{lookup_tui, TerminalForeground, TuiInfo};
+
+/// The fields of `/proc/<pid>/stat` we care about.
+#[derive(Debug, Clone, PartialEq)]
+struct ProcStat {
+    pid: u32,
+    comm: String,
+    ppid: u32,
+    /// Controlling terminal device number (0 = none)
+    tty_nr: i64,
+    /// Foreground process group of the controlling terminal (-1 = none)
+    tpgid: i64,
+}
+
+/// Detects the foreground TUI process running inside the terminal with `terminal_pid`.
+pub fn detect_tui_process(terminal_pid: u32) -> Option<TuiInfo> {
+    inspect_terminal(terminal_pid).tui
+}
+
+/// Inspects the foreground of the terminal with `terminal_pid`: which TUI (if
+/// any) runs there, and the working directory of that process.
+///
+/// Strategy: take the ptys of the terminal's own children (its tabs' shells;
+/// deeper descendants may be other terminal windows started from a shell),
+/// pick the one that most recently read input, read its foreground process
+/// group (`tpgid`), and map the group leader's `comm` through the known-TUI table.
+pub fn inspect_terminal(terminal_pid: u32) -> TerminalForeground {
+    inspect_terminal_in(Path::new("/proc"), Path::new("/dev/pts"), terminal_pid)
+}
+
+/// Parent of `pid`, from `/proc/<pid>/stat`.
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let content = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    parse_stat(&content).map(|stat| stat.ppid)
+}
+
+fn inspect_terminal_in(proc_root: &Path, pts_root: &Path, terminal_pid: u32) -> TerminalForeground {
+    let procs = read_all_stats(proc_root);
+    let by_pid: HashMap<u32, &ProcStat> = procs.iter().map(|p| (p.pid, p)).collect();
+
+    // The foreground group of each tab, with when its pty last read input
+    let mut tabs: Vec<(Option<SystemTime>, u32)> = Vec::new();
+    for child in procs.iter().filter(|p| p.ppid == terminal_pid) {
+        if child.tty_nr != 0 && child.tpgid > 0 && !tabs.iter().any(|(_, pgid)| *pgid == child.tpgid as u32) {
+            tabs.push((pty_last_input(pts_root, child.tty_nr), child.tpgid as u32));
+        }
+    }
+
+    // The tab typed in last (then the most recently started group) is the focused one
+    tabs.sort_unstable_by(|a, b| b.cmp(a));
+    let foreground = tabs.first().and_then(|(_, pgid)| by_pid.get(pgid).copied());
+    let tui = foreground.and_then(|leader| lookup_tui(&leader.comm));
+
+    TerminalForeground {
+        cwd: foreground.and_then(|leader| {
+            std::fs::read_link(proc_root.join(leader.pid.to_string()).join("cwd"))
+                .ok()
+                .map(|path| path.to_string_lossy().to_string())
+        }),
+        tui,
+    }
+}
+
+/// When the pty with device number `tty_nr` last read input: the kernel
+/// updates a tty's access time on reads, which is how `w` reports idle time.
+fn pty_last_input(pts_root: &Path, tty_nr: i64) -> Option<SystemTime> {
+    let major = (tty_nr >> 8) & 0xfff;
+    let minor = (tty_nr & 0xff) | ((tty_nr >> 12) & 0xfff00);
+    // Unix98 ptys use majors 136-143
+    if !(136..=143).contains(&major) {
+        return None;
+    }
+    let index = (major - 136) * 256 + minor;
+    std::fs::metadata(pts_root.join(index.to_string())).ok()?.accessed().ok()
+}
+
+fn read_all_stats(proc_root: &Path) -> Vec<ProcStat> {
+    let entries = match std::fs::read_dir(proc_root) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()))
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+        .filter_map(|content| parse_stat(&content))
+        .collect()
+}
+
+/// Parse `/proc/<pid>/stat`: `pid (comm) state ppid pgrp session tty_nr tpgid ...`.
+/// `comm` may itself contain spaces and parentheses, so split on the last `)`.
+fn parse_stat(content: &str) -> Option<ProcStat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let pid = content[..open].trim().parse().ok()?;
+    let comm = content[open + 1..close].to_string();
+
+    let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+    Some(ProcStat {
+        pid,
+        comm,
+        ppid: fields.get(1)?.parse().ok()?,
+        tty_nr: fields.get(4)?.parse().ok()?,
+        tpgid: fields.get(5)?.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_stat(root: &Path, pid: u32, comm: &str, ppid: u32, pgrp: u32, tty_nr: i64, tpgid: i64) {
+        let dir = root.join(pid.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("stat"),
+            format!("{} ({}) S {} {} {} {} {} 4194304 0 0\n", pid, comm, ppid, pgrp, pgrp, tty_nr, tpgid),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = parse_stat("4242 (tmux: server) S 1 4242 4242 34817 5000 4194560 ...").unwrap();
+        assert_eq!(stat.pid, 4242);
+        assert_eq!(stat.comm, "tmux: server");
+        assert_eq!(stat.ppid, 1);
+        assert_eq!(stat.tty_nr, 34817);
+        assert_eq!(stat.tpgid, 5000);
+    }
+
+    #[test]
+    fn test_detect_foreground_tui() {
+        let root = tempfile::tempdir().unwrap();
+        // alacritty (100) -> zsh (200, pty 34816) -> claude (300, foreground group)
+        write_stat(root.path(), 100, "alacritty", 1, 100, 0, -1);
+        write_stat(root.path(), 200, "zsh", 100, 200, 34816, 300);
+        write_stat(root.path(), 300, "claude", 200, 300, 34816, 300);
+        // Unrelated terminal running nvim must not be picked up
+        write_stat(root.path(), 400, "kitty", 1, 400, 0, -1);
+        write_stat(root.path(), 500, "bash", 400, 500, 34817, 600);
+        write_stat(root.path(), 600, "nvim", 500, 600, 34817, 600);
+
+        let tui = inspect_terminal_in(root.path(), &root.path().join("pts"), 100).tui.unwrap();
+        assert_eq!(tui.app_name, "Claude Code");
+        assert_eq!(tui.process_name, "claude");
+
+        let tui = inspect_terminal_in(root.path(), &root.path().join("pts"), 400).tui.unwrap();
+        assert_eq!(tui.app_name, "Neovim");
+    }
+
+    #[test]
+    fn test_tab_typed_in_last_wins() {
+        let root = tempfile::tempdir().unwrap();
+        // kitty (100) with two tabs: nvim on pts/0, htop (started later) on pts/1
+        write_stat(root.path(), 100, "kitty", 1, 100, 0, -1);
+        write_stat(root.path(), 200, "zsh", 100, 200, 34816, 300);
+        write_stat(root.path(), 300, "nvim", 200, 300, 34816, 300);
+        write_stat(root.path(), 210, "zsh", 100, 210, 34817, 310);
+        write_stat(root.path(), 310, "htop", 210, 310, 34817, 310);
+        // A terminal window opened from the first tab's shell is not one of kitty's tabs
+        write_stat(root.path(), 250, "foot", 200, 250, 34816, 300);
+        write_stat(root.path(), 260, "bash", 250, 260, 34818, 270);
+        write_stat(root.path(), 270, "claude", 260, 270, 34818, 270);
+
+        let pts = root.path().join("pts");
+        std::fs::create_dir_all(&pts).unwrap();
+        let now = SystemTime::now();
+        for (index, seconds_ago) in [(0, 5), (1, 60), (2, 0)] {
+            let file = std::fs::File::create(pts.join(index.to_string())).unwrap();
+            let accessed = now - std::time::Duration::from_secs(seconds_ago);
+            file.set_times(std::fs::FileTimes::new().set_accessed(accessed)).unwrap();
+        }
+        assert_eq!(inspect_terminal_in(root.path(), &pts, 100).tui.unwrap().app_name, "Neovim");
+
+        // Without input times the most recently started tab wins
+        let tui = inspect_terminal_in(root.path(), &root.path().join("none"), 100).tui.unwrap();
+        assert_eq!(tui.app_name, "htop");
+    }
+
+    #[test]
+    fn test_foreground_cwd() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        write_stat(root.path(), 100, "foot", 1, 100, 0, -1);
+        write_stat(root.path(), 200, "zsh", 100, 200, 34816, 300);
+        write_stat(root.path(), 300, "cargo", 200, 300, 34816, 300);
+        std::os::unix::fs::symlink(repo.path(), root.path().join("300/cwd")).unwrap();
+
+        let foreground = inspect_terminal_in(root.path(), &root.path().join("pts"), 100);
+        assert!(foreground.tui.is_none());
+        assert_eq!(foreground.cwd.as_deref(), Some(repo.path().to_str().unwrap()));
+    }
+
+    #[test]
+    fn test_detect_idle_shell() {
+        let root = tempfile::tempdir().unwrap();
+        write_stat(root.path(), 100, "foot", 1, 100, 0, -1);
+        write_stat(root.path(), 200, "fish", 100, 200, 34816, 200);
+
+        let foreground = inspect_terminal_in(root.path(), &root.path().join("pts"), 100);
+        assert!(foreground.tui.is_none());
+        assert!(foreground.cwd.is_none());
+    }
+}
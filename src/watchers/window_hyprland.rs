@@ -0,0 +1,118 @@
+use crate::error::{Result, TimelyError};
+use crate::watchers::window_linux::WindowInfo;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub fn get_active_window() -> Result<WindowInfo> {
+    get_active_window_from(&socket_path()?)
+}
+
+/// Query the focused window via Hyprland's request socket (`j/activewindow`).
+pub fn get_active_window_from(socket: &Path) -> Result<WindowInfo> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    stream.write_all(b"j/activewindow")?;
+
+    // Hyprland writes the reply and closes the connection
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    parse_active_window(&reply)
+}
+
+/// `$XDG_RUNTIME_DIR/hypr/<sig>/.socket.sock` (Hyprland >= 0.40), falling back to
+/// the older `/tmp/hypr/<sig>/.socket.sock` location.
+fn socket_path() -> Result<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").map_err(|_| {
+        TimelyError::PlatformNotSupported("Hyprland IPC socket not found (HYPRLAND_INSTANCE_SIGNATURE is not set)".into())
+    })?;
+
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").map(PathBuf::from).ok();
+    let candidates = runtime_dir
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from("/tmp")))
+        .map(|base| base.join("hypr").join(&signature).join(".socket.sock"));
+
+    for path in candidates {
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+    Err(TimelyError::PlatformNotSupported(format!(
+        "Hyprland IPC socket for instance {} not found",
+        signature
+    )))
+}
+
+fn parse_active_window(reply: &[u8]) -> Result<WindowInfo> {
+    // With no focused window Hyprland answers `{}` (or plain text on old versions)
+    let value: serde_json::Value = serde_json::from_slice(reply).unwrap_or(serde_json::Value::Null);
+    let class = value["class"].as_str().unwrap_or_default().to_string();
+    let wm_class = if value["xwayland"].as_bool() == Some(true) {
+        Some((value["initialClass"].as_str().unwrap_or(&class).to_string(), class.clone()))
+    } else {
+        None
+    };
+
+    Ok(WindowInfo {
+        app: class,
+        title: value["title"].as_str().unwrap_or_default().to_string(),
+        wm_class,
+        pid: value["pid"].as_u64().filter(|pid| *pid > 0).map(|pid| pid as u32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    const RECORDED_ACTIVEWINDOW: &str = r#"{
+        "address": "0x55d0c0a1b2c0",
+        "mapped": true,
+        "at": [10, 40],
+        "size": [1900, 1030],
+        "workspace": {"id": 1, "name": "1"},
+        "floating": false,
+        "class": "kitty",
+        "title": "nvim src/main.rs",
+        "initialClass": "kitty",
+        "initialTitle": "kitty",
+        "pid": 4242,
+        "xwayland": false
+    }"#;
+
+    /// Fake Hyprland: accept one connection, check the request, replay a recorded reply.
+    fn fake_hyprland(reply: &'static str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".socket.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 64];
+            let n = stream.read(&mut buf).unwrap();
+            assert_eq!(&buf[..n], b"j/activewindow");
+            stream.write_all(reply.as_bytes()).unwrap();
+        });
+        (dir, path)
+    }
+
+    #[test]
+    fn test_active_window() {
+        let (_dir, path) = fake_hyprland(RECORDED_ACTIVEWINDOW);
+        let window = get_active_window_from(&path).unwrap();
+        assert_eq!(window.app, "kitty");
+        assert_eq!(window.title, "nvim src/main.rs");
+        assert_eq!(window.wm_class, None);
+        assert_eq!(window.pid, Some(4242));
+    }
+
+    #[test]
+    fn test_no_active_window() {
+        let (_dir, path) = fake_hyprland("{}");
+        let window = get_active_window_from(&path).unwrap();
+        assert!(window.app.is_empty());
+        assert!(window.title.is_empty());
+    }
+}
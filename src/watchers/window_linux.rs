@@ -0,0 +1,212 @@
+use crate::error::{Result, TimelyError};
+use std::process::Command;
+
+pub struct WindowInfo {
+    pub app: String,
+    pub title: String,
+    /// Raw `WM_CLASS` as (instance, class), e.g. ("code", "Code")
+    pub wm_class: Option<(String, String)>,
+    /// PID of the client owning the window (`_NET_WM_PID`), if advertised
+    pub pid: Option<u32>,
+}
+
+impl WindowInfo {
+    /// `WM_CLASS` as `instance.class` (e.g. "code.Code"), the app's stable identity.
+    pub fn app_id(&self) -> Option<String> {
+        self.wm_class.as_ref().map(|(instance, class)| match (instance.is_empty(), class.is_empty()) {
+            (_, true) => instance.clone(),
+            (true, false) => class.clone(),
+            (false, false) => format!("{}.{}", instance, class),
+        })
+    }
+
+    /// Executable of the process owning the window.
+    pub fn app_path(&self) -> Option<String> {
+        let pid = self.pid?;
+        std::fs::read_link(format!("/proc/{}/exe", pid))
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+}
+
+pub fn get_active_window() -> Result<WindowInfo> {
+    // Use `xprop` against the root window for `_NET_ACTIVE_WINDOW` (EWMH),
+    // then query the focused window for its class and title. This works on
+    // any EWMH-compliant window manager and under Xvfb.
+    if std::env::var_os("DISPLAY").is_none() {
+        return Err(TimelyError::PlatformNotSupported(
+            "no X11 display (DISPLAY is not set)".into(),
+        ));
+    }
+
+    let root = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+    let window_id = match parse_active_window(&root) {
+        Some(id) => id,
+        // No focused window (e.g. empty desktop)
+        None => {
+            return Ok(WindowInfo {
+                app: String::new(),
+                title: String::new(),
+                wm_class: None,
+                pid: None,
+            })
+        }
+    };
+
+    let props = xprop(&["-id", &window_id, "WM_CLASS", "_NET_WM_NAME", "WM_NAME", "_NET_WM_PID"])?;
+    let wm_class = parse_wm_class(&props);
+    let pid = parse_cardinal_property(&props, "_NET_WM_PID");
+    let title = parse_string_property(&props, "_NET_WM_NAME")
+        .or_else(|| parse_string_property(&props, "WM_NAME"))
+        .unwrap_or_default();
+
+    // WM_CLASS class part is the stable, capitalized app name ("Code", "Alacritty", "firefox")
+    let app = wm_class
+        .as_ref()
+        .map(|(instance, class)| if class.is_empty() { instance.clone() } else { class.clone() })
+        .unwrap_or_default();
+
+    Ok(WindowInfo {
+        app,
+        title,
+        wm_class,
+        pid,
+    })
+}
+
+fn xprop(args: &[&str]) -> Result<String> {
+    let output = Command::new("xprop").args(args).output()?;
+    if !output.status.success() {
+        return Err(TimelyError::Generic(format!(
+            "xprop failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00003` into the window id.
+/// Returns None when no window is focused (id 0x0) or the property is missing.
+fn parse_active_window(output: &str) -> Option<String> {
+    let line = output.lines().find(|l| l.starts_with("_NET_ACTIVE_WINDOW"))?;
+    let id = line.rsplit('#').next()?.trim();
+    // Some WMs report "0x0" or "0x0, 0x0" when nothing has focus
+    let id = id.split(',').next()?.trim();
+    if !id.starts_with("0x") || u64::from_str_radix(&id[2..], 16).ok()? == 0 {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+/// Parse `WM_CLASS(STRING) = "code", "Code"` into (instance, class).
+fn parse_wm_class(output: &str) -> Option<(String, String)> {
+    let line = output.lines().find(|l| l.starts_with("WM_CLASS("))?;
+    let (_, value) = line.split_once(" = ")?;
+    let mut parts = split_quoted(value).into_iter();
+    let instance = parts.next()?;
+    let class = parts.next().unwrap_or_default();
+    Some((instance, class))
+}
+
+/// Parse a single string property such as `_NET_WM_NAME(UTF8_STRING) = "main.rs - Code"`.
+fn parse_string_property(output: &str, name: &str) -> Option<String> {
+    let prefix = format!("{}(", name);
+    let line = output.lines().find(|l| l.starts_with(&prefix))?;
+    let (_, value) = line.split_once(" = ")?;
+    split_quoted(value).into_iter().next()
+}
+
+/// Parse a numeric property such as `_NET_WM_PID(CARDINAL) = 4242`.
+fn parse_cardinal_property(output: &str, name: &str) -> Option<u32> {
+    let prefix = format!("{}(", name);
+    let line = output.lines().find(|l| l.starts_with(&prefix))?;
+    let (_, value) = line.split_once(" = ")?;
+    value.trim().parse().ok()
+}
+
+/// Split xprop's comma-separated list of quoted strings, undoing `\"` and `\\` escapes.
+fn split_quoted(value: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                result.push(std::mem::take(&mut current));
+                in_quotes = false;
+            }
+            '"' => in_quotes = true,
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            _ if in_quotes => current.push(c),
+            _ => {}
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_active_window() {
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00003\n"),
+            Some("0x3a00003".to_string())
+        );
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n"), None);
+        assert_eq!(parse_active_window("_NET_ACTIVE_WINDOW:  not found.\n"), None);
+    }
+
+    #[test]
+    fn test_parse_window_properties() {
+        let output = "WM_CLASS(STRING) = \"code\", \"Code\"\n\
+                      _NET_WM_NAME(UTF8_STRING) = \"main.rs — \\\"timely\\\" - Visual Studio Code\"\n\
+                      WM_NAME(STRING) = \"main.rs\"\n\
+                      _NET_WM_PID(CARDINAL) = 4242\n";
+        assert_eq!(
+            parse_wm_class(output),
+            Some(("code".to_string(), "Code".to_string()))
+        );
+        assert_eq!(
+            parse_string_property(output, "_NET_WM_NAME").as_deref(),
+            Some("main.rs — \"timely\" - Visual Studio Code")
+        );
+        assert_eq!(parse_string_property(output, "WM_NAME").as_deref(), Some("main.rs"));
+        assert_eq!(parse_cardinal_property(output, "_NET_WM_PID"), Some(4242));
+    }
+
+    #[test]
+    fn test_identity() {
+        let window = WindowInfo {
+            app: "Code".to_string(),
+            title: String::new(),
+            wm_class: Some(("code".to_string(), "Code".to_string())),
+            pid: Some(std::process::id()),
+        };
+        assert_eq!(window.app_id().as_deref(), Some("code.Code"));
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(window.app_path(), Some(exe.to_string_lossy().into_owned()));
+    }
+
+    #[test]
+    fn test_parse_missing_title() {
+        let output = "WM_CLASS(STRING) = \"xterm\", \"XTerm\"\n_NET_WM_NAME:  not found.\n";
+        assert_eq!(parse_string_property(output, "_NET_WM_NAME"), None);
+    }
+
+    #[test]
+    #[ignore = "requires an X server, e.g. `xvfb-run cargo test -- --ignored`"]
+    fn test_get_active_window_against_x_server() {
+        // Under a bare Xvfb there is no window manager, so nothing may be focused;
+        // the call must still succeed rather than error.
+        let _window = get_active_window().unwrap();
+    }
+}
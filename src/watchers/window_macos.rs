@@ -4,6 +4,9 @@ use std::process::Command;
 pub struct WindowInfo {
     pub app: String,
     pub title: String,
+    pub bundle_id: Option<String>,
+    /// Path of the app bundle, e.g. "/Applications/Visual Studio Code.app"
+    pub bundle_path: Option<String>,
 }
 
 pub fn get_active_window() -> Result<WindowInfo> {
@@ -14,6 +17,12 @@ pub fn get_active_window() -> Result<WindowInfo> {
 tell application "System Events"
     set frontProc to first application process whose frontmost is true
     set frontApp to name of frontProc
+    set bundleId to ""
+    set bundlePath to ""
+    try
+        set bundleId to bundle identifier of frontProc
+        set bundlePath to POSIX path of (file of frontProc as alias)
+    end try
     try
         set windowTitle to name of front window of frontProc
     on error
@@ -21,7 +30,7 @@ tell application "System Events"
     end try
 end tell
 
-return frontApp & "|" & windowTitle
+return frontApp & "|" & bundleId & "|" & bundlePath & "|" & windowTitle
 "#;
 
     let output = Command::new("osascript")
@@ -31,15 +40,39 @@ return frontApp & "|" & windowTitle
 
     let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-    if let Some((app, title)) = stdout.split_once('|') {
-        Ok(WindowInfo {
-            app: app.to_string(),
-            title: title.to_string(),
-        })
-    } else {
-        Ok(WindowInfo {
-            app: stdout,
-            title: String::new(),
-        })
+    Ok(parse_output(&stdout))
+}
+
+/// Parse `app|bundle id|bundle path|title`; the title may itself contain `|`.
+fn parse_output(stdout: &str) -> WindowInfo {
+    let mut parts = stdout.splitn(4, '|');
+    let app = parts.next().unwrap_or_default().to_string();
+    let mut field = || parts.next().map(|s| s.trim_end_matches('/').to_string()).filter(|s| !s.is_empty());
+    let bundle_id = field();
+    let bundle_path = field();
+    let title = parts.next().unwrap_or_default().to_string();
+    WindowInfo {
+        app,
+        title,
+        bundle_id,
+        bundle_path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        let window = parse_output("Code|com.microsoft.VSCode|/Applications/Visual Studio Code.app/|a | b");
+        assert_eq!(window.app, "Code");
+        assert_eq!(window.bundle_id.as_deref(), Some("com.microsoft.VSCode"));
+        assert_eq!(window.bundle_path.as_deref(), Some("/Applications/Visual Studio Code.app"));
+        assert_eq!(window.title, "a | b");
+
+        let window = parse_output("Finder|||");
+        assert_eq!(window.bundle_id, None);
+        assert_eq!(window.title, "");
     }
 }
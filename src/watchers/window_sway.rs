@@ -0,0 +1,150 @@
+use crate::error::{Result, TimelyError};
+use crate::watchers::window_linux::WindowInfo;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+const IPC_MAGIC: &[u8] = b"i3-ipc";
+const IPC_GET_TREE: u32 = 4;
+
+pub fn get_active_window() -> Result<WindowInfo> {
+    let socket = std::env::var("SWAYSOCK")
+        .map_err(|_| TimelyError::PlatformNotSupported("sway IPC socket not found (SWAYSOCK is not set)".into()))?;
+    get_active_window_from(Path::new(&socket))
+}
+
+/// Query the focused window over the i3-ipc protocol spoken by sway.
+pub fn get_active_window_from(socket: &Path) -> Result<WindowInfo> {
+    let tree = request(socket, IPC_GET_TREE, b"")?;
+    let tree: serde_json::Value = serde_json::from_slice(&tree)?;
+    Ok(parse_focused(&tree).unwrap_or(WindowInfo {
+        app: String::new(),
+        title: String::new(),
+        wm_class: None,
+        pid: None,
+    }))
+}
+
+/// Send one i3-ipc message and read its reply payload.
+/// Frame: "i3-ipc" | u32 payload length | u32 message type | payload (native endian).
+fn request(socket: &Path, msg_type: u32, payload: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+
+    let mut msg = Vec::with_capacity(14 + payload.len());
+    msg.extend_from_slice(IPC_MAGIC);
+    msg.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    msg.extend_from_slice(&msg_type.to_ne_bytes());
+    msg.extend_from_slice(payload);
+    stream.write_all(&msg)?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != IPC_MAGIC {
+        return Err(TimelyError::Generic("sway IPC: invalid reply header".into()));
+    }
+    let len = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok(body)
+}
+
+/// Walk the layout tree (tiled and floating) for the node with `"focused": true`.
+fn parse_focused(node: &serde_json::Value) -> Option<WindowInfo> {
+    if node["focused"].as_bool() == Some(true) && node["type"] != "workspace" && node["type"] != "output" {
+        let props = &node["window_properties"];
+        let wm_class = props["class"].as_str().map(|class| {
+            (
+                props["instance"].as_str().unwrap_or_default().to_string(),
+                class.to_string(),
+            )
+        });
+        // Native Wayland windows have an app_id; XWayland windows only a class
+        let app = node["app_id"]
+            .as_str()
+            .filter(|id| !id.is_empty())
+            .map(String::from)
+            .or_else(|| wm_class.as_ref().map(|(_, class)| class.clone()))
+            .unwrap_or_default();
+        return Some(WindowInfo {
+            app,
+            title: node["name"].as_str().unwrap_or_default().to_string(),
+            wm_class,
+            pid: node["pid"].as_u64().map(|pid| pid as u32),
+        });
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(parse_focused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    const RECORDED_TREE: &str = r#"{"id":1,"type":"root","focused":false,"nodes":[
+        {"id":3,"type":"output","name":"eDP-1","focused":false,"nodes":[
+            {"id":4,"type":"workspace","name":"1","focused":false,"nodes":[
+                {"id":7,"type":"con","name":"~/src/timely","app_id":"Alacritty","pid":4242,"focused":false,"nodes":[]}
+            ],"floating_nodes":[
+                {"id":9,"type":"floating_con","name":"main.rs - timely - Visual Studio Code","app_id":null,"pid":4343,
+                 "window_properties":{"class":"Code","instance":"code","title":"main.rs"},"focused":true,"nodes":[]}
+            ]}
+        ]}
+    ]}"#;
+
+    /// Fake sway: accept one connection, check the GET_TREE request, replay a recorded reply.
+    fn fake_sway(reply: &'static str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sway-ipc.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut header = [0u8; 14];
+            stream.read_exact(&mut header).unwrap();
+            assert_eq!(&header[..6], IPC_MAGIC);
+            assert_eq!(u32::from_ne_bytes([header[10], header[11], header[12], header[13]]), IPC_GET_TREE);
+
+            let mut frame = IPC_MAGIC.to_vec();
+            frame.extend_from_slice(&(reply.len() as u32).to_ne_bytes());
+            frame.extend_from_slice(&IPC_GET_TREE.to_ne_bytes());
+            frame.extend_from_slice(reply.as_bytes());
+            stream.write_all(&frame).unwrap();
+        });
+        (dir, path)
+    }
+
+    #[test]
+    fn test_focused_xwayland_window() {
+        let (_dir, path) = fake_sway(RECORDED_TREE);
+        let window = get_active_window_from(&path).unwrap();
+        assert_eq!(window.app, "Code");
+        assert_eq!(window.title, "main.rs - timely - Visual Studio Code");
+        assert_eq!(window.wm_class, Some(("code".to_string(), "Code".to_string())));
+        assert_eq!(window.pid, Some(4343));
+    }
+
+    #[test]
+    fn test_focused_native_window() {
+        let tree = RECORDED_TREE.replace("\"focused\":true", "\"focused\":false")
+            .replace("\"pid\":4242,\"focused\":false", "\"pid\":4242,\"focused\":true");
+        let window = parse_focused(&serde_json::from_str(&tree).unwrap()).unwrap();
+        assert_eq!(window.app, "Alacritty");
+        assert_eq!(window.title, "~/src/timely");
+        assert_eq!(window.wm_class, None);
+        assert_eq!(window.pid, Some(4242));
+    }
+
+    #[test]
+    fn test_focused_empty_workspace() {
+        let (_dir, path) = fake_sway(r#"{"id":1,"type":"root","focused":false,"nodes":[
+            {"id":4,"type":"workspace","name":"2","focused":true,"nodes":[]}]}"#);
+        let window = get_active_window_from(&path).unwrap();
+        assert!(window.app.is_empty());
+    }
+}
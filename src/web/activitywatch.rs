@@ -0,0 +1,265 @@
+//! ActivityWatch-compatible ingestion (`/api/0/...`), so existing AW watchers
+//! (aw-watcher-window, aw-watcher-web, editor plugins) can report into timely.
+//!
+//! Every bucket records onto its own device. `afkstatus` buckets don't produce
+//! events; their latest status marks events from other buckets on the same host as AFK.
+
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::response::Json;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::daemon::heartbeat;
+use crate::db;
+use crate::db::buckets::{self, Bucket};
+use crate::db::{devices, events};
+use crate::types::{EventSource, WatcherSnapshot};
+use crate::watchers::extract_domain;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwEvent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBucketBody {
+    pub client: String,
+    #[serde(rename = "type")]
+    pub bucket_type: String,
+    pub hostname: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeartbeatParams {
+    pub pulsetime: Option<f64>,
+}
+
+fn aw_error(status: StatusCode, msg: String) -> (StatusCode, Json<serde_json::Value>) {
+    (status, Json(serde_json::json!({ "message": msg })))
+}
+
+fn internal_error(msg: String) -> (StatusCode, Json<serde_json::Value>) {
+    aw_error(StatusCode::INTERNAL_SERVER_ERROR, msg)
+}
+
+/// Map an AW event's `data` to a snapshot, based on the bucket type.
+/// Returns None for buckets that don't describe foreground activity.
+pub fn snapshot_from_data(bucket: &Bucket, data: &serde_json::Value, is_afk: bool) -> Option<WatcherSnapshot> {
+    let str_field = |key: &str| data[key].as_str().filter(|s| !s.is_empty()).map(String::from);
+
+    let (app, title, url) = match bucket.bucket_type.as_str() {
+        "afkstatus" => return None,
+        "currentwindow" => (str_field("app")?, str_field("title").unwrap_or_default(), None),
+        // aw-watcher-web: the bucket id names the browser ("aw-watcher-web-firefox_myhost")
+        "web.tab.current" => {
+            let browser = bucket
+                .id
+                .strip_prefix("aw-watcher-web-")
+                .and_then(|rest| rest.split('_').next())
+                .filter(|b| !b.is_empty())
+                .map(capitalize)
+                .unwrap_or_else(|| bucket.client.clone());
+            (browser, str_field("title").unwrap_or_default(), str_field("url"))
+        }
+        // Editor plugins (app.editor.activity) and anything else: best effort
+        _ => {
+            let app = str_field("app")
+                .or_else(|| str_field("editor"))
+                .unwrap_or_else(|| bucket.client.trim_start_matches("aw-watcher-").to_string());
+            let title = str_field("title").or_else(|| str_field("file")).unwrap_or_default();
+            (app, title, str_field("url"))
+        }
+    };
+
+    let url_domain = url.as_deref().map(extract_domain).filter(|d| !d.is_empty());
+    Some(WatcherSnapshot {
+        app,
+        title,
+        url,
+        url_domain,
+        is_afk,
+        // aw-watcher-web flags incognito tabs
+        is_private: data["incognito"].as_bool().unwrap_or(false),
+        project: str_field("project"),
+        file: str_field("file"),
+        language: str_field("language"),
+        branch: str_field("branch"),
+        source: EventSource::ActivityWatch,
+        ..Default::default()
+    })
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Apply an AW heartbeat to a bucket with `process_heartbeat` merge semantics,
+/// using `pulsetime` as the merge gap. Returns None if the bucket doesn't exist.
+pub fn record_heartbeat(
+    conn: &Connection,
+    bucket_id: &str,
+    heartbeat: &AwEvent,
+    pulsetime: f64,
+) -> crate::error::Result<Option<AwEvent>> {
+    let bucket = match buckets::get_bucket(conn, bucket_id)? {
+        Some(bucket) => bucket,
+        None => return Ok(None),
+    };
+    buckets::touch_bucket(conn, bucket_id, &heartbeat.data)?;
+
+    let is_afk = buckets::afk_status_for_host(conn, &bucket.hostname)?.unwrap_or(false);
+    let snapshot = match snapshot_from_data(&bucket, &heartbeat.data, is_afk) {
+        Some(snapshot) => snapshot,
+        None => return Ok(Some(heartbeat.clone())),
+    };
+
+    // One heartbeat per request, so the pipeline is loaded per request
+    let event_id = heartbeat::Pipeline::from_config(conn)?.process(
+        conn,
+        &bucket.device_id,
+        &snapshot,
+        &heartbeat.timestamp,
+        heartbeat.duration,
+        pulsetime,
+    )?;
+
+    // Reply with the (possibly merged) event, as aw-server does
+    match (event_id, events::get_last_event(conn, &bucket.device_id)?) {
+        (Some(id), Some(event)) if event.id == id => Ok(Some(AwEvent {
+            id: Some(event.id),
+            timestamp: event.timestamp,
+            duration: event.duration,
+            data: heartbeat.data.clone(),
+        })),
+        _ => Ok(Some(heartbeat.clone())),
+    }
+}
+
+// --- Handlers ---
+
+pub async fn get_info() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "hostname": devices::hostname(),
+        "version": format!("timely v{}", env!("CARGO_PKG_VERSION")),
+        "testing": false,
+    }))
+}
+
+pub async fn get_buckets() -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        let list = buckets::list_buckets(&conn).map_err(|e| internal_error(e.to_string()))?;
+        let map: serde_json::Map<String, serde_json::Value> =
+            list.iter().map(|b| (b.id.clone(), serde_json::to_value(b).unwrap())).collect();
+        Ok(Json(serde_json::Value::Object(map)))
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
+pub async fn get_bucket(
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        match buckets::get_bucket(&conn, &id).map_err(|e| internal_error(e.to_string()))? {
+            Some(bucket) => Ok(Json(serde_json::to_value(bucket).unwrap())),
+            None => Err(aw_error(StatusCode::NOT_FOUND, format!("There's no bucket named {}", id))),
+        }
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
+pub async fn post_bucket(
+    Path(id): Path<String>,
+    Json(body): Json<CreateBucketBody>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        let created = buckets::create_bucket(&conn, &id, &body.bucket_type, &body.client, &body.hostname)
+            .map_err(|e| internal_error(e.to_string()))?;
+        // aw-server answers 304 when the bucket already exists; clients treat it as success
+        Ok(if created { StatusCode::OK } else { StatusCode::NOT_MODIFIED })
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
+pub async fn post_heartbeat(
+    Path(id): Path<String>,
+    Query(params): Query<HeartbeatParams>,
+    Json(body): Json<AwEvent>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let pulsetime = params.pulsetime.unwrap_or(0.0);
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        match record_heartbeat(&conn, &id, &body, pulsetime).map_err(|e| internal_error(e.to_string()))? {
+            Some(event) => Ok(Json(serde_json::to_value(event).unwrap())),
+            None => Err(aw_error(StatusCode::NOT_FOUND, format!("There's no bucket named {}", id))),
+        }
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(id: &str, bucket_type: &str, client: &str) -> Bucket {
+        Bucket {
+            id: id.to_string(),
+            device_id: "dev".to_string(),
+            bucket_type: bucket_type.to_string(),
+            client: client.to_string(),
+            hostname: "host".to_string(),
+            created: Utc::now(),
+            last_updated: None,
+            last_data: None,
+        }
+    }
+
+    #[test]
+    fn test_window_bucket_mapping() {
+        let b = bucket("aw-watcher-window_host", "currentwindow", "aw-watcher-window");
+        let s = snapshot_from_data(&b, &serde_json::json!({"app": "Code", "title": "main.rs"}), false).unwrap();
+        assert_eq!(s.app, "Code");
+        assert_eq!(s.title, "main.rs");
+        assert!(s.url.is_none());
+    }
+
+    #[test]
+    fn test_web_bucket_mapping() {
+        let b = bucket("aw-watcher-web-firefox_host", "web.tab.current", "aw-client-web");
+        let data = serde_json::json!({"url": "https://github.com/rust-lang", "title": "Rust", "audible": false});
+        let s = snapshot_from_data(&b, &data, false).unwrap();
+        assert_eq!(s.app, "Firefox");
+        assert_eq!(s.url_domain.as_deref(), Some("github.com"));
+    }
+
+    #[test]
+    fn test_editor_and_afk_buckets() {
+        let b = bucket("aw-watcher-vim_host", "app.editor.activity", "aw-watcher-vim");
+        let data = serde_json::json!({"file": "src/main.rs", "project": "timely", "language": "rust"});
+        let s = snapshot_from_data(&b, &data, false).unwrap();
+        assert_eq!(s.app, "vim");
+        assert_eq!(s.title, "src/main.rs");
+
+        let afk = bucket("aw-watcher-afk_host", "afkstatus", "aw-watcher-afk");
+        assert!(snapshot_from_data(&afk, &serde_json::json!({"status": "afk"}), false).is_none());
+    }
+}
@@ -3,10 +3,12 @@ use axum::http::{header, StatusCode, Uri};
 use axum::response::{Html, IntoResponse, Json, Response};
 use serde::Deserialize;
 
+use crate::afk;
 use crate::db;
-use crate::db::categories;
+use crate::db::{categories, devices};
+use crate::error::TimelyError;
 use crate::query;
-use crate::types::format_duration;
+use crate::types::{format_duration, CurrentActivity};
 use crate::web::assets::DashboardAssets;
 
 // --- Query parameter structs ---
@@ -73,6 +75,16 @@ pub struct UpdateRuleBody {
     pub category_id: Option<i64>,
 }
 
+#[derive(Deserialize)]
+pub struct AfkLabelBody {
+    /// An AFK period id, or `from` and `to` (HH:MM today, or RFC 3339)
+    pub id: Option<i64>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub category: Option<String>,
+    pub note: Option<String>,
+}
+
 // --- Helpers ---
 
 fn today_str() -> String {
@@ -102,6 +114,10 @@ fn date_range(from: Option<String>, to: Option<String>) -> (String, String) {
     (from_utc, to_utc)
 }
 
+fn bad_request(msg: String) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": msg })))
+}
+
 fn internal_error(msg: String) -> (StatusCode, Json<serde_json::Value>) {
     (
         StatusCode::INTERNAL_SERVER_ERROR,
@@ -109,6 +125,24 @@ fn internal_error(msg: String) -> (StatusCode, Json<serde_json::Value>) {
     )
 }
 
+/// Daemon coverage between two RFC 3339 boundaries from `date_range`.
+/// Malformed dates have no coverage rather than failing the whole request.
+fn coverage_between(
+    conn: &rusqlite::Connection,
+    from: &str,
+    to: &str,
+) -> Result<crate::types::CoverageResponse, (StatusCode, Json<serde_json::Value>)> {
+    let parse = |s: &str| chrono::DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&chrono::Utc));
+    let (from, to) = match (parse(from), parse(to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        _ => {
+            let now = chrono::Utc::now();
+            (now, now)
+        }
+    };
+    query::coverage::build_coverage(conn, &from, &to).map_err(|e| internal_error(e.to_string()))
+}
+
 /// Resolve a device filter string to a device_id, or None for "all"/absent.
 fn resolve_device_id(conn: &rusqlite::Connection, device: &Option<String>) -> Option<String> {
     match device {
@@ -148,16 +182,34 @@ pub async fn get_summary(
                     "SELECT CASE
                        WHEN e.url_domain IS NOT NULL AND e.url_domain != ''
                        THEN e.url_domain
-                       ELSE COALESCE(e.app, 'Unknown')
+                       ELSE COALESCE({}, 'Unknown')
                      END as label, SUM(e.duration) as total_seconds
                      FROM events e
+                     {}
                      WHERE e.timestamp >= ?1 AND e.timestamp <= ?2 AND e.is_afk = 0 AND e.duration > 0{}
                      GROUP BY label
                      ORDER BY total_seconds DESC",
+                    db::apps::CANONICAL_APP,
+                    db::apps::ALIAS_JOIN,
                     device_clause,
                 ),
                 true,
             )
+        } else if group_by == "project" || group_by == "language" {
+            let column = if group_by == "project" { "e.project" } else { "e.language" };
+            (
+                format!(
+                    "SELECT {col} as name, SUM(e.duration) as total_seconds
+                     FROM events e
+                     WHERE e.timestamp >= ?1 AND e.timestamp <= ?2 AND e.is_afk = 0 AND e.duration > 0
+                       AND {col} IS NOT NULL{}
+                     GROUP BY {col}
+                     ORDER BY total_seconds DESC",
+                    device_clause,
+                    col = column,
+                ),
+                true,
+            )
         } else {
             (
                 format!(
@@ -209,11 +261,19 @@ pub async fn get_summary(
             })
             .collect();
 
+        let coverage = coverage_between(&conn, &from_date, &to_date)?;
+
         Ok(Json(serde_json::json!({
             "period_from": from_date,
             "period_to": to_date,
             "total_active": format_duration(total_seconds),
             "total_active_seconds": total_seconds.round() as i64,
+            "tracked": coverage.tracked_time,
+            "tracked_seconds": coverage.tracked_seconds.round() as i64,
+            "untracked": coverage.untracked_time,
+            "untracked_seconds": coverage.untracked_seconds.round() as i64,
+            "paused": coverage.paused_time,
+            "paused_seconds": coverage.paused_seconds.round() as i64,
             "groups": groups,
         })))
     })
@@ -224,6 +284,23 @@ pub async fn get_summary(
 pub async fn get_current() -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)>
 {
     tokio::task::spawn_blocking(move || {
+        // Prefer the running daemon's view over the last stored event
+        let live = crate::config::control_socket_path()
+            .ok()
+            .and_then(|path| crate::daemon::control::snapshot(&path).ok())
+            .and_then(|live| live.current);
+        if let Some(e) = live {
+            return Ok(Json(serde_json::to_value(CurrentActivity {
+                app: Some(e.app),
+                title: Some(e.title),
+                url: e.url,
+                category: e.category_name.unwrap_or_else(|| "uncategorized".to_string()),
+                duration_seconds: e.duration,
+                is_afk: e.is_afk,
+                since: e.timestamp.to_rfc3339(),
+            }).unwrap()));
+        }
+
         let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
         let current =
             query::current::get_current(&conn).map_err(|e| internal_error(e.to_string()))?;
@@ -326,6 +403,21 @@ pub async fn get_timeline(
     .map_err(|e| internal_error(e.to_string()))?
 }
 
+/// Tracked vs untracked time, with the periods when timely was not running.
+pub async fn get_coverage(
+    Query(params): Query<TimeRangeParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let (from_date, to_date) = date_range(params.from, params.to);
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        let coverage = coverage_between(&conn, &from_date, &to_date)?;
+        Ok(Json(serde_json::to_value(coverage).unwrap()))
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
 pub async fn get_productivity(
     Query(params): Query<TimeRangeParams>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
@@ -423,8 +515,7 @@ pub async fn post_rule(
         }
 
         // Recategorize matching events
-        let updated = recategorize_events(&conn, &field, &pattern, category_id)
-            .map_err(|e| internal_error(e.to_string()))?;
+        let updated = recategorize_events(&conn, &field, &pattern)?;
 
         Ok(Json(serde_json::json!({ "success": true, "updated": updated })))
     })
@@ -480,8 +571,7 @@ pub async fn put_rule(
         )
         .map_err(|e| internal_error(e.to_string()))?;
 
-        let updated = recategorize_events(&conn, &field, &pattern, category_id)
-            .map_err(|e| internal_error(e.to_string()))?;
+        let updated = recategorize_events(&conn, &field, &pattern)?;
 
         Ok(Json(serde_json::json!({ "success": true, "updated": updated })))
     })
@@ -520,43 +610,66 @@ pub async fn delete_rule(
             ));
         }
 
-        // Find matching builtin rule for fallback
-        let builtin_cat: Option<i64> = conn
-            .query_row(
-                "SELECT category_id FROM category_rules WHERE field = ?1 AND pattern = ?2 AND is_builtin = 1 LIMIT 1",
-                rusqlite::params![field, pattern],
-                |row| row.get(0),
-            )
-            .ok();
-
-        let fallback_cat = match builtin_cat {
-            Some(id) => Some(id),
-            None => conn
-                .query_row(
-                    "SELECT id FROM categories WHERE name = 'uncategorized' LIMIT 1",
-                    [],
-                    |row| row.get(0),
-                )
-                .ok(),
-        };
-
-        // Recategorize affected events
-        let recategorized = recategorize_events_nullable(&conn, &field, &pattern, fallback_cat)
-            .map_err(|e| internal_error(e.to_string()))?;
-
-        // Delete the rule
         conn.execute(
             "DELETE FROM category_rules WHERE id = ?1",
             rusqlite::params![rule_id],
         )
         .map_err(|e| internal_error(e.to_string()))?;
 
+        // Events the rule covered fall back to the next matching rule, or uncategorized
+        let recategorized = recategorize_events(&conn, &field, &pattern)?;
+
         Ok(Json(serde_json::json!({ "success": true, "recategorized": recategorized })))
     })
     .await
     .map_err(|e| internal_error(e.to_string()))?
 }
 
+/// AFK periods of this machine, for labeling.
+pub async fn get_afk(
+    Query(params): Query<TimeRangeParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let (from_date, to_date) = date_range(params.from, params.to);
+
+    tokio::task::spawn_blocking(move || {
+        let parse = |s: &str| query::parse_time(s).map_err(|e| bad_request(e.to_string()));
+        let (from, to) = (parse(&from_date)?, parse(&to_date)?);
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        let device = devices::get_or_create_device(&conn).map_err(|e| internal_error(e.to_string()))?;
+        let periods = afk::list_periods(&conn, &device.id, &from, &to, 0.0).map_err(|e| internal_error(e.to_string()))?;
+        Ok(Json(serde_json::to_value(periods).unwrap()))
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
+/// Label AFK time as offline activity; see `timely afk label`.
+pub async fn post_afk_label(
+    Json(body): Json<AfkLabelBody>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let Some(category) = body.category.filter(|c| !c.is_empty()) else {
+        return Err(bad_request("Missing required field: category".into()));
+    };
+    let target = match (body.id, body.from, body.to) {
+        (Some(id), _, _) => afk::LabelTarget::Period(id),
+        (None, Some(from), Some(to)) => afk::parse_target(&format!("{}..{}", from, to), &chrono::Local::now())
+            .map_err(|e| bad_request(e.to_string()))?,
+        _ => return Err(bad_request("Give either id, or from and to".into())),
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        let device = devices::get_or_create_device(&conn).map_err(|e| internal_error(e.to_string()))?;
+        let result = afk::label(&conn, &device.id, &target, &category, body.note.as_deref()).map_err(|e| match e {
+            TimelyError::Generic(msg) => bad_request(msg),
+            e => internal_error(e.to_string()),
+        })?;
+        Ok(Json(serde_json::to_value(result).unwrap()))
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
+
 pub async fn get_urls(
     Query(params): Query<UrlsParams>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
@@ -693,20 +806,22 @@ pub async fn get_app_details(
     tokio::task::spawn_blocking(move || {
         let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
 
-        // Match on either app name or url_domain
-        let mut stmt = conn
-            .prepare(
-                "SELECT e.timestamp, e.duration, e.app, e.title, e.url,
-                        COALESCE(c.name, 'uncategorized') as category
-                 FROM events e
-                 LEFT JOIN categories c ON e.category_id = c.id
-                 WHERE e.timestamp >= ?1 AND e.timestamp <= ?2
-                   AND e.is_afk = 0 AND e.duration > 0
-                   AND (e.app = ?3 OR e.url_domain = ?3)
-                 ORDER BY e.timestamp ASC
-                 LIMIT 200",
-            )
-            .map_err(|e| internal_error(e.to_string()))?;
+        // Match on either the canonical app name or url_domain
+        let sql = format!(
+            "SELECT e.timestamp, e.duration, e.app, e.title, e.url,
+                    COALESCE(c.name, 'uncategorized') as category
+             FROM events e
+             LEFT JOIN categories c ON e.category_id = c.id
+             {}
+             WHERE e.timestamp >= ?1 AND e.timestamp <= ?2
+               AND e.is_afk = 0 AND e.duration > 0
+               AND ({} = ?3 OR e.url_domain = ?3)
+             ORDER BY e.timestamp ASC
+             LIMIT 200",
+            db::apps::ALIAS_JOIN,
+            db::apps::CANONICAL_APP,
+        );
+        let mut stmt = conn.prepare(&sql).map_err(|e| internal_error(e.to_string()))?;
 
         let rows = stmt
             .query_map(rusqlite::params![from_date, to_date, name], |row| {
@@ -735,62 +850,15 @@ pub async fn get_app_details(
     .map_err(|e| internal_error(e.to_string()))?
 }
 
+/// Reapply the current rules to the events a `field`/`pattern` rule covers.
 fn recategorize_events(
     conn: &rusqlite::Connection,
     field: &str,
     pattern: &str,
-    category_id: i64,
-) -> std::result::Result<usize, String> {
-    let changed = match field {
-        "app" => conn
-            .execute(
-                "UPDATE events SET category_id = ?1 WHERE app = ?2 AND is_afk = 0",
-                rusqlite::params![category_id, pattern],
-            )
-            .map_err(|e| e.to_string())?,
-        "url_domain" => conn
-            .execute(
-                "UPDATE events SET category_id = ?1 WHERE url_domain = ?2 AND is_afk = 0",
-                rusqlite::params![category_id, pattern],
-            )
-            .map_err(|e| e.to_string())?,
-        _ => conn
-            .execute(
-                "UPDATE events SET category_id = ?1 WHERE title LIKE ?2 AND is_afk = 0",
-                rusqlite::params![category_id, format!("%{}%", pattern)],
-            )
-            .map_err(|e| e.to_string())?,
-    };
-    Ok(changed)
-}
-
-fn recategorize_events_nullable(
-    conn: &rusqlite::Connection,
-    field: &str,
-    pattern: &str,
-    category_id: Option<i64>,
-) -> std::result::Result<usize, String> {
-    let changed = match field {
-        "app" => conn
-            .execute(
-                "UPDATE events SET category_id = ?1 WHERE app = ?2 AND is_afk = 0",
-                rusqlite::params![category_id, pattern],
-            )
-            .map_err(|e| e.to_string())?,
-        "url_domain" => conn
-            .execute(
-                "UPDATE events SET category_id = ?1 WHERE url_domain = ?2 AND is_afk = 0",
-                rusqlite::params![category_id, pattern],
-            )
-            .map_err(|e| e.to_string())?,
-        _ => conn
-            .execute(
-                "UPDATE events SET category_id = ?1 WHERE title LIKE ?2 AND is_afk = 0",
-                rusqlite::params![category_id, format!("%{}%", pattern)],
-            )
-            .map_err(|e| e.to_string())?,
-    };
-    Ok(changed)
+) -> Result<usize, (StatusCode, Json<serde_json::Value>)> {
+    let rules = crate::categories::rule_set(conn).map_err(|e| internal_error(e.to_string()))?;
+    let covered = crate::categories::RuleSet::for_pattern(field, pattern);
+    crate::categories::reclassify_events(conn, &rules, Some(&covered)).map_err(|e| internal_error(e.to_string()))
 }
 
 // --- Embedded SPA serving ---
@@ -813,3 +881,34 @@ pub async fn serve_embedded(uri: Uri) -> Response {
         (StatusCode::NOT_FOUND, "Dashboard not built. Run: make build").into_response()
     }
 }
+
+// --- Ingestion ---
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum IngestBody {
+    Many(Vec<crate::ingest::IngestEvent>),
+    One(Box<crate::ingest::IngestEvent>),
+}
+
+pub async fn post_events(
+    Json(body): Json<IngestBody>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let events = match body {
+        IngestBody::Many(events) => events,
+        IngestBody::One(event) => vec![*event],
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db().map_err(|e| internal_error(e.to_string()))?;
+        match crate::ingest::ingest_events(&conn, &events) {
+            Ok(result) => Ok(Json(crate::output::success(&result))),
+            Err(e @ crate::error::TimelyError::Config(_)) => {
+                Err((StatusCode::BAD_REQUEST, Json(crate::output::error(&e))))
+            }
+            Err(e) => Err(internal_error(e.to_string())),
+        }
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+}
@@ -1,3 +1,5 @@
+pub mod activitywatch;
 pub mod assets;
 pub mod handlers;
 pub mod router;
+pub mod wakatime;
@@ -3,7 +3,7 @@ use axum::routing::{delete, get, post, put};
 use axum::Router;
 use tower_http::cors::CorsLayer;
 
-use super::handlers;
+use super::{activitywatch, handlers, wakatime};
 use crate::sync::{auth, server as sync_server};
 
 pub fn build_router() -> Router {
@@ -13,15 +13,38 @@ pub fn build_router() -> Router {
         .route("/api/categories", get(handlers::get_categories))
         .route("/api/apps", get(handlers::get_apps))
         .route("/api/timeline", get(handlers::get_timeline))
+        .route("/api/coverage", get(handlers::get_coverage))
         .route("/api/productivity", get(handlers::get_productivity))
         .route("/api/trends", get(handlers::get_trends))
         .route("/api/apps/{name}/details", get(handlers::get_app_details))
         .route("/api/urls", get(handlers::get_urls))
+        .route("/api/afk", get(handlers::get_afk))
+        .route("/api/afk/label", post(handlers::post_afk_label))
         .route("/api/rules", get(handlers::get_rules))
         .route("/api/rules", post(handlers::post_rule))
         .route("/api/rules/{id}", put(handlers::put_rule))
         .route("/api/rules/{id}", delete(handlers::delete_rule));
 
+    // ActivityWatch-compatible ingestion, so existing AW watchers can report to timely.
+    // AW clients send no key, so only watchers on this machine are let in.
+    let aw_api = Router::new()
+        .route("/api/0/info", get(activitywatch::get_info))
+        .route("/api/0/buckets", get(activitywatch::get_buckets))
+        .route("/api/0/buckets/", get(activitywatch::get_buckets))
+        .route("/api/0/buckets/{id}", get(activitywatch::get_bucket).post(activitywatch::post_bucket))
+        .route("/api/0/buckets/{id}/heartbeat", post(activitywatch::post_heartbeat))
+        .layer(middleware::from_fn(auth::require_local_client));
+
+    // WakaTime-compatible heartbeats from editor plugins (auth via wakatime.api_key)
+    let wakatime_api = Router::new()
+        .route("/api/v1/users/current/heartbeats", post(wakatime::post_heartbeat))
+        .route("/api/v1/users/current/heartbeats.bulk", post(wakatime::post_heartbeats_bulk));
+
+    // Event ingestion — only with a configured API key
+    let ingest_api = Router::new()
+        .route("/api/events", post(handlers::post_events))
+        .layer(middleware::from_fn(auth::require_configured_api_key));
+
     // Sync API routes — protected by API key middleware
     let sync_api = Router::new()
         .route("/api/sync/push", post(sync_server::handle_push))
@@ -31,6 +54,9 @@ pub fn build_router() -> Router {
 
     Router::new()
         .merge(api)
+        .merge(aw_api)
+        .merge(wakatime_api)
+        .merge(ingest_api)
         .merge(sync_api)
         .fallback(handlers::serve_embedded)
         .layer(CorsLayer::permissive())
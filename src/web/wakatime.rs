@@ -0,0 +1,294 @@
+//! WakaTime-compatible heartbeat ingestion, so editor plugins can report
+//! file/project/language/branch context. Point a plugin at
+//! `api_url = http://localhost:<port>/api/v1`, with `api_key` set to
+//! `wakatime.api_key`.
+//!
+//! Heartbeats from each editor record onto their own device (`wakatime-<editor>`).
+
+use axum::extract::Json;
+use axum::http::{header, HeaderMap, StatusCode};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use crate::config::WAKATIME_MERGE_GAP_SECS;
+use crate::daemon::heartbeat;
+use crate::db;
+use crate::db::{config_store, devices};
+use crate::types::{EventSource, WatcherSnapshot};
+use crate::watchers::extract_domain;
+
+/// Platform recorded for devices backing WakaTime editors.
+pub const WAKATIME_PLATFORM: &str = "wakatime";
+
+/// Plugin name (from `<plugin>-wakatime/<version>`) -> app name used by category rules.
+const EDITOR_APPS: &[(&str, &str)] = &[
+    ("vscode", "Code"),
+    ("cursor", "Cursor"),
+    ("vim", "Vim"),
+    ("neovim", "Neovim"),
+    ("emacs", "Emacs"),
+    ("sublime", "Sublime Text"),
+    ("jetbrains", "IntelliJ IDEA"),
+    ("intellij", "IntelliJ IDEA"),
+    ("xcode", "Xcode"),
+    ("zed", "Zed"),
+    ("helix", "Helix"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WakaHeartbeat {
+    pub entity: String,
+    /// `file`, `app`, `domain` or `url`
+    #[serde(rename = "type", default)]
+    pub entity_type: Option<String>,
+    /// Unix timestamp in (fractional) seconds
+    pub time: f64,
+    pub project: Option<String>,
+    pub branch: Option<String>,
+    pub language: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// Editor app name from a WakaTime user agent such as
+/// `wakatime/v1.73.1 (linux-6.5.0) go1.21.1 vscode/1.85.1 vscode-wakatime/24.4.0`.
+pub fn editor_from_user_agent(user_agent: &str) -> String {
+    let tokens: Vec<&str> = user_agent.split_whitespace().collect();
+    let Some(pos) = tokens.iter().position(|t| t.contains("-wakatime/")) else {
+        return "WakaTime".to_string();
+    };
+    let plugin = tokens[pos].split("-wakatime/").next().unwrap_or_default().to_lowercase();
+    // vim-wakatime also serves Neovim, which names itself in the preceding token
+    let editor = match pos.checked_sub(1).map(|i| tokens[i].split('/').next().unwrap_or_default()) {
+        Some(prev) if prev.eq_ignore_ascii_case("neovim") => "neovim".to_string(),
+        _ => plugin,
+    };
+
+    EDITOR_APPS
+        .iter()
+        .find(|(name, _)| *name == editor)
+        .map(|(_, app)| app.to_string())
+        .unwrap_or_else(|| {
+            let mut chars = editor.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => "WakaTime".to_string(),
+            }
+        })
+}
+
+pub fn snapshot_from_heartbeat(app: &str, heartbeat: &WakaHeartbeat) -> WatcherSnapshot {
+    let non_empty = |v: &Option<String>| v.clone().filter(|s| !s.is_empty());
+
+    let mut snapshot = WatcherSnapshot {
+        app: app.to_string(),
+        title: heartbeat.entity.clone(),
+        project: non_empty(&heartbeat.project),
+        language: non_empty(&heartbeat.language),
+        branch: non_empty(&heartbeat.branch),
+        source: EventSource::WakaTime,
+        ..Default::default()
+    };
+    match heartbeat.entity_type.as_deref().unwrap_or("file") {
+        "file" => snapshot.file = Some(heartbeat.entity.clone()),
+        "domain" => snapshot.url_domain = Some(heartbeat.entity.clone()),
+        "url" => {
+            snapshot.url_domain = Some(extract_domain(&heartbeat.entity)).filter(|d| !d.is_empty());
+            snapshot.url = Some(heartbeat.entity.clone());
+        }
+        _ => {}
+    }
+    snapshot
+}
+
+/// Store heartbeats as events, merging consecutive heartbeats on the same
+/// file within the WakaTime timeout. Returns the affected event id per heartbeat.
+pub fn record_heartbeats(
+    conn: &Connection,
+    heartbeats: &[WakaHeartbeat],
+    default_user_agent: Option<&str>,
+) -> crate::error::Result<Vec<Option<i64>>> {
+    // Plugins flush their offline queue in bulk, not necessarily in order
+    let mut order: Vec<usize> = (0..heartbeats.len()).collect();
+    order.sort_by(|a, b| heartbeats[*a].time.total_cmp(&heartbeats[*b].time));
+
+    let pipeline = heartbeat::Pipeline::from_config(conn)?;
+    let mut ids = vec![None; heartbeats.len()];
+    for i in order {
+        let hb = &heartbeats[i];
+        let app = editor_from_user_agent(hb.user_agent.as_deref().or(default_user_agent).unwrap_or_default());
+        let device = devices::get_or_create_named_device(
+            conn,
+            &format!("wakatime-{}", app.to_lowercase().replace(' ', "-")),
+            WAKATIME_PLATFORM,
+        )?;
+        let timestamp = DateTime::<Utc>::from_timestamp_millis((hb.time * 1000.0) as i64)
+            .ok_or_else(|| crate::error::TimelyError::Generic(format!("invalid heartbeat time: {}", hb.time)))?;
+
+        ids[i] = pipeline.process(
+            conn,
+            &device.id,
+            &snapshot_from_heartbeat(&app, hb),
+            &timestamp,
+            0.0,
+            WAKATIME_MERGE_GAP_SECS,
+        )?;
+    }
+    Ok(ids)
+}
+
+fn waka_error(status: StatusCode, msg: String) -> (StatusCode, Json<serde_json::Value>) {
+    (status, Json(serde_json::json!({ "error": msg })))
+}
+
+/// Require `wakatime.api_key` as `Authorization: Basic base64(key)` (what the
+/// plugins send) or `Bearer key`. Nothing is accepted until a key is set.
+fn check_api_key(conn: &Connection, headers: &HeaderMap) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let stored = config_store::get(conn, "wakatime.api_key")
+        .map_err(|e| waka_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    authorize(stored.as_deref(), headers)
+}
+
+fn authorize(stored: Option<&str>, headers: &HeaderMap) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let Some(stored) = stored.filter(|k| !k.is_empty()) else {
+        return Err(waka_error(
+            StatusCode::FORBIDDEN,
+            "No API key configured; run `timely config set wakatime.api_key <key>`".into(),
+        ));
+    };
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            if let Some(encoded) = v.strip_prefix("Basic ") {
+                let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+                let decoded = String::from_utf8(decoded).ok()?;
+                // Some clients send "key:" (empty password)
+                Some(decoded.trim_end_matches(':').to_string())
+            } else {
+                v.strip_prefix("Bearer ").map(|k| k.trim().to_string())
+            }
+        });
+
+    match provided {
+        Some(key) if key == stored => Ok(()),
+        _ => Err(waka_error(StatusCode::UNAUTHORIZED, "Invalid or missing API key".into())),
+    }
+}
+
+fn heartbeat_json(hb: &WakaHeartbeat, id: Option<i64>) -> serde_json::Value {
+    serde_json::json!({
+        "id": id.map(|id| id.to_string()).unwrap_or_default(),
+        "entity": hb.entity,
+        "type": hb.entity_type.as_deref().unwrap_or("file"),
+        "time": hb.time,
+    })
+}
+
+// --- Handlers ---
+
+pub async fn post_heartbeat(
+    headers: HeaderMap,
+    Json(body): Json<WakaHeartbeat>,
+) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db()
+            .map_err(|e| waka_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        check_api_key(&conn, &headers)?;
+        let ids = record_heartbeats(&conn, std::slice::from_ref(&body), user_agent.as_deref())
+            .map_err(|e| waka_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+        Ok((
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "data": heartbeat_json(&body, ids[0]) })),
+        ))
+    })
+    .await
+    .map_err(|e| waka_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+pub async fn post_heartbeats_bulk(
+    headers: HeaderMap,
+    Json(body): Json<Vec<WakaHeartbeat>>,
+) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    tokio::task::spawn_blocking(move || {
+        let conn = db::open_default_db()
+            .map_err(|e| waka_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        check_api_key(&conn, &headers)?;
+        let ids = record_heartbeats(&conn, &body, user_agent.as_deref())
+            .map_err(|e| waka_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+
+        // Same shape as the WakaTime API: one [body, status] pair per heartbeat
+        let responses: Vec<serde_json::Value> = body
+            .iter()
+            .zip(ids)
+            .map(|(hb, id)| serde_json::json!([{ "data": heartbeat_json(hb, id) }, 201]))
+            .collect();
+        Ok((StatusCode::CREATED, Json(serde_json::json!({ "responses": responses }))))
+    })
+    .await
+    .map_err(|e| waka_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_from_user_agent() {
+        assert_eq!(
+            editor_from_user_agent("wakatime/v1.73.1 (linux-6.5.0) go1.21.1 vscode/1.85.1 vscode-wakatime/24.4.0"),
+            "Code"
+        );
+        assert_eq!(
+            editor_from_user_agent("wakatime/v1.73.1 (darwin-23.1.0) go1.21.1 neovim/0.9.4 vim-wakatime/11.1.1"),
+            "Neovim"
+        );
+        assert_eq!(editor_from_user_agent("wakatime/v1.73.1 kakoune-wakatime/1.0"), "Kakoune");
+        assert_eq!(editor_from_user_agent("curl/8.0"), "WakaTime");
+    }
+
+    #[test]
+    fn test_api_key_required() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(authorize(None, &headers).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(authorize(Some(""), &headers).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(authorize(Some("s3cret"), &headers).unwrap_err().0, StatusCode::UNAUTHORIZED);
+
+        // base64("s3cret:")
+        headers.insert(header::AUTHORIZATION, "Basic czNjcmV0Og==".parse().unwrap());
+        assert!(authorize(Some("s3cret"), &headers).is_ok());
+        headers.insert(header::AUTHORIZATION, "Bearer other".parse().unwrap());
+        assert_eq!(authorize(Some("s3cret"), &headers).unwrap_err().0, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_snapshot_from_file_heartbeat() {
+        let hb = WakaHeartbeat {
+            entity: "/home/me/src/timely/src/main.rs".into(),
+            entity_type: Some("file".into()),
+            time: 1_700_000_000.5,
+            project: Some("timely".into()),
+            branch: Some("main".into()),
+            language: Some("Rust".into()),
+            user_agent: None,
+        };
+        let snapshot = snapshot_from_heartbeat("Code", &hb);
+        assert_eq!(snapshot.file.as_deref(), Some("/home/me/src/timely/src/main.rs"));
+        assert_eq!(snapshot.project.as_deref(), Some("timely"));
+        assert_eq!(snapshot.language.as_deref(), Some("Rust"));
+        assert_eq!(snapshot.branch.as_deref(), Some("main"));
+        assert!(snapshot.url_domain.is_none());
+    }
+}
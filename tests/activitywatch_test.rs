@@ -0,0 +1,122 @@
+use chrono::{Duration, TimeZone, Utc};
+use tempfile::NamedTempFile;
+use timely::db;
+use timely::db::buckets;
+use timely::db::categories as db_categories;
+use timely::db::events;
+use timely::web::activitywatch::{record_heartbeat, AwEvent};
+
+fn setup_db() -> rusqlite::Connection {
+    let tmp = NamedTempFile::new().unwrap();
+    let conn = db::open_db(tmp.path()).unwrap();
+    db_categories::seed_builtin_categories(&conn).unwrap();
+    conn
+}
+
+fn heartbeat(offset_secs: i64, data: serde_json::Value) -> AwEvent {
+    AwEvent {
+        id: None,
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap() + Duration::seconds(offset_secs),
+        duration: 0.0,
+        data,
+    }
+}
+
+fn device_events(conn: &rusqlite::Connection, bucket_id: &str) -> Vec<timely::types::Event> {
+    let bucket = buckets::get_bucket(conn, bucket_id).unwrap().unwrap();
+    let from = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+    let to = from + Duration::days(1);
+    let mut list: Vec<_> = events::query_events(conn, &from, &to, None)
+        .unwrap()
+        .into_iter()
+        .filter(|e| e.device_id == bucket.device_id)
+        .collect();
+    list.sort_by_key(|e| e.id);
+    list
+}
+
+#[test]
+fn test_bucket_gets_own_device() {
+    let conn = setup_db();
+    assert!(buckets::create_bucket(&conn, "aw-watcher-window_box", "currentwindow", "aw-watcher-window", "box").unwrap());
+    assert!(!buckets::create_bucket(&conn, "aw-watcher-window_box", "currentwindow", "aw-watcher-window", "box").unwrap());
+
+    let devices = db::devices::list_devices(&conn).unwrap();
+    assert!(devices.iter().any(|d| d.name == "aw-watcher-window_box" && d.platform == buckets::BUCKET_PLATFORM));
+}
+
+#[test]
+fn test_heartbeats_merge_within_pulsetime() {
+    let conn = setup_db();
+    buckets::create_bucket(&conn, "aw-watcher-window_box", "currentwindow", "aw-watcher-window", "box").unwrap();
+    let code = serde_json::json!({"app": "Code", "title": "main.rs"});
+
+    record_heartbeat(&conn, "aw-watcher-window_box", &heartbeat(0, code.clone()), 10.0).unwrap();
+    let merged = record_heartbeat(&conn, "aw-watcher-window_box", &heartbeat(8, code.clone()), 10.0)
+        .unwrap()
+        .unwrap();
+    assert_eq!(merged.duration, 8.0);
+
+    // Gap larger than pulsetime starts a new event
+    record_heartbeat(&conn, "aw-watcher-window_box", &heartbeat(60, code), 10.0).unwrap();
+    // Different data starts a new event
+    record_heartbeat(
+        &conn,
+        "aw-watcher-window_box",
+        &heartbeat(61, serde_json::json!({"app": "Firefox", "title": "GitHub"})),
+        10.0,
+    )
+    .unwrap();
+
+    let list = device_events(&conn, "aw-watcher-window_box");
+    let summary: Vec<(&str, f64)> = list.iter().map(|e| (e.app.as_str(), e.duration)).collect();
+    assert_eq!(summary, vec![("Code", 8.0), ("Code", 0.0), ("Firefox", 0.0)]);
+    assert!(list[0].category_name.is_some());
+}
+
+#[test]
+fn test_web_bucket_records_url_and_afk_status() {
+    let conn = setup_db();
+    buckets::create_bucket(&conn, "aw-watcher-web-firefox_box", "web.tab.current", "aw-client-web", "box").unwrap();
+    buckets::create_bucket(&conn, "aw-watcher-afk_box", "afkstatus", "aw-watcher-afk", "box").unwrap();
+
+    let tab = serde_json::json!({"url": "https://github.com/rust-lang/rust", "title": "rust", "audible": false});
+    record_heartbeat(&conn, "aw-watcher-web-firefox_box", &heartbeat(0, tab.clone()), 30.0).unwrap();
+    record_heartbeat(&conn, "aw-watcher-afk_box", &heartbeat(5, serde_json::json!({"status": "afk"})), 30.0).unwrap();
+    record_heartbeat(&conn, "aw-watcher-web-firefox_box", &heartbeat(10, tab), 30.0).unwrap();
+
+    let list = device_events(&conn, "aw-watcher-web-firefox_box");
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].app, "Firefox");
+    assert_eq!(list[0].url_domain.as_deref(), Some("github.com"));
+    assert!(!list[0].is_afk);
+    assert!(list[1].is_afk);
+
+    // The AFK bucket itself records no events
+    assert!(device_events(&conn, "aw-watcher-afk_box").is_empty());
+}
+
+#[test]
+fn test_unknown_bucket() {
+    let conn = setup_db();
+    let hb = heartbeat(0, serde_json::json!({"app": "Code"}));
+    assert!(record_heartbeat(&conn, "missing", &hb, 10.0).unwrap().is_none());
+}
+
+#[test]
+fn test_excluded_and_incognito_tabs_are_dropped() {
+    let conn = setup_db();
+    db::config_store::set(&conn, "exclude.domains", "bank.example").unwrap();
+    buckets::create_bucket(&conn, "aw-watcher-web-firefox_box", "web.tab.current", "aw-client-web", "box").unwrap();
+
+    let bank = serde_json::json!({"url": "https://login.bank.example/", "title": "Login", "audible": false});
+    let private = serde_json::json!({"url": "https://example.org/", "title": "Search", "incognito": true});
+    let docs = serde_json::json!({"url": "https://docs.rs/", "title": "docs.rs", "audible": false});
+    record_heartbeat(&conn, "aw-watcher-web-firefox_box", &heartbeat(0, bank), 30.0).unwrap();
+    record_heartbeat(&conn, "aw-watcher-web-firefox_box", &heartbeat(5, private), 30.0).unwrap();
+    record_heartbeat(&conn, "aw-watcher-web-firefox_box", &heartbeat(10, docs), 30.0).unwrap();
+
+    let list = device_events(&conn, "aw-watcher-web-firefox_box");
+    let domains: Vec<_> = list.iter().map(|e| e.url_domain.as_deref()).collect();
+    assert_eq!(domains, vec![Some("docs.rs")]);
+}
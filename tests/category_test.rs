@@ -14,7 +14,8 @@ fn setup_db() -> rusqlite::Connection {
 #[test]
 fn test_classify_ide() {
     let conn = setup_db();
-    let rules = db_categories::list_rules(&conn).unwrap();
+    // "Code" is classified through its builtin alias "Visual Studio Code"
+    let rules = categories::rule_set(&conn).unwrap();
 
     let snapshot = WatcherSnapshot {
         app: "Code".to_string(),
@@ -22,9 +23,10 @@ fn test_classify_ide() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
-    let cat_id = categories::classify(&snapshot, &rules);
+    let cat_id = rules.classify(&snapshot);
     assert!(cat_id.is_some());
 
     let cat = db_categories::get_category_by_id(&conn, cat_id.unwrap()).unwrap().unwrap();
@@ -43,6 +45,7 @@ fn test_classify_terminal() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -63,6 +66,7 @@ fn test_classify_browser_youtube() {
         url: Some("https://www.youtube.com/watch?v=123".to_string()),
         url_domain: Some("www.youtube.com".to_string()),
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -83,6 +87,7 @@ fn test_classify_browser_github() {
         url: Some("https://github.com/user/repo/pull/42".to_string()),
         url_domain: Some("github.com".to_string()),
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -103,6 +108,7 @@ fn test_classify_unknown_app_returns_none() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -125,6 +131,7 @@ fn test_user_rule_overrides_builtin() {
         url: Some("https://github.com/org/repo/pull/1".to_string()),
         url_domain: Some("github.com".to_string()),
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules).unwrap();
@@ -145,6 +152,7 @@ fn test_classify_claude_code() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -166,6 +174,7 @@ fn test_classify_codex_cli() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -186,6 +195,7 @@ fn test_classify_aider() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
     let cat_id = categories::classify(&snapshot, &rules);
@@ -198,7 +208,7 @@ fn test_classify_aider() {
 #[test]
 fn test_case_insensitive_matching() {
     let conn = setup_db();
-    let rules = db_categories::list_rules(&conn).unwrap();
+    let rules = categories::rule_set(&conn).unwrap();
 
     let snapshot = WatcherSnapshot {
         app: "code".to_string(), // lowercase
@@ -206,8 +216,79 @@ fn test_case_insensitive_matching() {
         url: None,
         url_domain: None,
         is_afk: false,
+        ..Default::default()
     };
 
-    let cat_id = categories::classify(&snapshot, &rules);
+    let cat_id = rules.classify(&snapshot);
     assert!(cat_id.is_some(), "Matching should be case-insensitive");
 }
+
+#[test]
+fn test_reclassify_history_with_compiled_rules() {
+    let conn = setup_db();
+    conn.execute(
+        "INSERT INTO devices (id, name, platform, last_sync) VALUES ('dev', 'test', 'macos', datetime('now'))",
+        [],
+    )
+    .unwrap();
+    let insert = |app: &str, title: &str, category_id: Option<i64>| {
+        timely::db::events::insert_event(
+            &conn,
+            "dev",
+            &chrono::Utc::now(),
+            5.0,
+            app,
+            title,
+            None,
+            None,
+            category_id,
+            false,
+        )
+        .unwrap()
+    };
+    let coding = db_categories::get_category_by_name(&conn, "work/coding").unwrap().unwrap();
+    for i in 0..2000 {
+        insert("Zed", &format!("file{}.rs", i % 50), None);
+    }
+    let code = insert("Code", "main.rs", Some(coding.id));
+
+    db_categories::insert_rule(&conn, coding.id, "app", "zed", false, 200).unwrap();
+    let rules = categories::rule_set(&conn).unwrap();
+
+    // Only the events the new rule covers are touched
+    let covered = categories::RuleSet::for_pattern("app", "zed");
+    assert_eq!(categories::reclassify_events(&conn, &rules, Some(&covered)).unwrap(), 2000);
+    let zed_coding: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM events WHERE app = 'Zed' AND category_id = ?1",
+            [coding.id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(zed_coding, 2000);
+
+    // Nothing left to change on a full pass
+    assert_eq!(categories::reclassify_events(&conn, &rules, None).unwrap(), 0);
+    let code_category: Option<i64> = conn
+        .query_row("SELECT category_id FROM events WHERE id = ?1", [code], |row| row.get(0))
+        .unwrap();
+    assert_eq!(code_category, Some(coding.id));
+}
+
+#[test]
+fn test_rules_follow_app_aliases() {
+    let conn = setup_db();
+    let design = db_categories::insert_category(&conn, "work/design", None, 1.5).unwrap();
+    db_categories::insert_rule(&conn, design, "app", "Figma", false, 200).unwrap();
+
+    let beta = WatcherSnapshot {
+        app: "Figma Beta".to_string(),
+        title: "Landing page".to_string(),
+        ..Default::default()
+    };
+    assert_ne!(categories::rule_set(&conn).unwrap().classify(&beta), Some(design));
+
+    // Adding an alias invalidates the cached rules
+    timely::db::apps::set_alias(&conn, "Figma Beta", "Figma").unwrap();
+    assert_eq!(categories::rule_set(&conn).unwrap().classify(&beta), Some(design));
+}
@@ -0,0 +1,229 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tempfile::NamedTempFile;
+use timely::daemon::{control, run_loop, LoopOptions};
+use timely::db;
+use timely::db::categories as db_categories;
+use timely::db::{config_store, coverage, devices, events};
+use timely::error::Result;
+use timely::types::WatcherSnapshot;
+use timely::watchers::{SnapshotPatch, Watcher, WatcherRegistry};
+
+/// Clears the running flag after a fixed number of ticks.
+struct StopAfter {
+    ticks: usize,
+    running: Arc<AtomicBool>,
+}
+
+impl Watcher for StopAfter {
+    fn name(&self) -> &str {
+        "stop-after"
+    }
+
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        self.ticks = self.ticks.saturating_sub(1);
+        if self.ticks == 0 {
+            self.running.store(false, Ordering::SeqCst);
+        }
+        Ok(SnapshotPatch::default())
+    }
+}
+
+#[test]
+fn test_daemon_loop_with_replay_watcher() {
+    let tmp = NamedTempFile::new().unwrap();
+    let conn = db::open_db(tmp.path()).unwrap();
+    db_categories::seed_builtin_categories(&conn).unwrap();
+    let device = devices::get_or_create_device(&conn).unwrap();
+
+    let mut replay = NamedTempFile::new().unwrap();
+    write!(
+        replay,
+        r#"[
+            {{"app": "Code", "title": "main.rs — timely"}},
+            {{"app": "Code", "title": "main.rs — timely"}},
+            {{"app": "Firefox", "title": "GitHub", "url": "https://github.com/", "url_domain": "github.com"}},
+            {{"app": "Firefox", "title": "GitHub", "url_domain": "github.com", "is_afk": true}}
+        ]"#
+    )
+    .unwrap();
+
+    config_store::set(&conn, "watchers.enabled", "replay").unwrap();
+    config_store::set(&conn, "watchers.replay.file", replay.path().to_str().unwrap()).unwrap();
+    config_store::set(&conn, "watchers.replay.interval_secs", "0.001").unwrap();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let mut registry = WatcherRegistry::from_config(&conn, 180).unwrap();
+    assert_eq!(registry.names(), vec!["replay"]);
+    registry.register_with_interval(
+        Box::new(StopAfter {
+            ticks: 5,
+            running: running.clone(),
+        }),
+        Duration::ZERO,
+    );
+
+    let options = LoopOptions {
+        tick: Duration::from_millis(5),
+        flush_interval: Duration::from_secs(30),
+        sync_interval: None,
+        logind: false,
+        sd_notify: false,
+        control_socket: None,
+    };
+    run_loop(&conn, &device, &mut registry, &running, &options).unwrap();
+
+    let from = chrono::Utc::now() - chrono::Duration::hours(1);
+    let to = chrono::Utc::now() + chrono::Duration::hours(1);
+    let mut recorded = events::query_events(&conn, &from, &to, None).unwrap();
+    recorded.sort_by_key(|e| e.id);
+    let apps: Vec<(&str, bool)> = recorded.iter().map(|e| (e.app.as_str(), e.is_afk)).collect();
+    assert_eq!(apps, vec![("Code", false), ("Firefox", false), ("Firefox", true)]);
+    assert_eq!(recorded[1].url_domain.as_deref(), Some("github.com"));
+    assert!(recorded[0].category_name.is_some());
+
+    // The run is recorded as a closed coverage interval
+    let intervals = coverage::query_intervals(&conn, &from, &to).unwrap();
+    assert_eq!(intervals.len(), 1);
+    assert!(intervals[0].stopped);
+    assert_eq!(intervals[0].device_id, device.id);
+}
+
+/// Always reports the same focused window.
+struct Fixed;
+
+impl Watcher for Fixed {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    fn enrich(&mut self, _snapshot: &WatcherSnapshot) -> Result<SnapshotPatch> {
+        Ok(SnapshotPatch {
+            app: Some("Code".into()),
+            title: Some("main.rs".into()),
+            ..Default::default()
+        })
+    }
+}
+
+#[test]
+fn test_daemon_control_socket() {
+    let tmp = NamedTempFile::new().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("control.sock");
+
+    let running = Arc::new(AtomicBool::new(true));
+    let daemon = {
+        let db_path = tmp.path().to_path_buf();
+        let socket = socket.clone();
+        let running = running.clone();
+        std::thread::spawn(move || {
+            let conn = db::open_db(&db_path).unwrap();
+            db_categories::seed_builtin_categories(&conn).unwrap();
+            let device = devices::get_or_create_device(&conn).unwrap();
+            let mut registry = WatcherRegistry::new();
+            registry.register_with_interval(Box::new(Fixed), Duration::ZERO);
+            let options = LoopOptions {
+                tick: Duration::from_millis(10),
+                flush_interval: Duration::from_secs(30),
+                sync_interval: None,
+                logind: false,
+                sd_notify: false,
+                control_socket: Some(socket),
+            };
+            run_loop(&conn, &device, &mut registry, &running, &options).unwrap();
+        })
+    };
+
+    // Wait for the first heartbeat to land
+    let mut live = None;
+    for _ in 0..200 {
+        if let Ok(snapshot) = control::snapshot(&socket) {
+            if snapshot.current.is_some() {
+                live = Some(snapshot);
+                break;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let live = live.expect("daemon never reported a current event");
+    assert_eq!(live.snapshot.unwrap().app, "Code");
+    assert_eq!(live.current.unwrap().title, "main.rs");
+
+    let status = control::status(&socket).unwrap();
+    assert!(status.responding);
+    assert_eq!(status.paused, Some(false));
+    assert_eq!(status.watchers.len(), 1);
+    assert_eq!(status.watchers[0].name, "fixed");
+    assert!(status.watchers[0].error.is_none());
+
+    control::request(&socket, control::Request::Pause { until: None }).unwrap();
+    assert_eq!(control::status(&socket).unwrap().paused, Some(true));
+    std::thread::sleep(Duration::from_millis(100));
+    control::request(&socket, control::Request::Resume).unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+
+    running.store(false, Ordering::SeqCst);
+    daemon.join().unwrap();
+    assert!(!socket.exists());
+
+    // The pause is recorded as such, and splits the event around it
+    let conn = db::open_db(tmp.path()).unwrap();
+    let from = chrono::Utc::now() - chrono::Duration::hours(1);
+    let to = chrono::Utc::now() + chrono::Duration::hours(1);
+    assert_eq!(coverage::query_pauses(&conn, &from, &to).unwrap().len(), 1);
+    assert_eq!(events::query_events(&conn, &from, &to, None).unwrap().len(), 2);
+}
+
+#[test]
+fn test_daemon_never_writes_excluded_activity() {
+    let tmp = NamedTempFile::new().unwrap();
+    let conn = db::open_db(tmp.path()).unwrap();
+    db_categories::seed_builtin_categories(&conn).unwrap();
+    let device = devices::get_or_create_device(&conn).unwrap();
+
+    let mut replay = NamedTempFile::new().unwrap();
+    write!(
+        replay,
+        r#"[
+            {{"app": "Code", "title": "main.rs"}},
+            {{"app": "Firefox", "title": "Statement", "url_domain": "bank.example"}},
+            {{"app": "Firefox", "title": "Inbox - Mozilla Firefox Private Browsing"}},
+            {{"app": "Code", "title": "main.rs"}}
+        ]"#
+    )
+    .unwrap();
+    config_store::set(&conn, "watchers.enabled", "replay").unwrap();
+    config_store::set(&conn, "watchers.replay.file", replay.path().to_str().unwrap()).unwrap();
+    config_store::set(&conn, "watchers.replay.interval_secs", "0.001").unwrap();
+    config_store::set(&conn, "exclude.domains", "bank.example").unwrap();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let mut registry = WatcherRegistry::from_config(&conn, 180).unwrap();
+    registry.register_with_interval(
+        Box::new(StopAfter {
+            ticks: 5,
+            running: running.clone(),
+        }),
+        Duration::ZERO,
+    );
+    let options = LoopOptions {
+        tick: Duration::from_millis(5),
+        flush_interval: Duration::from_secs(30),
+        sync_interval: None,
+        logind: false,
+        sd_notify: false,
+        control_socket: None,
+    };
+    run_loop(&conn, &device, &mut registry, &running, &options).unwrap();
+
+    let from = chrono::Utc::now() - chrono::Duration::hours(1);
+    let to = chrono::Utc::now() + chrono::Duration::hours(1);
+    let recorded = events::query_events(&conn, &from, &to, None).unwrap();
+    // Both Code stretches are kept apart rather than merged across the excluded time
+    assert_eq!(recorded.len(), 2);
+    assert!(recorded.iter().all(|e| e.app == "Code"));
+}
@@ -0,0 +1,40 @@
+#!/usr/bin/env python3
+"""Minimal MPRIS player for testing the media watcher (needs python3-dbus and PyGObject).
+
+    dbus-run-session -- sh -c 'python3 tests/fixtures/mock_mpris.py & sleep 1; cargo test media -- --ignored'
+"""
+import sys
+
+import dbus
+import dbus.service
+from dbus.mainloop.glib import DBusGMainLoop
+from gi.repository import GLib
+
+STATUS = sys.argv[1] if len(sys.argv) > 1 else "Playing"
+
+
+class Player(dbus.service.Object):
+    @dbus.service.method(dbus.PROPERTIES_IFACE, in_signature="ss", out_signature="v")
+    def Get(self, interface, prop):
+        return self.GetAll(interface)[prop]
+
+    @dbus.service.method(dbus.PROPERTIES_IFACE, in_signature="s", out_signature="a{sv}")
+    def GetAll(self, interface):
+        if interface == "org.mpris.MediaPlayer2":
+            return {"Identity": "Mock Player"}
+        metadata = dbus.Dictionary(
+            {
+                "xesam:title": "Song",
+                "xesam:artist": dbus.Array(["Band"], signature="s"),
+                "mpris:length": dbus.Int64(180_000_000),
+            },
+            signature="sv",
+        )
+        return {"PlaybackStatus": STATUS, "Metadata": metadata}
+
+
+DBusGMainLoop(set_as_default=True)
+bus = dbus.SessionBus()
+name = dbus.service.BusName("org.mpris.MediaPlayer2.mock", bus)
+Player(bus, "/org/mpris/MediaPlayer2")
+GLib.MainLoop().run()
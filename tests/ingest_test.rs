@@ -0,0 +1,62 @@
+use chrono::{TimeZone, Utc};
+use tempfile::NamedTempFile;
+use timely::db;
+use timely::db::categories as db_categories;
+use timely::db::{devices, events};
+use timely::ingest::{ingest_events, parse_ndjson, INGEST_PLATFORM};
+use timely::types::EventSource;
+
+fn setup_db() -> rusqlite::Connection {
+    let tmp = NamedTempFile::new().unwrap();
+    let conn = db::open_db(tmp.path()).unwrap();
+    db_categories::seed_builtin_categories(&conn).unwrap();
+    conn
+}
+
+fn all_events(conn: &rusqlite::Connection) -> Vec<timely::types::Event> {
+    let from = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let to = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    let mut list = events::query_events(conn, &from, &to, None).unwrap();
+    list.sort_by_key(|e| e.id);
+    list
+}
+
+#[test]
+fn test_ingest_heartbeats_merge_per_device() {
+    let conn = setup_db();
+    let input = r#"
+{"source": "call-logger", "timestamp": "2025-03-01T09:00:00Z", "app": "Phone", "title": "Call with Ana"}
+{"source": "call-logger", "timestamp": "2025-03-01T09:00:30Z", "app": "Phone", "title": "Call with Ana"}
+{"source": "ci-bot", "device": "ci", "timestamp": "2025-03-01T09:00:10Z", "duration": 300, "app": "GitHub Actions", "url": "https://github.com/acme/api/actions"}
+{"source": "call-logger", "timestamp": "2025-03-01T09:20:00Z", "app": "Phone", "title": "Call with Ana"}
+"#;
+    let result = ingest_events(&conn, &parse_ndjson(input).unwrap()).unwrap();
+    assert_eq!(result.accepted, 4);
+    assert_eq!(result.event_ids[0], result.event_ids[1]);
+    assert_ne!(result.event_ids[1], result.event_ids[3]);
+
+    let list = all_events(&conn);
+    let summary: Vec<(&str, f64)> = list.iter().map(|e| (e.app.as_str(), e.duration)).collect();
+    assert_eq!(summary, vec![("Phone", 30.0), ("GitHub Actions", 300.0), ("Phone", 0.0)]);
+    assert_eq!(list[1].url_domain.as_deref(), Some("github.com"));
+    assert!(list.iter().all(|e| e.category_id.is_some()));
+    assert!(list.iter().all(|e| e.source == EventSource::Ingest));
+
+    let names: Vec<String> = devices::list_devices(&conn)
+        .unwrap()
+        .into_iter()
+        .filter(|d| d.platform == INGEST_PLATFORM)
+        .map(|d| d.name)
+        .collect();
+    assert_eq!(names, vec!["call-logger", "ci"]);
+}
+
+#[test]
+fn test_ingest_rejects_batch_without_source() {
+    let conn = setup_db();
+    let input = r#"{"source": "ci-bot", "app": "GitHub Actions"}
+{"app": "Phone"}"#;
+    let err = ingest_events(&conn, &parse_ndjson(input).unwrap()).unwrap_err();
+    assert!(err.to_string().contains("event 2"));
+    assert!(all_events(&conn).is_empty());
+}
@@ -1,8 +1,9 @@
 use chrono::{Duration, Utc};
 use timely::db;
 use timely::db::categories as db_categories;
-use timely::db::events;
-use timely::query::{self, summary, timeline};
+use timely::db::{apps, coverage, events, media};
+use timely::query::{self, summary, timeline, SourceFilter};
+use timely::types::{EventSource, MediaPlayback};
 use timely::query::summary::GroupBy;
 use tempfile::NamedTempFile;
 
@@ -92,7 +93,7 @@ fn test_summary_by_category() {
     let from = Utc::now() - Duration::hours(4);
     let to = Utc::now();
 
-    let result = summary::build_summary(&conn, &from, &to, GroupBy::Category).unwrap();
+    let result = summary::build_summary(&conn, &from, &to, GroupBy::Category, false).unwrap();
 
     assert!(result.total_seconds > 0.0);
     assert!(!result.groups.is_empty());
@@ -112,22 +113,89 @@ fn test_summary_by_app() {
     let from = Utc::now() - Duration::hours(4);
     let to = Utc::now();
 
-    let result = summary::build_summary(&conn, &from, &to, GroupBy::App).unwrap();
+    let result = summary::build_summary(&conn, &from, &to, GroupBy::App, false).unwrap();
 
-    let code_group = result.groups.iter().find(|g| g.label == "Code");
+    // "Code" is grouped under its builtin alias
+    let code_group = result.groups.iter().find(|g| g.label == "Visual Studio Code");
     assert!(code_group.is_some());
 
     let slack_group = result.groups.iter().find(|g| g.label == "Slack");
     assert!(slack_group.is_some());
 }
 
+#[test]
+fn test_summary_by_app_groups_aliases() {
+    let conn = setup_db_with_events();
+    let coding_cat = db_categories::get_category_by_name(&conn, "work/coding").unwrap().unwrap();
+    events::insert_event(&conn, "test-device", &(Utc::now() - Duration::minutes(30)), 600.0,
+        "Visual Studio Code", "mod.rs", None, None, Some(coding_cat.id), false).unwrap();
+    apps::set_alias(&conn, "Code", "VS Code").unwrap();
+    apps::set_alias(&conn, "Visual Studio Code", "VS Code").unwrap();
+
+    let from = Utc::now() - Duration::hours(4);
+    let to = Utc::now();
+    let result = summary::build_summary(&conn, &from, &to, GroupBy::App, false).unwrap();
+    let vscode = result.groups.iter().find(|g| g.label == "VS Code").unwrap();
+    assert_eq!(vscode.seconds, 3600.0 + 1200.0 + 600.0);
+    assert!(!result.groups.iter().any(|g| g.label == "Code" || g.label == "Visual Studio Code"));
+
+    let breakdown = query::apps::build_apps(&conn, &from.to_rfc3339(), &to.to_rfc3339(), 10).unwrap();
+    assert!(breakdown.iter().any(|a| a.app == "VS Code"));
+}
+
+#[test]
+fn test_source_filters() {
+    let conn = setup_db_with_events();
+    let imported = events::insert_event(&conn, "test-device", &(Utc::now() - Duration::minutes(20)), 900.0,
+        "Code", "old.rs", None, None, None, false).unwrap();
+    events::set_event_source(&conn, imported, EventSource::Import).unwrap();
+
+    let from = Utc::now() - Duration::hours(4);
+    let to = Utc::now();
+    let code_seconds = |sources: &SourceFilter| {
+        let result = summary::build_summary_filtered(&conn, &from, &to, GroupBy::App, false, sources).unwrap();
+        result.groups.iter().find(|g| g.label == "Visual Studio Code").map(|g| g.seconds)
+    };
+    assert_eq!(code_seconds(&SourceFilter::default()), Some(3600.0 + 1200.0 + 900.0));
+    let without_imports = SourceFilter { exclude: vec![EventSource::Import], ..Default::default() };
+    assert_eq!(code_seconds(&without_imports), Some(3600.0 + 1200.0));
+    let only_imports = SourceFilter { only: vec![EventSource::Import], ..Default::default() };
+    assert_eq!(code_seconds(&only_imports), Some(900.0));
+
+    let result = timeline::build_timeline_filtered(&conn, &from, &to, None, &only_imports).unwrap();
+    let sources: Vec<_> = result.entries.iter().filter(|e| !e.untracked && !e.paused).map(|e| e.source).collect();
+    assert_eq!(sources, vec![Some(EventSource::Import)]);
+}
+
+#[test]
+fn test_media_summary_splits_by_overlapping_focus() {
+    let conn = setup_db_with_events();
+    let now = Utc::now();
+    let song = MediaPlayback {
+        player: "Spotify".to_string(),
+        title: None,
+        artist: None,
+    };
+    // Across the end of the first coding stretch into Slack, then during the second
+    media::insert_media_event(&conn, "test-device", &(now - Duration::minutes(130)), 1200.0, &song).unwrap();
+    media::insert_media_event(&conn, "test-device", &(now - Duration::minutes(55)), 600.0, &song).unwrap();
+
+    let media = summary::build_media_summary(&conn, &(now - Duration::hours(4)), &now).unwrap();
+    assert_eq!(media.len(), 1);
+    assert_eq!(media[0].seconds, 1800.0);
+    let alongside = |category: &str| media[0].alongside.iter().find(|a| a.category == category).unwrap().seconds;
+    assert!((alongside("work/coding") - 1200.0).abs() < 1.0);
+    assert!((alongside("communication/chat") - 600.0).abs() < 1.0);
+    assert_eq!(media[0].alongside.len(), 2);
+}
+
 #[test]
 fn test_summary_percentages_sum_to_100() {
     let conn = setup_db_with_events();
     let from = Utc::now() - Duration::hours(4);
     let to = Utc::now();
 
-    let result = summary::build_summary(&conn, &from, &to, GroupBy::Category).unwrap();
+    let result = summary::build_summary(&conn, &from, &to, GroupBy::Category, false).unwrap();
 
     let total_pct: f64 = result.groups.iter().map(|g| g.percentage).sum();
     assert!((total_pct - 100.0).abs() < 1.0, "Percentages should sum to ~100%");
@@ -141,7 +209,7 @@ fn test_summary_no_data() {
     let from = Utc::now() - Duration::hours(1);
     let to = Utc::now();
 
-    let result = summary::build_summary(&conn, &from, &to, GroupBy::Category);
+    let result = summary::build_summary(&conn, &from, &to, GroupBy::Category, false);
     assert!(result.is_err());
 }
 
@@ -181,3 +249,71 @@ fn test_timeline_no_data() {
     let result = timeline::build_timeline(&conn, &from, &to, None);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_untracked_gaps_in_timeline_and_summary() {
+    let conn = setup_db_with_events();
+    let now = Utc::now();
+    let device_id = "test-device";
+
+    // Daemon ran 4h..2h ago, crashed, and was restarted 30m ago
+    let first = coverage::start_interval(&conn, device_id, &(now - Duration::hours(4))).unwrap();
+    coverage::touch_interval(&conn, first, &(now - Duration::hours(2))).unwrap();
+    let second = coverage::start_interval(&conn, device_id, &(now - Duration::minutes(30))).unwrap();
+    coverage::touch_interval(&conn, second, &now).unwrap();
+
+    let from = now - Duration::hours(5);
+    let result = timeline::build_timeline(&conn, &from, &now, None).unwrap();
+    let untracked: Vec<_> = result.entries.iter().filter(|e| e.untracked).collect();
+    assert_eq!(untracked.len(), 1);
+    assert!((untracked[0].duration_seconds - 5400.0).abs() < 1.0);
+    for i in 1..result.entries.len() {
+        assert!(result.entries[i].timestamp >= result.entries[i - 1].timestamp);
+    }
+
+    // Time before coverage began counts as neither tracked nor untracked
+    let result = summary::build_summary(&conn, &from, &now, GroupBy::Category, false).unwrap();
+    assert!((result.untracked_seconds - 5400.0).abs() < 1.0);
+    assert!((result.tracked_seconds - 9000.0).abs() < 1.0);
+}
+
+#[test]
+fn test_summary_reports_untracked_range_without_events() {
+    let tmp = NamedTempFile::new().unwrap();
+    let conn = db::open_db(tmp.path()).unwrap();
+    conn.execute(
+        "INSERT INTO devices (id, name, platform, last_sync) VALUES ('d', 'test', 'linux', datetime('now'))",
+        [],
+    ).unwrap();
+    let now = Utc::now();
+    let id = coverage::start_interval(&conn, "d", &(now - Duration::hours(3))).unwrap();
+    coverage::stop_interval(&conn, id, &(now - Duration::hours(2))).unwrap();
+
+    let from = now - Duration::hours(1);
+    let result = summary::build_summary(&conn, &from, &now, GroupBy::Category, false).unwrap();
+    assert!(result.groups.is_empty());
+    assert!((result.untracked_seconds - 3600.0).abs() < 1.0);
+}
+
+#[test]
+fn test_paused_time_is_its_own_state() {
+    let conn = setup_db_with_events();
+    let now = Utc::now();
+    let device_id = "test-device";
+
+    let run = coverage::start_interval(&conn, device_id, &(now - Duration::hours(4))).unwrap();
+    coverage::touch_interval(&conn, run, &now).unwrap();
+    let pause = coverage::start_pause(&conn, device_id, &(now - Duration::minutes(30))).unwrap();
+    coverage::extend_pause(&conn, pause, &(now - Duration::minutes(10))).unwrap();
+
+    let from = now - Duration::hours(4);
+    let result = timeline::build_timeline(&conn, &from, &now, None).unwrap();
+    let paused: Vec<_> = result.entries.iter().filter(|e| e.paused).collect();
+    assert_eq!(paused.len(), 1);
+    assert!(!paused[0].is_afk && !paused[0].untracked);
+    assert!((paused[0].duration_seconds - 1200.0).abs() < 1.0);
+
+    let result = summary::build_summary(&conn, &from, &now, GroupBy::Category, false).unwrap();
+    assert!((result.paused_seconds - 1200.0).abs() < 1.0);
+    assert_eq!(result.untracked_seconds, 0.0);
+}
@@ -2,6 +2,7 @@ use timely::db;
 use timely::db::config_store;
 use timely::db::events;
 use timely::db::sync as db_sync;
+use timely::types::{Event, EventSource};
 use tempfile::NamedTempFile;
 
 fn setup_db() -> rusqlite::Connection {
@@ -34,25 +35,43 @@ fn insert_test_event(
     .unwrap();
 }
 
+fn remote_event(timestamp: &str, duration: f64, app: &str, title: &str) -> Event {
+    Event {
+        id: 0,
+        device_id: "device-a".into(),
+        timestamp: timestamp.parse().unwrap(),
+        duration,
+        app: app.into(),
+        title: title.into(),
+        raw_title: None,
+        app_id: None,
+        app_path: None,
+        url: None,
+        url_domain: None,
+        category_id: None,
+        category_name: None,
+        is_afk: false,
+        is_idle: false,
+        project: None,
+        file: None,
+        language: None,
+        branch: None,
+        command: None,
+        cwd: None,
+        exit_status: None,
+        source: EventSource::Watcher,
+    }
+}
+
+const T0: &str = "2025-01-15T10:00:00+00:00";
+
 // --- Event dedup tests ---
 
 #[test]
 fn test_upsert_remote_event_inserts_new() {
     let conn = setup_db();
 
-    let is_new = db_sync::upsert_remote_event(
-        &conn,
-        "device-a",
-        "2025-01-15T10:00:00+00:00",
-        30.0,
-        "Code",
-        "main.rs",
-        None,
-        None,
-        None,
-        false,
-    )
-    .unwrap();
+    let is_new = db_sync::upsert_remote_event(&conn, &remote_event(T0, 30.0, "Code", "main.rs")).unwrap();
 
     assert!(is_new, "Should insert new event");
 
@@ -60,6 +79,10 @@ fn test_upsert_remote_event_inserts_new() {
         .query_row("SELECT COUNT(*) FROM events", [], |row| row.get(0))
         .unwrap();
     assert_eq!(count, 1);
+    let source: String = conn
+        .query_row("SELECT source FROM events", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(source, "sync");
 }
 
 #[test]
@@ -67,35 +90,11 @@ fn test_upsert_remote_event_dedup_same_event() {
     let conn = setup_db();
 
     // Insert first time
-    let is_new1 = db_sync::upsert_remote_event(
-        &conn,
-        "device-a",
-        "2025-01-15T10:00:00+00:00",
-        30.0,
-        "Code",
-        "main.rs",
-        None,
-        None,
-        None,
-        false,
-    )
-    .unwrap();
+    let is_new1 = db_sync::upsert_remote_event(&conn, &remote_event(T0, 30.0, "Code", "main.rs")).unwrap();
     assert!(is_new1);
 
     // Insert same event again (same device, timestamp, app, title)
-    let is_new2 = db_sync::upsert_remote_event(
-        &conn,
-        "device-a",
-        "2025-01-15T10:00:00+00:00",
-        30.0,
-        "Code",
-        "main.rs",
-        None,
-        None,
-        None,
-        false,
-    )
-    .unwrap();
+    let is_new2 = db_sync::upsert_remote_event(&conn, &remote_event(T0, 30.0, "Code", "main.rs")).unwrap();
     assert!(!is_new2, "Duplicate should not be new");
 
     let count: i64 = conn
@@ -109,34 +108,10 @@ fn test_upsert_remote_event_takes_max_duration() {
     let conn = setup_db();
 
     // Insert with duration=30
-    db_sync::upsert_remote_event(
-        &conn,
-        "device-a",
-        "2025-01-15T10:00:00+00:00",
-        30.0,
-        "Code",
-        "main.rs",
-        None,
-        None,
-        None,
-        false,
-    )
-    .unwrap();
+    db_sync::upsert_remote_event(&conn, &remote_event(T0, 30.0, "Code", "main.rs")).unwrap();
 
     // Push same event with longer duration=60
-    db_sync::upsert_remote_event(
-        &conn,
-        "device-a",
-        "2025-01-15T10:00:00+00:00",
-        60.0,
-        "Code",
-        "main.rs",
-        None,
-        None,
-        None,
-        false,
-    )
-    .unwrap();
+    db_sync::upsert_remote_event(&conn, &remote_event(T0, 60.0, "Code", "main.rs")).unwrap();
 
     let duration: f64 = conn
         .query_row(
@@ -148,19 +123,7 @@ fn test_upsert_remote_event_takes_max_duration() {
     assert_eq!(duration, 60.0, "Should take MAX(duration)");
 
     // Push with shorter duration=20 — should NOT downgrade
-    db_sync::upsert_remote_event(
-        &conn,
-        "device-a",
-        "2025-01-15T10:00:00+00:00",
-        20.0,
-        "Code",
-        "main.rs",
-        None,
-        None,
-        None,
-        false,
-    )
-    .unwrap();
+    db_sync::upsert_remote_event(&conn, &remote_event(T0, 20.0, "Code", "main.rs")).unwrap();
 
     let duration2: f64 = conn
         .query_row(
@@ -172,6 +135,34 @@ fn test_upsert_remote_event_takes_max_duration() {
     assert_eq!(duration2, 60.0, "Should not downgrade duration");
 }
 
+#[test]
+fn test_upsert_remote_event_keeps_pushed_fields() {
+    let conn = setup_db();
+
+    let event = Event {
+        is_idle: true,
+        project: Some("timely".into()),
+        file: Some("src/main.rs".into()),
+        command: Some("cargo test".into()),
+        exit_status: Some(101),
+        raw_title: Some("main.rs (3) - timely".into()),
+        app_id: Some("code".into()),
+        ..remote_event(T0, 30.0, "Code", "main.rs - timely")
+    };
+    assert!(db_sync::upsert_remote_event(&conn, &event).unwrap());
+
+    let stored = events::get_last_event(&conn, "device-a").unwrap().unwrap();
+    assert!(stored.is_idle);
+    assert!(!stored.is_afk);
+    assert_eq!(stored.project.as_deref(), Some("timely"));
+    assert_eq!(stored.file.as_deref(), Some("src/main.rs"));
+    assert_eq!(stored.command.as_deref(), Some("cargo test"));
+    assert_eq!(stored.exit_status, Some(101));
+    assert_eq!(stored.raw_title.as_deref(), Some("main.rs (3) - timely"));
+    assert_eq!(stored.app_id.as_deref(), Some("code"));
+    assert_eq!(stored.source, EventSource::Sync);
+}
+
 // --- Device registration tests ---
 
 #[test]
@@ -336,19 +327,7 @@ fn test_full_sync_push_flow_db_level() {
     let mut accepted = 0;
     let mut duplicates = 0;
     for event in &events_to_push {
-        let is_new = db_sync::upsert_remote_event(
-            &conn,
-            &event.device_id,
-            &event.timestamp.to_rfc3339(),
-            event.duration,
-            &event.app,
-            &event.title,
-            event.url.as_deref(),
-            event.url_domain.as_deref(),
-            event.category_id,
-            event.is_afk,
-        )
-        .unwrap();
+        let is_new = db_sync::upsert_remote_event(&conn, event).unwrap();
 
         if is_new {
             accepted += 1;
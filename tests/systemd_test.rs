@@ -0,0 +1,82 @@
+#![cfg(target_os = "linux")]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use timely::daemon::systemd::{unit_file, Systemctl};
+
+/// A `systemctl` that logs its arguments and answers `show` with a canned state.
+fn stub_systemctl(dir: &Path) -> (PathBuf, PathBuf) {
+    let log = dir.join("calls.log");
+    let program = dir.join("systemctl");
+    std::fs::write(
+        &program,
+        format!(
+            "#!/bin/sh\n\
+             echo \"$@\" >> '{log}'\n\
+             case \"$2\" in\n\
+               show) printf 'ActiveState=active\\nSubState=running\\nMainPID=4242\\nNRestarts=2\\n' ;;\n\
+             esac\n",
+            log = log.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    (program, log)
+}
+
+fn calls(log: &Path) -> Vec<String> {
+    std::fs::read_to_string(log)
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn test_unit_file() {
+    let unit = unit_file(Path::new("/opt/timely 2/bin/timely"));
+    assert!(unit.contains("ExecStart=\"/opt/timely 2/bin/timely\" daemon run\n"));
+    assert!(unit.contains("Type=notify\n"));
+    assert!(unit.contains("Restart=on-failure\n"));
+    assert!(unit.contains("WatchdogSec=60\n"));
+    assert!(unit.contains("WantedBy=default.target\n"));
+}
+
+#[test]
+fn test_start_stop_status_drive_systemctl() {
+    let dir = tempfile::tempdir().unwrap();
+    let (program, log) = stub_systemctl(dir.path());
+    let systemctl = Systemctl::new(&program);
+    let unit_path = dir.path().join("timely.service");
+
+    systemctl.start(&unit_path, Path::new("/usr/bin/timely")).unwrap();
+    let unit = std::fs::read_to_string(&unit_path).unwrap();
+    assert_eq!(unit, unit_file(Path::new("/usr/bin/timely")));
+    let started = calls(&log);
+    assert_eq!(started.first().map(String::as_str), Some("--user daemon-reload"));
+    assert_eq!(started.last().map(String::as_str), Some("--user enable --now timely.service"));
+
+    let status = systemctl.status().unwrap();
+    assert_eq!(status.active_state, "active");
+    assert_eq!(status.sub_state, "running");
+    assert_eq!(status.main_pid, Some(4242));
+    assert_eq!(status.restarts, Some(2));
+
+    systemctl.stop().unwrap();
+    assert_eq!(calls(&log).last().map(String::as_str), Some("--user stop timely.service"));
+}
+
+#[test]
+fn test_systemctl_failure_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = Systemctl::new(dir.path().join("no-such-systemctl"));
+    assert!(missing.stop().is_err());
+
+    let program = dir.path().join("systemctl");
+    std::fs::write(&program, "#!/bin/sh\necho 'Failed to connect to bus' >&2\nexit 1\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let err = Systemctl::new(&program).stop().unwrap_err().to_string();
+    assert!(err.contains("systemctl --user stop timely.service failed"), "{}", err);
+    assert!(err.contains("Failed to connect to bus"), "{}", err);
+}
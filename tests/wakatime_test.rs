@@ -0,0 +1,60 @@
+use chrono::{TimeZone, Utc};
+use tempfile::NamedTempFile;
+use timely::db;
+use timely::db::categories as db_categories;
+use timely::query::summary::{build_summary, GroupBy};
+use timely::web::wakatime::{record_heartbeats, WakaHeartbeat};
+
+const VSCODE_UA: &str = "wakatime/v1.73.1 (linux-6.5.0) go1.21.1 vscode/1.85.1 vscode-wakatime/24.4.0";
+
+fn setup_db() -> rusqlite::Connection {
+    let tmp = NamedTempFile::new().unwrap();
+    let conn = db::open_db(tmp.path()).unwrap();
+    db_categories::seed_builtin_categories(&conn).unwrap();
+    conn
+}
+
+fn hb(offset_secs: f64, entity: &str, project: &str, language: &str) -> WakaHeartbeat {
+    let base = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap().timestamp() as f64;
+    WakaHeartbeat {
+        entity: entity.to_string(),
+        entity_type: Some("file".into()),
+        time: base + offset_secs,
+        project: Some(project.to_string()),
+        branch: Some("main".to_string()),
+        language: Some(language.to_string()),
+        user_agent: None,
+    }
+}
+
+#[test]
+fn test_heartbeats_summarize_by_project_and_language() {
+    let conn = setup_db();
+    // Bulk flushes can arrive out of order
+    let heartbeats = vec![
+        hb(120.0, "/src/timely/src/main.rs", "timely", "Rust"),
+        hb(0.0, "/src/timely/src/main.rs", "timely", "Rust"),
+        hb(240.0, "/src/timely/src/main.rs", "timely", "Rust"),
+        hb(300.0, "/src/site/index.ts", "site", "TypeScript"),
+        hb(360.0, "/src/site/index.ts", "site", "TypeScript"),
+    ];
+    let ids = record_heartbeats(&conn, &heartbeats, Some(VSCODE_UA)).unwrap();
+    assert_eq!(ids[0], ids[1]);
+    assert_ne!(ids[2], ids[3]);
+
+    let from = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+    let to = Utc.with_ymd_and_hms(2025, 3, 2, 0, 0, 0).unwrap();
+
+    let by_project = build_summary(&conn, &from, &to, GroupBy::Project, false).unwrap();
+    let projects: Vec<(&str, f64)> = by_project.groups.iter().map(|g| (g.label.as_str(), g.seconds)).collect();
+    assert_eq!(projects, vec![("timely", 240.0), ("site", 60.0)]);
+
+    let by_language = build_summary(&conn, &from, &to, GroupBy::Language, false).unwrap();
+    assert_eq!(by_language.groups[0].label, "Rust");
+
+    // Editor mapped through the builtin aliases and rules
+    let by_app = build_summary(&conn, &from, &to, GroupBy::App, false).unwrap();
+    assert_eq!(by_app.groups[0].label, "Visual Studio Code");
+    let by_category = build_summary(&conn, &from, &to, GroupBy::Category, false).unwrap();
+    assert_eq!(by_category.groups[0].label, "work/coding");
+}